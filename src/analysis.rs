@@ -0,0 +1,329 @@
+use crate::command::Command;
+use crate::error::LogoError;
+use crate::lexer::{Span, Token};
+use crate::parser::TitleLine;
+use crate::primitive::Primitive;
+
+// usage and description of each keyword, which the parser handles itself
+const KEYWORDS: &[(&str, &str, &str)] = &[
+    (
+        "repeat",
+        "repeat count list",
+        "Runs a list of instructions count times.",
+    ),
+    (
+        "make",
+        "make name value",
+        "Sets a variable, creating a global variable if there is none.",
+    ),
+    (
+        "to",
+        "to name :input ... [:optional value] ... [:rest] arity",
+        "Declares a procedure, up to the following `end`.",
+    ),
+    ("end", "end", "Ends the declaration of a procedure."),
+    (
+        "output",
+        "output value",
+        "Stops the procedure being run and outputs a value.",
+    ),
+    (
+        "op",
+        "op value",
+        "Stops the procedure being run and outputs a value.",
+    ),
+    ("stop", "stop", "Stops the procedure being run."),
+    (
+        "if",
+        "if condition list",
+        "Runs a list if the condition is true.",
+    ),
+    (
+        "ifelse",
+        "ifelse condition list1 list2",
+        "Runs the first list if the condition is true and the second otherwise.",
+    ),
+];
+
+/// A procedure declared with `to`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Definition {
+    pub name: String,
+    /// The required inputs.
+    pub params: Vec<String>,
+    /// The optional inputs as written, e.g. `[:size 50]`.
+    pub optional: Vec<String>,
+    pub rest: Option<String>,
+    /// The number of inputs taken by unparenthesized calls, if it is written on
+    /// the title line.
+    pub default_arity: Option<usize>,
+    /// Where the name of the procedure is.
+    pub span: Span,
+    /// Where the declaration is, from `to` to its `end`, or to the end of the
+    /// program if it has no `end`.
+    pub range: Span,
+}
+
+impl Definition {
+    /// Returns the title line of the declaration, e.g. `to square :size`.
+    pub fn title(&self) -> String {
+        let mut title = format!("to {}", self.name);
+        for param in self.params.iter() {
+            title.push_str(&format!(" :{}", param));
+        }
+        for optional in self.optional.iter() {
+            title.push_str(&format!(" {}", optional));
+        }
+        if let Some(rest) = &self.rest {
+            title.push_str(&format!(" [:{}]", rest));
+        }
+        if let Some(arity) = self.default_arity {
+            title.push_str(&format!(" {}", arity));
+        }
+        title
+    }
+}
+
+/// A use of a name: a procedure call, a keyword, or a quoted word naming a
+/// procedure declared in the same program, as in `map "double [1 2]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    pub name: String,
+    pub span: Span,
+}
+
+/// The procedures declared and the names used in a program, and the errors that
+/// keep it from being parsed, see `analyze_program`.
+#[derive(Debug, Default)]
+pub struct Analysis {
+    pub definitions: Vec<Definition>,
+    pub references: Vec<Reference>,
+    pub errors: Vec<LogoError>,
+}
+
+impl Analysis {
+    /// Returns the name declared or used at the byte offset `offset`.
+    pub fn name_at(&self, offset: usize) -> Option<&str> {
+        let contains = |span: &Span| span.start <= offset && offset <= span.end;
+        let definition = self.definitions.iter().find(|def| contains(&def.span));
+        let reference = self.references.iter().find(|re| contains(&re.span));
+        definition
+            .map(|def| def.name.as_str())
+            .or_else(|| reference.map(|re| re.name.as_str()))
+    }
+
+    /// Returns the declaration of the procedure `name`, the last one if it is
+    /// declared several times.
+    pub fn definition(&self, name: &str) -> Option<&Definition> {
+        self.definitions.iter().rev().find(|def| def.name == name)
+    }
+
+    /// Returns the uses of `name`, in the order they appear.
+    pub fn references_to<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Reference> {
+        self.references.iter().filter(move |re| re.name == name)
+    }
+}
+
+/// Finds the procedure declarations and the names used in `tokens`, given the
+/// source they were lexed from and the span of every token. Declarations are
+/// found the same way the parser finds the arities of procedures, so this works
+/// on programs that don't parse.
+pub fn analyze(source: &str, tokens: &[Token], spans: &[Span]) -> Analysis {
+    let mut analysis = Analysis::default();
+
+    let mut i = 0;
+    while i < tokens.len() {
+        if let (Token::To, Some(Token::Identifier { literal })) = (&tokens[i], tokens.get(i + 1)) {
+            let title = TitleLine::scan(&tokens[i + 2..]);
+            // optional inputs are kept as written, with their default value
+            let optional = title
+                .optional
+                .iter()
+                .map(|range| {
+                    let (first, last) = (spans[i + 2 + range.start], spans[i + 1 + range.end]);
+                    source[first.start..last.end].to_string()
+                })
+                .collect();
+            let end = tokens[i..]
+                .iter()
+                .position(|tok| *tok == Token::End)
+                .map_or(tokens.len() - 1, |position| i + position);
+            analysis.definitions.push(Definition {
+                name: literal.to_string(),
+                params: title.params,
+                optional,
+                rest: title.rest,
+                default_arity: title.default_arity,
+                span: spans[i + 1],
+                range: spans[i].to(&spans[end]),
+            });
+            // the name after `to` is a declaration rather than a use
+            analysis.references.push(Reference {
+                name: String::from("to"),
+                span: spans[i],
+            });
+            i += 2;
+            continue;
+        }
+
+        match &tokens[i] {
+            Token::Identifier { literal } => analysis.references.push(Reference {
+                name: literal.to_string(),
+                span: spans[i],
+            }),
+            tok if is_keyword(tok.to_string()) => analysis.references.push(Reference {
+                name: tok.to_string().to_string(),
+                span: spans[i],
+            }),
+            _ => (),
+        }
+        i += 1;
+    }
+
+    // quoted words are only names of procedures if such a procedure is declared
+    for (tok, span) in tokens.iter().zip(spans) {
+        if let Token::Word { literal } = tok {
+            if analysis.definition(literal).is_some() {
+                let span = Span {
+                    start: span.start + 1,
+                    column: span.column + 1,
+                    ..*span
+                };
+                analysis.references.push(Reference {
+                    name: literal.to_string(),
+                    span,
+                });
+            }
+        }
+    }
+    analysis.references.sort_by_key(|re| re.span.start);
+
+    analysis
+}
+
+/// Returns the usage and a description of a primitive, turtle command or
+/// keyword, e.g. `forward distance` and "Moves the turtle forward.". The name
+/// may be written in any case.
+pub fn describe(name: &str) -> Option<(String, &'static str)> {
+    let usage = |inputs: &[&str]| {
+        let mut usage = name.to_string();
+        for input in inputs {
+            usage.push(' ');
+            usage.push_str(input);
+        }
+        usage
+    };
+
+    let key = name.to_lowercase();
+    if let Some(command) = Command::from_string(&key) {
+        Some((usage(command.inputs()), command.doc()))
+    } else if let Some(primitive) = Primitive::from_string(&key) {
+        Some((usage(primitive.inputs()), primitive.doc()))
+    } else {
+        let keyword = KEYWORDS.iter().find(|(keyword, _, _)| *keyword == key);
+        keyword.map(|(_, usage, doc)| (usage.to_string(), *doc))
+    }
+}
+
+/// Returns whether `name` is a keyword, such as `repeat` or `to`, rather than a
+/// procedure.
+pub fn is_keyword(name: &str) -> bool {
+    KEYWORDS.iter().any(|(keyword, _, _)| *keyword == name)
+}
+
+/// Returns the names of every primitive, turtle command and keyword.
+pub fn builtin_names() -> Vec<&'static str> {
+    let keywords = KEYWORDS.iter().map(|(name, _, _)| *name);
+    let mut names = Command::names();
+    names.extend(Primitive::names());
+    names.extend(keywords);
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+
+    fn analyze_source(source: &str) -> Analysis {
+        let mut lexer = Lexer::new(source);
+        let tokens = lexer.collect_tokens().unwrap();
+        analyze(source, &tokens, lexer.spans())
+    }
+
+    #[test]
+    fn analyze_definition_test() {
+        let source = "to square :size\nrepeat 4 [fd :size rt 90]\nend\nsquare 10\n";
+        let analysis = analyze_source(source);
+
+        assert_eq!(analysis.definitions.len(), 1);
+        let square = &analysis.definitions[0];
+        assert_eq!(square.title(), "to square :size");
+        assert_eq!((square.span.line, square.span.column), (1, 4));
+        assert_eq!(&source[square.range.start..square.range.end], &source[..45]);
+
+        let names: Vec<_> = analysis
+            .references
+            .iter()
+            .map(|re| re.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["to", "repeat", "fd", "rt", "end", "square"]);
+        let calls: Vec<_> = analysis.references_to("square").collect();
+        assert_eq!((calls[0].span.line, calls[0].span.column), (4, 1));
+
+        assert_eq!(analysis.name_at(5), Some("square"));
+        assert_eq!(analysis.name_at(source.len() - 8), Some("square"));
+        assert_eq!(analysis.name_at(12), None);
+    }
+
+    #[test]
+    fn analyze_quoted_name_test() {
+        // quoted words are references only if they name a declared procedure
+        let analysis =
+            analyze_source("to double :x\noutput :x * 2\nend\nshow map \"double [1 2]\nmake \"x 1");
+
+        let words: Vec<_> = analysis.references_to("double").collect();
+        assert_eq!(words.len(), 1);
+        assert_eq!((words[0].span.line, words[0].span.column), (4, 11));
+        assert_eq!(analysis.references_to("x").count(), 0);
+
+        // declarations without an `end` still count
+        let analysis = analyze_source("to broken :a\nfd :a");
+        assert_eq!(analysis.definitions[0].params, vec!["a"]);
+    }
+
+    #[test]
+    fn analyze_optional_inputs_test() {
+        let analysis = analyze_source("to poly :sides [:size   [10 20]] [:rest] 2\nend");
+        let poly = &analysis.definitions[0];
+        assert_eq!(poly.optional, vec!["[:size   [10 20]]"]);
+        assert_eq!(poly.title(), "to poly :sides [:size   [10 20]] [:rest] 2");
+    }
+
+    #[test]
+    fn describe_test() {
+        let (usage, doc) = describe("fd").unwrap();
+        assert_eq!(usage, "fd distance");
+        assert_eq!(doc, "Moves the turtle forward.");
+        assert_eq!(
+            describe("ifelse").unwrap().0,
+            "ifelse condition list1 list2"
+        );
+        assert_eq!(describe("FD").unwrap().0, "FD distance");
+        assert_eq!(describe("nothing"), None);
+
+        // every name can be described, and usages list as many inputs as the
+        // primitive takes
+        for name in builtin_names() {
+            assert!(describe(name).is_some(), "{} has no description", name);
+        }
+        for name in Command::names() {
+            let command = Command::from_string(name).unwrap();
+            assert_eq!(command.inputs().len(), command.arity(), "{}", name);
+        }
+        for name in Primitive::names() {
+            let primitive = Primitive::from_string(name).unwrap();
+            assert_eq!(primitive.inputs().len(), primitive.arity(), "{}", name);
+        }
+    }
+}
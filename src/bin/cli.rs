@@ -1,17 +1,26 @@
+use std::cell::RefCell;
+use std::env;
 use std::fs;
+use std::rc::Rc;
 
-use clap::{App, Arg};
+use clap::{App, Arg, ArgMatches, SubCommand};
 use rustyline::error::ReadlineError;
 use rustyline::Editor;
+use serde_json::json;
 use turtle::Turtle;
 
-use logo::{Interpreter, Instruction, Command};
+use logo::{
+    Command, Dialect, Instruction, Interpreter, Locale, LogoError, Pause, Trace, TraceEvent,
+};
 
-fn run_instructions(instructions: &Vec<Instruction>, turtle: &mut Turtle) {
+fn run_instructions(instructions: &[Instruction], turtle: &mut Turtle) {
     for instruction in instructions.iter() {
-        let args: Vec<_> = instruction.args.iter().map(|val| {
-            *val as f64
-        }).collect();
+        // the evaluator only passes numbers to turtle commands
+        let args: Vec<_> = instruction
+            .args
+            .iter()
+            .map(|val| val.number().unwrap_or_default() as f64)
+            .collect();
 
         use Command::*;
         match instruction.command {
@@ -38,12 +47,151 @@ fn run_instructions(instructions: &Vec<Instruction>, turtle: &mut Turtle) {
                 turtle
                     .drawing_mut()
                     .set_background_color([args[0], args[1], args[2]]),
-            Show => println!("{}", args[0]),
+            Show => println!("{}", instruction.args[0]),
             Exit => std::process::exit(0),
         }
     }
 }
 
+/// Reads and runs instructions while a program is paused, until `continue` is run
+/// or the input ends. The prompt shows the name of the paused procedure.
+fn pause_shell(pause: &mut Pause, turtle: &mut Turtle, locale: Locale) {
+    run_instructions(&pause.take_instructions(), turtle);
+
+    let prompt = format!("{}? ", pause.procedure().unwrap_or(""));
+    let mut rl = Editor::<()>::new();
+    while !pause.resumed() {
+        match rl.readline(&prompt) {
+            Ok(line) => {
+                rl.add_history_entry(line.as_str());
+                match pause.run(&line) {
+                    Ok(i) => run_instructions(&i, turtle),
+                    Err(e) => eprintln!("{}", e.message(locale)),
+                }
+            }
+            Err(ReadlineError::Interrupted) => eprintln!("CTRL-C"),
+            Err(_) => break,
+        }
+    }
+}
+
+/// Prints trace lines as the program runs. Before each statement of a stepped
+/// procedure, the statement is printed and the user has to press return.
+fn trace_line(trace: &mut Trace, turtle: &mut Turtle) {
+    run_instructions(&trace.take_instructions(), turtle);
+    match trace.event() {
+        TraceEvent::Step { .. } => {
+            // any input continues, as does the end of the input
+            let mut rl = Editor::<()>::new();
+            let _ = rl.readline(&format!("{} >>> ", trace.event()));
+        }
+        event => println!("{}", event),
+    }
+}
+
+/// Formats every given file as canonical Logo source, printing the result or
+/// writing it back to the file.
+fn format_files(matches: &ArgMatches) {
+    let mut failed = false;
+    for file in matches.values_of("FILE").into_iter().flatten() {
+        let source = match fs::read_to_string(file) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("Error reading file {}: {}", file, e);
+                failed = true;
+                continue;
+            }
+        };
+
+        match logo::format_program(&source) {
+            Ok(formatted) if matches.is_present("write") => {
+                if let Err(e) = fs::write(file, formatted) {
+                    eprintln!("Error writing file {}: {}", file, e);
+                    failed = true;
+                }
+            }
+            Ok(formatted) => print!("{}", formatted),
+            Err(e) => {
+                eprintln!("{}: {}", file, e);
+                failed = true;
+            }
+        }
+    }
+
+    std::process::exit(if failed { 1 } else { 0 });
+}
+
+/// Checks every given file for likely mistakes without running it. Each warning
+/// or syntax error is printed as `file:line:column: severity[code]: message`, or
+/// as an object of a JSON array with `--json`. Exits with status 1 if anything was
+/// found.
+fn check_files(matches: &ArgMatches) {
+    let mut failed = false;
+    let mut found = Vec::new();
+    for file in matches.values_of("FILE").into_iter().flatten() {
+        let source = match fs::read_to_string(file) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("Error reading file {}: {}", file, e);
+                failed = true;
+                continue;
+            }
+        };
+
+        match logo::check_program(&source) {
+            Ok(warnings) => {
+                for warning in warnings {
+                    let code = warning.lint.code();
+                    found.push((
+                        file,
+                        warning.span,
+                        "warning",
+                        code,
+                        warning.lint.to_string(),
+                    ));
+                }
+            }
+            Err(errors) => {
+                for e in errors {
+                    let message = match &e {
+                        // the position is printed separately
+                        LogoError::Parse { error, span: _ } => error.to_string(),
+                        _ => e.to_string(),
+                    };
+                    found.push((file, e.span(), "error", "syntax-error", message));
+                }
+            }
+        }
+    }
+
+    if matches.is_present("json") {
+        let found: Vec<_> = found
+            .iter()
+            .map(|(file, span, severity, code, message)| {
+                json!({
+                    "file": file,
+                    "line": span.map(|span| span.line),
+                    "column": span.map(|span| span.column),
+                    "severity": severity,
+                    "code": code,
+                    "message": message,
+                })
+            })
+            .collect();
+        println!("{}", json!(found));
+    } else {
+        for (file, span, severity, code, message) in found.iter() {
+            let (line, column) = span.map_or((0, 0), |span| (span.line, span.column));
+            println!(
+                "{}:{}:{}: {}[{}]: {}",
+                file, line, column, severity, code, message
+            );
+        }
+    }
+
+    std::process::exit(if failed || !found.is_empty() { 1 } else { 0 });
+}
+
 fn main() {
     // clap CLI app setup
     let matches = App::new("Logo")
@@ -63,30 +211,144 @@ fn main() {
                 .help("do not create turtle or window or startup")
                 .takes_value(false),
         )
+        .arg(
+            Arg::with_name("startup")
+                .short("s")
+                .long("startup")
+                .value_name("FILE")
+                .help("load a file, such as a workspace written by save, before anything else")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("path")
+                .short("I")
+                .long("path")
+                .value_name("DIR")
+                .help("add a directory to the module search path, searched before LOGO_PATH")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("locale")
+                .long("locale")
+                .value_name("LANGUAGE")
+                .help("also accept primitive names in a language, and report errors in it")
+                .possible_values(Locale::names())
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("dialect")
+                .long("dialect")
+                .value_name("DIALECT")
+                .help("read programs written for another dialect of Logo, such as ucblogo")
+                .possible_values(Dialect::names())
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("case-sensitive")
+                .long("case-sensitive")
+                .help("only match procedure and variable names spelled with the same case")
+                .takes_value(false),
+        )
+        .subcommand(
+            SubCommand::with_name("fmt")
+                .about("Formats Logo source files")
+                .arg(
+                    Arg::with_name("FILE")
+                        .help("Files to format")
+                        .required(true)
+                        .multiple(true),
+                )
+                .arg(
+                    Arg::with_name("write")
+                        .short("w")
+                        .long("write")
+                        .help("write the formatted source back to the files")
+                        .takes_value(false),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("check")
+                .about("Checks Logo source files for likely mistakes without running them")
+                .arg(
+                    Arg::with_name("FILE")
+                        .help("Files to check")
+                        .required(true)
+                        .multiple(true),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("print the warnings as a JSON array")
+                        .takes_value(false),
+                ),
+        )
         .get_matches();
 
-    // create the Interpreter
+    if let Some(matches) = matches.subcommand_matches("fmt") {
+        format_files(matches);
+    }
+    if let Some(matches) = matches.subcommand_matches("check") {
+        check_files(matches);
+    }
+
+    // create the Interpreter, procedures are often redefined while experimenting
+    // in the REPL so let the user know when it happens
     let mut interpreter = Interpreter::new();
+    interpreter.set_redefinition_warnings(true);
+    interpreter.set_case_sensitive(matches.is_present("case-sensitive"));
+    let locale = matches
+        .value_of("locale")
+        .and_then(Locale::from_string)
+        .unwrap_or_default();
+    interpreter.set_locale(locale);
+    if let Some(dialect) = matches.value_of("dialect").and_then(Dialect::from_string) {
+        interpreter.set_dialect(dialect);
+    }
+
+    // modules are searched for in the directories given with --path, then the
+    // ones listed in the LOGO_PATH environment variable
+    for dir in matches.values_of("path").into_iter().flatten() {
+        interpreter.add_search_path(dir);
+    }
+    if let Some(paths) = env::var_os("LOGO_PATH") {
+        for dir in env::split_paths(&paths) {
+            interpreter.add_search_path(&dir.to_string_lossy());
+        }
+    }
 
-    // create the turtle (also creates the window)
-    let mut turtle = Turtle::new();
+    // create the turtle (also creates the window), it is shared with the pause and
+    // trace handlers which draw while a program is paused or being stepped through
+    let turtle = Rc::new(RefCell::new(Turtle::new()));
+    let pause_turtle = Rc::clone(&turtle);
+    interpreter
+        .set_pause_handler(move |pause| pause_shell(pause, &mut pause_turtle.borrow_mut(), locale));
+    let trace_turtle = Rc::clone(&turtle);
+    interpreter.set_trace_handler(move |trace| trace_line(trace, &mut trace_turtle.borrow_mut()));
+
+    // load the startup file before running the script or the shell
+    if let Some(file) = matches.value_of("startup") {
+        match interpreter.load_file(file) {
+            Ok(i) => run_instructions(&i, &mut turtle.borrow_mut()),
+            Err(e) => eprintln!("{}", e.message(locale)),
+        }
+    }
 
     // if a script argument was passed, run the script
     if let Some(file) = matches.value_of("SCRIPT") {
         // read in the file
-        let instructions = interpreter.run_program(
-            match &fs::read_to_string(file) {
-                Ok(input) => input,
-                Err(e) => {
-                    eprintln!("Error reading file: {}", e);
-                    std::process::exit(1);
-                }
-            },
-        );
+        let instructions = interpreter.run_program(match &fs::read_to_string(file) {
+            Ok(input) => input,
+            Err(e) => {
+                eprintln!("Error reading file: {}", e);
+                std::process::exit(1);
+            }
+        });
 
         match instructions {
-            Ok(i) => run_instructions(&i, &mut turtle),
-            Err(e) => eprintln!("{}", e)
+            Ok(i) => run_instructions(&i, &mut turtle.borrow_mut()),
+            Err(e) => eprintln!("{}", e.message(locale)),
         };
     }
 
@@ -98,8 +360,8 @@ fn main() {
             Ok(line) => {
                 rl.add_history_entry(line.as_str());
                 match interpreter.run_program(&line) {
-                    Ok(i) => run_instructions(&i, &mut turtle),
-                    Err(e) => eprintln!("{}", e),
+                    Ok(i) => run_instructions(&i, &mut turtle.borrow_mut()),
+                    Err(e) => eprintln!("{}", e.message(locale)),
                 }
             }
             Err(ReadlineError::Interrupted) => {
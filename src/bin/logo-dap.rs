@@ -0,0 +1,444 @@
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::process;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use serde_json::{json, Value as Json};
+
+use logo::{
+    Breakpoint, Command, Debugger, Instruction, Interpreter, Resume, StopReason, Stopped, Value,
+};
+
+// a Logo program only has one thread
+const THREAD_ID: i64 = 1;
+
+// variable references of the scopes shown while stopped, the local scopes of
+// procedure calls are numbered from LOCALS by the depth of the call
+const GLOBALS: i64 = 1;
+const LOCALS: i64 = 2;
+
+/// Writes Debug Adapter Protocol messages to stdout. It is shared with the thread
+/// reading requests, which answers some of them while the program runs.
+struct Output {
+    seq: i64,
+}
+
+impl Output {
+    fn send(&mut self, mut message: Json) {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        let body = message.to_string();
+
+        // there is nobody left to tell if the client has gone away
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        let _ = write!(stdout, "Content-Length: {}\r\n\r\n{}", body.len(), body);
+        let _ = stdout.flush();
+    }
+
+    fn respond(&mut self, request: &Json, body: Json) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": true,
+            "body": body,
+        }));
+    }
+
+    fn fail(&mut self, request: &Json, message: &str) {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "command": request["command"],
+            "success": false,
+            "message": message,
+        }));
+    }
+
+    fn event(&mut self, event: &str, body: Json) {
+        self.send(json!({ "type": "event", "event": event, "body": body }));
+    }
+}
+
+/// Reads one message, returning None at the end of the input.
+fn read_message(input: &mut impl BufRead) -> Option<Json> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            length = value.trim().parse().ok();
+        }
+    }
+
+    let mut body = vec![0; length?];
+    input.read_exact(&mut body).ok()?;
+    serde_json::from_slice(&body).ok()
+}
+
+/// Reads requests on another thread, so that the program can be paused while it
+/// runs. `threads` and `pause` are answered right away, every other request is
+/// sent on to the session.
+fn read_requests(sender: Sender<Json>, output: Arc<Mutex<Output>>, interrupt: Arc<AtomicBool>) {
+    thread::spawn(move || {
+        let stdin = io::stdin();
+        let mut input = stdin.lock();
+        while let Some(request) = read_message(&mut input) {
+            match request["command"].as_str() {
+                Some("threads") => output.lock().unwrap().respond(
+                    &request,
+                    json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] }),
+                ),
+                Some("pause") => {
+                    interrupt.store(true, Ordering::SeqCst);
+                    output.lock().unwrap().respond(&request, json!({}));
+                }
+                _ => {
+                    if sender.send(request).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// A debugging session of one program.
+struct Session {
+    output: Arc<Mutex<Output>>,
+    requests: Receiver<Json>,
+    program: RefCell<String>,
+    breakpoints: RefCell<Vec<Breakpoint>>,
+}
+
+impl Session {
+    /// Returns the next request, or exits once the client has gone away.
+    fn next_request(&self) -> Json {
+        match self.requests.recv() {
+            Ok(request) => request,
+            Err(_) => process::exit(0),
+        }
+    }
+
+    fn respond(&self, request: &Json, body: Json) {
+        self.output.lock().unwrap().respond(request, body);
+    }
+
+    fn fail(&self, request: &Json, message: &str) {
+        self.output.lock().unwrap().fail(request, message);
+    }
+
+    fn event(&self, event: &str, body: Json) {
+        self.output.lock().unwrap().event(event, body);
+    }
+
+    /// Handles the requests that can be made whether or not the program is
+    /// running. Returns false if `request` isn't one of them.
+    fn configure(&self, request: &Json) -> bool {
+        let arguments = &request["arguments"];
+        match request["command"].as_str().unwrap_or_default() {
+            // breakpoints are only set in the program being debugged
+            "setBreakpoints" => {
+                let lines: Vec<_> = arguments["breakpoints"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|breakpoint| breakpoint["line"].as_u64())
+                    .collect();
+                let path = arguments["source"]["path"].as_str().unwrap_or_default();
+                let verified = same_file(path, &self.program.borrow());
+                self.breakpoints
+                    .borrow_mut()
+                    .retain(|b| !matches!(b, Breakpoint::Line(_)));
+                if verified {
+                    let mut breakpoints = self.breakpoints.borrow_mut();
+                    breakpoints.extend(lines.iter().map(|&line| Breakpoint::Line(line as usize)));
+                }
+                let results: Vec<_> = lines
+                    .iter()
+                    .map(|line| json!({ "verified": verified, "line": line }))
+                    .collect();
+                self.respond(request, json!({ "breakpoints": results }));
+            }
+            "setFunctionBreakpoints" => {
+                let names: Vec<_> = arguments["breakpoints"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|breakpoint| breakpoint["name"].as_str())
+                    .collect();
+                let mut breakpoints = self.breakpoints.borrow_mut();
+                breakpoints.retain(|b| !matches!(b, Breakpoint::Procedure(_)));
+                breakpoints.extend(
+                    names
+                        .iter()
+                        .map(|name| Breakpoint::Procedure(name.to_string())),
+                );
+                let results: Vec<_> = names.iter().map(|_| json!({ "verified": true })).collect();
+                self.respond(request, json!({ "breakpoints": results }));
+            }
+            "setExceptionBreakpoints" => self.respond(request, json!({})),
+            "disconnect" => {
+                self.respond(request, json!({}));
+                process::exit(0);
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Sends the output of `show` instructions to the client. There is no turtle,
+    /// so the other instructions are dropped.
+    fn flush(&self, instructions: &[Instruction]) {
+        for instruction in instructions.iter() {
+            if instruction.command == Command::Show {
+                let output = format!("{}\n", instruction.args[0]);
+                self.event("output", json!({ "category": "stdout", "output": output }));
+            }
+        }
+    }
+
+    /// The debug handler, which answers requests about the stopped program until
+    /// the client resumes it.
+    fn stopped(&self, stopped: &mut Stopped) -> Resume {
+        self.flush(&stopped.take_instructions());
+        let reason = match stopped.reason() {
+            StopReason::Breakpoint => "breakpoint",
+            StopReason::Step => "step",
+            StopReason::Pause => "pause",
+        };
+        self.event(
+            "stopped",
+            json!({ "reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true }),
+        );
+
+        loop {
+            let request = self.next_request();
+            let arguments = &request["arguments"];
+            let resume = match request["command"].as_str().unwrap_or_default() {
+                "continue" => Resume::Continue,
+                "next" => Resume::StepOver,
+                "stepIn" => Resume::StepInto,
+                "stepOut" => Resume::StepOut,
+                "stackTrace" => {
+                    let frames = self.stack_frames(stopped);
+                    let total = frames.len();
+                    self.respond(
+                        &request,
+                        json!({ "stackFrames": frames, "totalFrames": total }),
+                    );
+                    continue;
+                }
+                "scopes" => {
+                    // frame 0 is the top level, which only sees global variables
+                    let frame = arguments["frameId"].as_i64().unwrap_or_default();
+                    let mut scopes = Vec::new();
+                    if frame > 0 && stopped.procedure_locals(frame as usize).is_some() {
+                        scopes.push(json!({
+                            "name": "Locals",
+                            "variablesReference": LOCALS + frame - 1,
+                            "expensive": false,
+                        }));
+                    }
+                    scopes.push(json!({
+                        "name": "Globals",
+                        "variablesReference": GLOBALS,
+                        "expensive": false,
+                    }));
+                    self.respond(&request, json!({ "scopes": scopes }));
+                    continue;
+                }
+                "variables" => {
+                    let reference = arguments["variablesReference"].as_i64().unwrap_or_default();
+                    let scope = if reference == GLOBALS {
+                        stopped.globals()
+                    } else {
+                        let depth = (reference - LOCALS + 1).max(0) as usize;
+                        stopped.procedure_locals(depth).unwrap_or_default()
+                    };
+                    let variables: Vec<_> = scope.into_iter().map(variable).collect();
+                    self.respond(&request, json!({ "variables": variables }));
+                    continue;
+                }
+                "evaluate" => {
+                    let expression = arguments["expression"].as_str().unwrap_or_default();
+                    match stopped.evaluate(expression) {
+                        Ok(value) => {
+                            let result = value.map(|val| val.to_string()).unwrap_or_default();
+                            self.respond(
+                                &request,
+                                json!({ "result": result, "variablesReference": 0 }),
+                            );
+                        }
+                        Err(e) => self.fail(&request, &e.to_string()),
+                    }
+                    self.flush(&stopped.take_instructions());
+                    continue;
+                }
+                _ => {
+                    if self.configure(&request) {
+                        stopped.set_breakpoints(self.breakpoints.borrow().clone());
+                    } else {
+                        self.fail(&request, "not supported while stopped");
+                    }
+                    continue;
+                }
+            };
+
+            self.respond(&request, json!({ "allThreadsContinued": true }));
+            return resume;
+        }
+    }
+
+    /// Returns the stack frames of the stopped program, innermost first. Frames
+    /// are numbered by their depth, the top level being 0.
+    fn stack_frames(&self, stopped: &Stopped) -> Vec<Json> {
+        let program = self.program.borrow();
+        let name = Path::new(program.as_str())
+            .file_name()
+            .map(|name| name.to_string_lossy());
+        let source = json!({ "name": name, "path": *program });
+
+        let mut frames = Vec::new();
+        let mut span = stopped.span();
+        let calls = stopped
+            .call_stack()
+            .iter()
+            .map(|frame| (frame.to_string(), frame.span));
+        let calls: Vec<_> = std::iter::once((String::from("top level"), None))
+            .chain(calls)
+            .collect();
+        for (depth, (name, call_span)) in calls.into_iter().enumerate().rev() {
+            frames.push(json!({
+                "id": depth,
+                "name": name,
+                "source": source,
+                "line": span.map_or(0, |span| span.line),
+                "column": span.map_or(0, |span| span.column),
+            }));
+            span = call_span;
+        }
+        frames
+    }
+}
+
+/// Whether the paths name the same file, however they are written. Paths of
+/// files that can't be found are compared as they are.
+fn same_file(a: &str, b: &str) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Converts a variable into a DAP variable.
+fn variable((name, val): (String, Option<Value>)) -> Json {
+    let value = match val {
+        Some(val) => val.to_string(),
+        None => String::from("(no value)"),
+    };
+    json!({ "name": name, "value": value, "variablesReference": 0 })
+}
+
+/// A Debug Adapter Protocol server for Logo programs, speaking over stdin and
+/// stdout. The client launches a program with `{"program": "path.lgo"}`, and may
+/// ask to stop on entry with `"stopOnEntry": true`.
+fn main() {
+    let output = Arc::new(Mutex::new(Output { seq: 0 }));
+    let (sender, requests) = mpsc::channel();
+    let session = Rc::new(Session {
+        output: Arc::clone(&output),
+        requests,
+        program: RefCell::new(String::new()),
+        breakpoints: RefCell::new(Vec::new()),
+    });
+
+    let handler_session = Rc::clone(&session);
+    let mut debugger = Debugger::new(move |stopped| handler_session.stopped(stopped));
+    read_requests(sender, output, debugger.interrupt_handle());
+
+    // wait for the program and the breakpoints
+    let mut source = None;
+    let mut configured = false;
+    while source.is_none() || !configured {
+        let request = session.next_request();
+        let arguments = &request["arguments"];
+        match request["command"].as_str().unwrap_or_default() {
+            "initialize" => {
+                session.respond(
+                    &request,
+                    json!({
+                        "supportsConfigurationDoneRequest": true,
+                        "supportsFunctionBreakpoints": true,
+                    }),
+                );
+                session.event("initialized", json!({}));
+            }
+            "launch" => {
+                let program = arguments["program"].as_str().unwrap_or_default();
+                match fs::read_to_string(program) {
+                    Ok(contents) => {
+                        source = Some(contents);
+                        *session.program.borrow_mut() = program.to_string();
+                        if arguments["stopOnEntry"].as_bool().unwrap_or_default() {
+                            debugger.stop_on_entry();
+                        }
+                        session.respond(&request, json!({}));
+                    }
+                    Err(e) => session.fail(&request, &format!("Error reading {}: {}", program, e)),
+                }
+            }
+            "configurationDone" => {
+                configured = true;
+                session.respond(&request, json!({}));
+            }
+            _ => {
+                if !session.configure(&request) {
+                    session.fail(&request, "not supported before launch");
+                }
+            }
+        }
+    }
+
+    // modules are imported from the directory of the program
+    let mut interpreter = Interpreter::new();
+    if let Some(dir) = Path::new(session.program.borrow().as_str()).parent() {
+        interpreter.add_search_path(&dir.to_string_lossy());
+    }
+    debugger.set_breakpoints(session.breakpoints.borrow().clone());
+    interpreter.set_debugger(Some(debugger));
+
+    let exit_code = match interpreter.run_program(&source.unwrap_or_default()) {
+        Ok(instructions) => {
+            session.flush(&instructions);
+            0
+        }
+        Err(e) => {
+            let output = format!("{}\n", e);
+            session.event("output", json!({ "category": "stderr", "output": output }));
+            1
+        }
+    };
+    session.event("exited", json!({ "exitCode": exit_code }));
+    session.event("terminated", json!({}));
+
+    loop {
+        let request = session.next_request();
+        if !session.configure(&request) {
+            session.fail(&request, "the program has finished");
+        }
+    }
+}
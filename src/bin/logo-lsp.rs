@@ -0,0 +1,469 @@
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+
+use serde_json::{json, Value as Json};
+
+use logo::{analyze_program, builtin_names, describe, is_keyword, Analysis, LogoError, Span};
+
+// LSP kinds of completion items, symbols and diagnostics
+const COMPLETION_FUNCTION: i64 = 3;
+const COMPLETION_KEYWORD: i64 = 14;
+const SYMBOL_FUNCTION: i64 = 12;
+const SEVERITY_ERROR: i64 = 1;
+
+/// Reads one message, returning None at the end of the input.
+fn read_message(input: &mut impl BufRead) -> Option<Json> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            length = value.trim().parse().ok();
+        }
+    }
+
+    let mut body = vec![0; length?];
+    input.read_exact(&mut body).ok()?;
+    serde_json::from_slice(&body).ok()
+}
+
+fn write_message(output: &mut impl Write, message: &Json) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+/// Converts a byte offset in `source` into an LSP position, whose characters are
+/// counted in UTF-16 code units.
+fn position(source: &str, offset: usize) -> Json {
+    let before = &source[..offset.min(source.len())];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let character: usize = before[line_start..].chars().map(char::len_utf16).sum();
+    json!({ "line": before.matches('\n').count(), "character": character })
+}
+
+/// Converts an LSP position into a byte offset in `source`.
+fn offset(source: &str, position: &Json) -> usize {
+    let line = position["line"].as_u64().unwrap_or_default() as usize;
+    let character = position["character"].as_u64().unwrap_or_default() as usize;
+    let line_start: usize = source.split_inclusive('\n').take(line).map(str::len).sum();
+
+    let mut units = 0;
+    for (i, c) in source[line_start..].char_indices() {
+        if units >= character || c == '\n' {
+            return line_start + i;
+        }
+        units += c.len_utf16();
+    }
+    source.len()
+}
+
+fn range(source: &str, span: &Span) -> Json {
+    json!({ "start": position(source, span.start), "end": position(source, span.end) })
+}
+
+/// Converts an error into an LSP diagnostic. Errors without a span are shown at
+/// the start of the document.
+fn diagnostic(source: &str, error: &LogoError) -> Json {
+    let message = match error {
+        // the position is already shown by the editor
+        LogoError::Parse { error, span: _ } => error.to_string(),
+        _ => error.to_string(),
+    };
+    json!({
+        "range": range(source, &error.span().unwrap_or_default()),
+        "severity": SEVERITY_ERROR,
+        "code": error.code(),
+        "source": "logo",
+        "message": message,
+    })
+}
+
+/// The documents open in the editor, by URI.
+#[derive(Default)]
+struct Server {
+    documents: BTreeMap<String, String>,
+}
+
+impl Server {
+    /// Handles a request or notification from the client, returning the messages
+    /// to send back.
+    fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let params = &message["params"];
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let method = message["method"].as_str().unwrap_or_default();
+
+        let result = match method {
+            "initialize" => json!({
+                "capabilities": {
+                    // documents are sent whole on every change
+                    "textDocumentSync": { "openClose": true, "change": 1, "save": true },
+                    "definitionProvider": true,
+                    "referencesProvider": true,
+                    "hoverProvider": true,
+                    "completionProvider": {},
+                    "documentSymbolProvider": true,
+                },
+                "serverInfo": { "name": "logo-lsp" },
+            }),
+            "shutdown" => Json::Null,
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.documents.insert(uri.to_string(), text.to_string());
+                return vec![self.diagnostics(uri)];
+            }
+            "textDocument/didChange" => {
+                let changes = params["contentChanges"].as_array();
+                if let Some(text) = changes.and_then(|changes| changes.last()) {
+                    let text = text["text"].as_str().unwrap_or_default();
+                    self.documents.insert(uri.to_string(), text.to_string());
+                }
+                return vec![];
+            }
+            "textDocument/didSave" => return vec![self.diagnostics(uri)],
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                return vec![publish_diagnostics(uri, vec![])];
+            }
+            "textDocument/definition" => self.definition(uri, &params["position"]),
+            "textDocument/references" => {
+                let declaration = params["context"]["includeDeclaration"].as_bool();
+                self.references(uri, &params["position"], declaration.unwrap_or_default())
+            }
+            "textDocument/hover" => self.hover(uri, &params["position"]),
+            "textDocument/completion" => self.completion(uri),
+            "textDocument/documentSymbol" => self.symbols(uri),
+            _ => {
+                // other notifications are ignored
+                if message["id"].is_null() {
+                    return vec![];
+                }
+                return vec![json!({
+                    "jsonrpc": "2.0",
+                    "id": message["id"],
+                    "error": { "code": -32601, "message": format!("unknown method {}", method) },
+                })];
+            }
+        };
+
+        if message["id"].is_null() {
+            return vec![];
+        }
+        vec![json!({ "jsonrpc": "2.0", "id": message["id"], "result": result })]
+    }
+
+    fn source(&self, uri: &str) -> &str {
+        self.documents.get(uri).map_or("", |source| source.as_str())
+    }
+
+    fn diagnostics(&self, uri: &str) -> Json {
+        let source = self.source(uri);
+        let analysis = analyze_program(source);
+        let diagnostics = analysis
+            .errors
+            .iter()
+            .map(|e| diagnostic(source, e))
+            .collect();
+        publish_diagnostics(uri, diagnostics)
+    }
+
+    /// Returns the name declared or used at `position` in the document `uri`.
+    fn name_at(&self, uri: &str, position: &Json) -> Option<String> {
+        let source = self.source(uri);
+        let analysis = analyze_program(source);
+        analysis.name_at(offset(source, position)).map(String::from)
+    }
+
+    /// Returns the URI, source and analysis of every open document, starting with
+    /// `uri` so that its declarations win.
+    fn analyses(&self, uri: &str) -> Vec<(&str, &str, Analysis)> {
+        let mut analyses: Vec<_> = self
+            .documents
+            .iter()
+            .map(|(uri, source)| (uri.as_str(), source.as_str(), analyze_program(source)))
+            .collect();
+        analyses.sort_by_key(|(other, _, _)| *other != uri);
+        analyses
+    }
+
+    fn definition(&self, uri: &str, position: &Json) -> Json {
+        let name = match self.name_at(uri, position) {
+            Some(name) => name,
+            None => return Json::Null,
+        };
+        for (uri, source, analysis) in self.analyses(uri).iter() {
+            if let Some(definition) = analysis.definition(&name) {
+                return json!({ "uri": uri, "range": range(source, &definition.span) });
+            }
+        }
+        Json::Null
+    }
+
+    fn references(&self, uri: &str, position: &Json, declaration: bool) -> Json {
+        let name = match self.name_at(uri, position) {
+            Some(name) => name,
+            None => return json!([]),
+        };
+        let mut locations = Vec::new();
+        for (uri, source, analysis) in self.analyses(uri).iter() {
+            if declaration {
+                for definition in analysis.definitions.iter().filter(|def| def.name == name) {
+                    locations.push(json!({ "uri": uri, "range": range(source, &definition.span) }));
+                }
+            }
+            for reference in analysis.references_to(&name) {
+                locations.push(json!({ "uri": uri, "range": range(source, &reference.span) }));
+            }
+        }
+        json!(locations)
+    }
+
+    fn hover(&self, uri: &str, position: &Json) -> Json {
+        let name = match self.name_at(uri, position) {
+            Some(name) => name,
+            None => return Json::Null,
+        };
+        let analyses = self.analyses(uri);
+        let declared = analyses
+            .iter()
+            .find_map(|(_, _, analysis)| analysis.definition(&name));
+        let value = if let Some(definition) = declared {
+            format!("```logo\n{}\n```", definition.title())
+        } else if let Some((usage, doc)) = describe(&name) {
+            format!("```logo\n{}\n```\n{}", usage, doc)
+        } else {
+            return Json::Null;
+        };
+        json!({ "contents": { "kind": "markdown", "value": value } })
+    }
+
+    fn completion(&self, uri: &str) -> Json {
+        let analysis = analyze_program(self.source(uri));
+        let mut items = Vec::new();
+        for definition in analysis.definitions.iter() {
+            items.push(json!({
+                "label": definition.name,
+                "kind": COMPLETION_FUNCTION,
+                "detail": definition.title(),
+            }));
+        }
+        for name in builtin_names() {
+            let (usage, doc) = describe(name).unwrap_or_default();
+            let kind = if is_keyword(name) {
+                COMPLETION_KEYWORD
+            } else {
+                COMPLETION_FUNCTION
+            };
+            items.push(json!({
+                "label": name,
+                "kind": kind,
+                "detail": usage,
+                "documentation": doc,
+            }));
+        }
+        json!(items)
+    }
+
+    fn symbols(&self, uri: &str) -> Json {
+        let source = self.source(uri);
+        let analysis = analyze_program(source);
+        let symbols: Vec<_> = analysis
+            .definitions
+            .iter()
+            .map(|definition| {
+                json!({
+                    "name": definition.name,
+                    "detail": definition.title(),
+                    "kind": SYMBOL_FUNCTION,
+                    "range": range(source, &definition.range),
+                    "selectionRange": range(source, &definition.span),
+                })
+            })
+            .collect();
+        json!(symbols)
+    }
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Json>) -> Json {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
+
+/// A Language Server Protocol server for Logo source files, speaking over stdin
+/// and stdout.
+fn main() {
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let stdout = io::stdout();
+    let mut output = stdout.lock();
+
+    let mut server = Server::default();
+    while let Some(message) = read_message(&mut input) {
+        if message["method"] == "exit" {
+            break;
+        }
+        for reply in server.handle(&message) {
+            if write_message(&mut output, &reply).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URI: &str = "file:///shapes.lgo";
+    const SOURCE: &str =
+        "to square :size\nrepeat 4 [fd :size rt 90]\nend\n\nsquare 10\nsquare 20\n";
+
+    /// Acts as the editor, sending messages to the server the way they would be
+    /// read from stdin.
+    struct Client {
+        server: Server,
+        id: i64,
+    }
+
+    impl Client {
+        fn open(source: &str) -> (Self, Json) {
+            let mut client = Client {
+                server: Server::default(),
+                id: 0,
+            };
+            client.request("initialize", json!({}));
+            let document =
+                json!({ "uri": URI, "languageId": "logo", "version": 1, "text": source });
+            let mut replies =
+                client.notify("textDocument/didOpen", json!({ "textDocument": document }));
+            (client, replies.remove(0))
+        }
+
+        fn notify(&mut self, method: &str, params: Json) -> Vec<Json> {
+            let message = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+            self.server.handle(&message)
+        }
+
+        fn request(&mut self, method: &str, params: Json) -> Json {
+            self.id += 1;
+            let message =
+                json!({ "jsonrpc": "2.0", "id": self.id, "method": method, "params": params });
+            let mut replies = self.server.handle(&message);
+            assert_eq!(replies.len(), 1);
+            assert_eq!(replies[0]["id"], self.id);
+            replies.remove(0)["result"].take()
+        }
+
+        fn at(&mut self, method: &str, line: usize, character: usize) -> Json {
+            let params = json!({
+                "textDocument": { "uri": URI },
+                "position": { "line": line, "character": character },
+                "context": { "includeDeclaration": true },
+            });
+            self.request(method, params)
+        }
+    }
+
+    #[test]
+    fn lsp_navigation_test() {
+        let (mut client, diagnostics) = Client::open(SOURCE);
+        assert_eq!(diagnostics["params"]["diagnostics"], json!([]));
+
+        // from the call on line 5 to the name after `to`
+        let definition = client.at("textDocument/definition", 4, 2);
+        assert_eq!(definition["uri"], URI);
+        assert_eq!(
+            definition["range"]["start"],
+            json!({ "line": 0, "character": 3 })
+        );
+        assert_eq!(
+            definition["range"]["end"],
+            json!({ "line": 0, "character": 9 })
+        );
+
+        let references = client.at("textDocument/references", 0, 4);
+        let lines: Vec<_> = references
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|location| location["range"]["start"]["line"].clone())
+            .collect();
+        assert_eq!(lines, vec![json!(0), json!(4), json!(5)]);
+
+        let symbols = client.request(
+            "textDocument/documentSymbol",
+            json!({ "textDocument": { "uri": URI } }),
+        );
+        assert_eq!(symbols[0]["name"], "square");
+        assert_eq!(symbols[0]["detail"], "to square :size");
+        assert_eq!(
+            symbols[0]["range"]["end"],
+            json!({ "line": 2, "character": 3 })
+        );
+    }
+
+    #[test]
+    fn lsp_hover_completion_test() {
+        let (mut client, _) = Client::open(SOURCE);
+
+        let hover = client.at("textDocument/hover", 1, 11);
+        assert_eq!(
+            hover["contents"]["value"],
+            "```logo\nfd distance\n```\nMoves the turtle forward."
+        );
+        let hover = client.at("textDocument/hover", 5, 0);
+        assert_eq!(hover["contents"]["value"], "```logo\nto square :size\n```");
+        assert_eq!(client.at("textDocument/hover", 1, 7), Json::Null);
+
+        let completion = client.request(
+            "textDocument/completion",
+            json!({ "textDocument": { "uri": URI } }),
+        );
+        let items = completion.as_array().unwrap();
+        let labels: Vec<_> = items
+            .iter()
+            .map(|item| item["label"].as_str().unwrap())
+            .collect();
+        assert_eq!(labels[0], "square");
+        assert!(labels.contains(&"forward") && labels.contains(&"map"));
+        let repeat = items.iter().find(|item| item["label"] == "repeat").unwrap();
+        assert_eq!(repeat["kind"], COMPLETION_KEYWORD);
+    }
+
+    #[test]
+    fn lsp_diagnostics_test() {
+        // every syntax error is reported, not just the first
+        let (mut client, diagnostics) = Client::open("fd 10 )\nrt 90 ]\n");
+        let codes: Vec<_> = diagnostics["params"]["diagnostics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|diagnostic| diagnostic["code"].clone())
+            .collect();
+        assert_eq!(codes, vec![json!(12), json!(26)]);
+        let diagnostic = &diagnostics["params"]["diagnostics"][1];
+        assert_eq!(
+            diagnostic["range"]["start"],
+            json!({ "line": 1, "character": 6 })
+        );
+
+        // diagnostics are only published again when the document is saved
+        let change =
+            json!({ "textDocument": { "uri": URI }, "contentChanges": [{ "text": "fd 10\n" }] });
+        assert!(client.notify("textDocument/didChange", change).is_empty());
+        let saved = client.notify(
+            "textDocument/didSave",
+            json!({ "textDocument": { "uri": URI } }),
+        );
+        assert_eq!(saved[0]["params"]["diagnostics"], json!([]));
+    }
+}
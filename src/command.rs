@@ -1,4 +1,4 @@
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Command {
     // movement
     Forward,
@@ -25,31 +25,47 @@ pub enum Command {
     Exit,
 }
 
+// every name a command can be called by, including abbreviations. Names in other
+// languages are translated to these, see `Locale`
+const NAMES: &[(&str, Command)] = &[
+    ("forward", Command::Forward),
+    ("fd", Command::Forward),
+    ("backward", Command::Backward),
+    ("bk", Command::Backward),
+    ("left", Command::Left),
+    ("lt", Command::Left),
+    ("right", Command::Right),
+    ("rt", Command::Right),
+    ("setheading", Command::SetHeading),
+    ("seth", Command::SetHeading),
+    ("setxy", Command::SetXY),
+    ("home", Command::Home),
+    ("penup", Command::PenUp),
+    ("pu", Command::PenUp),
+    ("pendown", Command::PenDown),
+    ("pd", Command::PenDown),
+    ("setpensize", Command::SetPenSize),
+    ("setpencolor", Command::SetPenColor),
+    ("setpc", Command::SetPenColor),
+    ("hideturtle", Command::HideTurtle),
+    ("ht", Command::HideTurtle),
+    ("showturtle", Command::ShowTurtle),
+    ("st", Command::ShowTurtle),
+    ("clearscreen", Command::ClearScreen),
+    ("cs", Command::ClearScreen),
+    ("clean", Command::Clean),
+    ("setscreencolor", Command::SetScreenColor),
+    ("setsc", Command::SetScreenColor),
+    ("show", Command::Show),
+    ("exit", Command::Exit),
+];
+
 impl Command {
     pub fn from_string(s: &str) -> Option<Self> {
-        use Command::*;
-        let command = match s {
-            "forward" | "fd" => Forward,
-            "backward" | "bk" => Backward,
-            "left" | "lt" => Left,
-            "right" | "rt" => Right,
-            "setheading" | "seth" => SetHeading,
-            "setxy" => SetXY,
-            "home" => Home,
-            "penup" | "pu" => PenUp,
-            "pendown" | "pd" => PenDown,
-            "setpensize" => SetPenSize,
-            "setpencolor" | "setpc" => SetPenColor,
-            "hideturtle" | "ht" => HideTurtle,
-            "showturtle" | "st" => ShowTurtle,
-            "clearscreen" | "cs" => ClearScreen,
-            "clean" => Clean,
-            "setscreencolor" | "setsc" => SetScreenColor,
-            "show" => Show,
-            "exit" => Exit,
-            _ => return None,
-        };
-        Some(command)
+        NAMES
+            .iter()
+            .find(|(name, _)| *name == s)
+            .map(|(_, command)| *command)
     }
 
     pub fn arity(&self) -> usize {
@@ -74,4 +90,48 @@ impl Command {
             Exit => 0,
         }
     }
+
+    /// Every name a command can be called by, including abbreviations.
+    pub fn names() -> Vec<&'static str> {
+        NAMES.iter().map(|(name, _)| *name).collect()
+    }
+
+    /// Names of the inputs taken by the command, as shown in its usage.
+    pub fn inputs(&self) -> &'static [&'static str] {
+        use Command::*;
+        match self {
+            Forward | Backward => &["distance"],
+            Left | Right | SetHeading => &["degrees"],
+            SetXY => &["x", "y"],
+            SetPenSize => &["size"],
+            SetPenColor | SetScreenColor => &["red", "green", "blue"],
+            Show => &["thing"],
+            Home | PenUp | PenDown | HideTurtle | ShowTurtle | ClearScreen | Clean | Exit => &[],
+        }
+    }
+
+    /// A short description of what the command does.
+    pub fn doc(&self) -> &'static str {
+        use Command::*;
+        match self {
+            Forward => "Moves the turtle forward.",
+            Backward => "Moves the turtle backward.",
+            Left => "Turns the turtle to the left.",
+            Right => "Turns the turtle to the right.",
+            SetHeading => "Turns the turtle to face a heading, 0 being up.",
+            SetXY => "Moves the turtle to a position, 0 0 being the center.",
+            Home => "Moves the turtle to the center, facing up.",
+            PenUp => "Lifts the pen, so that the turtle moves without drawing.",
+            PenDown => "Puts the pen down, so that the turtle draws as it moves.",
+            SetPenSize => "Sets the width of the lines drawn.",
+            SetPenColor => "Sets the color of the lines drawn, from 0 to 255 each.",
+            HideTurtle => "Hides the turtle.",
+            ShowTurtle => "Shows the turtle.",
+            ClearScreen => "Clears the screen and moves the turtle to the center.",
+            Clean => "Clears the screen without moving the turtle.",
+            SetScreenColor => "Sets the background color, from 0 to 255 each.",
+            Show => "Prints a value.",
+            Exit => "Stops the program and closes the window.",
+        }
+    }
 }
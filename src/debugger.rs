@@ -0,0 +1,137 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::evaluator::Stopped;
+
+/// A place where a debugged program stops.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Breakpoint {
+    /// Stops before a statement that starts on the line, in the program being
+    /// debugged rather than in an imported module.
+    Line(usize),
+    /// Stops before the first statement of every call of the procedure.
+    Procedure(String),
+}
+
+/// Why a debugged program stopped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopReason {
+    Breakpoint,
+    Step,
+    /// The program was interrupted, see `Debugger::interrupt_handle`.
+    Pause,
+}
+
+/// How a stopped program continues, as returned by the debug handler.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resume {
+    /// Runs until the next breakpoint.
+    Continue,
+    /// Stops before the next statement of the current procedure, or of its caller
+    /// once it has finished.
+    StepOver,
+    /// Stops before the next statement, including those of procedures it calls.
+    StepInto,
+    /// Stops before the next statement of the caller of the current procedure.
+    StepOut,
+}
+
+/// Called whenever a debugged program stops. It can inspect the stopped program
+/// and returns how it should continue.
+pub type DebugHandler = Box<dyn FnMut(&mut Stopped) -> Resume>;
+
+/// Decides where a program being run stops, see `Interpreter::set_debugger`.
+pub struct Debugger {
+    pub(crate) breakpoints: Vec<Breakpoint>,
+    pub(crate) handler: DebugHandler,
+    // a stepping program stops at the next statement run with at most this many
+    // procedure calls on the call stack
+    step_depth: Option<usize>,
+    // set by another thread to stop the program at the next statement
+    interrupt: Arc<AtomicBool>,
+    // a procedure breakpoint was hit, so the program stops at the next statement
+    entered: bool,
+    // the line of the last statement run, so that a line breakpoint stops once
+    // rather than before every statement on the line
+    last_line: Option<usize>,
+    // the index of the local scope of every procedure call being run, innermost
+    // last, as templates that name their inputs have local scopes too
+    pub(crate) scopes: Vec<usize>,
+}
+
+impl Debugger {
+    pub fn new<F: FnMut(&mut Stopped) -> Resume + 'static>(handler: F) -> Self {
+        Debugger {
+            breakpoints: Vec::new(),
+            handler: Box::new(handler),
+            step_depth: None,
+            interrupt: Arc::new(AtomicBool::new(false)),
+            entered: false,
+            last_line: None,
+            scopes: Vec::new(),
+        }
+    }
+
+    pub fn set_breakpoints(&mut self, breakpoints: Vec<Breakpoint>) {
+        self.breakpoints = breakpoints;
+    }
+
+    /// Makes the program stop before its first statement.
+    pub fn stop_on_entry(&mut self) {
+        self.step_depth = Some(usize::MAX);
+    }
+
+    /// Returns a flag that stops the program before its next statement when set,
+    /// which can be done from another thread while the program runs.
+    pub fn interrupt_handle(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.interrupt)
+    }
+
+    /// Called when the procedure `name` is called, `scope` being the index of the
+    /// local scope of the call.
+    pub(crate) fn enter(&mut self, name: &str, scope: usize) {
+        self.scopes.push(scope);
+        if self
+            .breakpoints
+            .contains(&Breakpoint::Procedure(name.to_string()))
+        {
+            self.entered = true;
+        }
+    }
+
+    /// Called when the innermost procedure call has finished.
+    pub(crate) fn exit(&mut self) {
+        self.scopes.pop();
+    }
+
+    /// Called before every statement. `line` is the line the statement starts on,
+    /// if it is part of the program being debugged, and `depth` is the number of
+    /// procedure calls on the call stack. Returns why the program should stop
+    /// before the statement, if it should.
+    pub(crate) fn check(&mut self, line: Option<usize>, depth: usize) -> Option<StopReason> {
+        let new_line = line.is_some() && line != self.last_line;
+        self.last_line = line;
+
+        if self.interrupt.swap(false, Ordering::SeqCst) {
+            Some(StopReason::Pause)
+        } else if std::mem::take(&mut self.entered) {
+            Some(StopReason::Breakpoint)
+        } else if self.step_depth.is_some_and(|max| depth <= max) {
+            Some(StopReason::Step)
+        } else if new_line && self.breakpoints.contains(&Breakpoint::Line(line.unwrap())) {
+            Some(StopReason::Breakpoint)
+        } else {
+            None
+        }
+    }
+
+    /// Called after the handler returned `resume` for a statement run at `depth`.
+    pub(crate) fn resume(&mut self, resume: Resume, depth: usize) {
+        self.step_depth = match resume {
+            Resume::Continue => None,
+            Resume::StepOver => Some(depth),
+            Resume::StepInto => Some(usize::MAX),
+            Resume::StepOut => depth.checked_sub(1),
+        };
+    }
+}
@@ -0,0 +1,121 @@
+/// The dialect of Logo programs are written in, which only changes how programs
+/// are read. The UCBLogo dialect reads Berkeley Logo programs as they are
+/// written: `;` starts a comment, names may contain `?`, as in `empty?`, only
+/// whitespace and brackets separate the words of a list, so `[a+b]` holds a
+/// single word, and `\` and `|...|` put characters such as `[` and spaces in
+/// words. Programs run the same way in both dialects once they are read, with the
+/// same primitives and error numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dialect {
+    #[default]
+    Standard,
+    UCBLogo,
+}
+
+impl Dialect {
+    /// Returns the dialect named `s`, either `standard` or `ucblogo`.
+    pub fn from_string(s: &str) -> Option<Self> {
+        match s {
+            "standard" => Some(Dialect::Standard),
+            "ucblogo" => Some(Dialect::UCBLogo),
+            _ => None,
+        }
+    }
+
+    /// The names of every dialect.
+    pub fn names() -> &'static [&'static str] {
+        &["standard", "ucblogo"]
+    }
+
+    /// Names of primitives that are spelled differently in the dialect, with the
+    /// name each one stands for.
+    pub fn aliases(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Dialect::Standard => &[],
+            Dialect::UCBLogo => &[("empty?", "emptyp"), ("name?", "namep")],
+        }
+    }
+
+    /// Returns the name of the primitive called `name` in this dialect, if it is
+    /// spelled differently.
+    pub fn translate(&self, name: &str) -> Option<&'static str> {
+        let mut aliases = self.aliases().iter();
+        aliases
+            .find(|(alias, _)| *alias == name)
+            .map(|(_, standard)| *standard)
+    }
+
+    /// Whether `;` starts a comment that runs to the end of the line.
+    pub(crate) fn has_comments(&self) -> bool {
+        *self == Dialect::UCBLogo
+    }
+
+    /// Whether names may contain `?`, as in `empty?`. `?` on its own is still the
+    /// first input of a template.
+    pub(crate) fn has_predicate_names(&self) -> bool {
+        *self == Dialect::UCBLogo
+    }
+
+    /// Whether only whitespace and brackets separate the words of a list, so that
+    /// `[a+b, c]` holds the words `a+b,` and `c`. Lists run as code are read again
+    /// when they are run, where `a+b` is an addition.
+    pub(crate) fn has_list_words(&self) -> bool {
+        *self == Dialect::UCBLogo
+    }
+
+    /// Whether `\` makes the character after it part of a word and `|...|` the
+    /// characters between the bars, as in `"a\[b` and `"|hello world|`, and `"`
+    /// on its own is the empty word.
+    pub(crate) fn has_word_escapes(&self) -> bool {
+        *self == Dialect::UCBLogo
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Interpreter;
+
+    #[test]
+    fn dialect_test() {
+        // the values shown by `source`, or None if it can't be run
+        let run = |dialect, source: &str| {
+            let mut interpreter = Interpreter::new();
+            interpreter.set_dialect(dialect);
+            let instructions = interpreter.run_program(source).ok()?;
+            let shown = instructions.iter().map(|i| i.args[0].to_string());
+            Some(shown.collect::<Vec<_>>().join(" "))
+        };
+        let differences = [
+            // comments
+            ("show 1 ; one", None, Some("1")),
+            // names with `?`, and the names of primitives spelled with `?`
+            (
+                "to even? :n output 0 = remainder :n 2 end show even? 4",
+                None,
+                Some("true"),
+            ),
+            ("show empty? [] show name? \"x", None, Some("true false")),
+            // list words
+            ("show [a+b c]", Some("[a + b c]"), Some("[a+b c]")),
+            ("show [hello, world!]", None, Some("[hello, world!]")),
+            ("show [3.5]", None, Some("[3.5]")),
+            ("run [show 2+3]", Some("5"), Some("5")),
+            // word escapes and the empty word
+            ("show \"a\\[b", None, Some("a[b")),
+            ("show \"|two words|", None, Some("two words")),
+            ("show count \"", None, Some("0")),
+        ];
+        for (source, standard, ucblogo) in differences.iter() {
+            assert_eq!(
+                (
+                    run(Dialect::Standard, source),
+                    run(Dialect::UCBLogo, source)
+                ),
+                (standard.map(String::from), ucblogo.map(String::from)),
+                "{}",
+                source
+            );
+        }
+    }
+}
@@ -1,43 +1,190 @@
-use crate::lexer::Token;
+use crate::lexer::{Span, Token};
+use crate::locale::Locale;
+use crate::value::Value;
+use std::error::Error;
 use std::fmt;
 
+/// An error from any phase of the interpreter, along with where it happened.
 #[derive(Debug)]
 pub enum LogoError {
     Lex(LexError),
-    Parse(ParseError),
-    Runtime(RuntimeError),
+    /// `span` is the span of the token the parser stopped at.
+    Parse {
+        error: ParseError,
+        span: Option<Span>,
+    },
+    /// `span` is the span of the innermost statement that failed, in the source it
+    /// was read from (a loaded file for procedures declared in one), and
+    /// `call_stack` holds the calls of user defined procedures being run when the
+    /// error was raised, innermost last.
+    Runtime {
+        error: RuntimeError,
+        span: Option<Span>,
+        call_stack: Vec<Frame>,
+    },
 }
 
-impl fmt::Display for LogoError {
+/// A call of a user defined procedure, as recorded in the call stack.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub name: String,
+    pub args: Vec<Value>,
+    /// The span of the statement the procedure was called from.
+    pub span: Option<Span>,
+}
+
+impl fmt::Display for Frame {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "{}", self.name)?;
+        for arg in self.args.iter() {
+            write!(formatter, " {}", arg)?;
+        }
+        Ok(())
+    }
+}
+
+impl LogoError {
+    /// Returns the number of the error, see `RuntimeError::code`.
+    pub fn code(&self) -> usize {
+        match self {
+            LogoError::Lex(error) => error.code(),
+            LogoError::Parse { error, span: _ } => error.code(),
+            LogoError::Runtime { error, .. } => error.code(),
+        }
+    }
+
+    /// Returns where the error happened, if known.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            LogoError::Lex(LexError::UnrecognizedToken { found: _, span }) => Some(*span),
+            LogoError::Parse { error: _, span } => *span,
+            LogoError::Runtime { span, .. } => *span,
+        }
+    }
+
+    /// Returns the calls of the procedures being run when a runtime error was
+    /// raised, innermost last. Empty for errors raised at the top level, and for
+    /// lexing and parsing errors.
+    pub fn call_stack(&self) -> &[Frame] {
+        match self {
+            LogoError::Runtime { call_stack, .. } => call_stack,
+            _ => &[],
+        }
+    }
+
+    /// Returns the message of the error in the language of `locale`, followed by
+    /// the trace of the calls that led to it for runtime errors. This is what the
+    /// error displays as in English.
+    pub fn message(&self, locale: Locale) -> String {
         use LogoError::*;
-        formatter.write_str(&match self {
-            Lex(e) => format!("{}", e),
-            Parse(e) => format!("{}", e),
-            Runtime(e) => format!("{}", e),
-        })
+        match self {
+            Lex(e) => e.message(locale),
+            Parse {
+                error,
+                span: Some(span),
+            } => locale.text(
+                "{} at {}",
+                &[&error.message(locale), &position(locale, span)],
+            ),
+            Parse { error, span: None } => error.message(locale),
+            Runtime {
+                error,
+                span,
+                call_stack,
+            } => {
+                let mut s = error.message(locale);
+                // a trace of the calls that led to the error, from the line it was
+                // raised on to the line of the top level call. Deep recursion makes
+                // long traces, of which only the innermost and outermost
+                // `TRACE_ENDS` calls are printed
+                let mut span = *span;
+                let skipped = call_stack.len().saturating_sub(2 * TRACE_ENDS);
+                for (i, frame) in call_stack.iter().rev().enumerate() {
+                    if skipped > 0 && i == TRACE_ENDS {
+                        let more = locale.text("... {} more calls", &[&skipped]);
+                        s.push_str(&format!("\n  {}", more));
+                    }
+                    if skipped == 0 || i < TRACE_ENDS || i >= TRACE_ENDS + skipped {
+                        let called = if i == 0 { "in {}" } else { "called from {}" };
+                        let call = format!("{}{}", frame, line(locale, span));
+                        s.push_str(&format!("\n  {}", locale.text(called, &[&call])));
+                    }
+                    span = frame.span;
+                }
+                if !call_stack.is_empty() {
+                    let top_level = locale.text("called from top level{}", &[&line(locale, span)]);
+                    s.push_str(&format!("\n  {}", top_level));
+                }
+                s
+            }
+        }
+    }
+}
+
+impl fmt::Display for LogoError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        formatter.write_str(&self.message(Locale::English))
+    }
+}
+
+// how many of the innermost and outermost calls are shown in a trace of calls that
+// is longer than twice as many
+const TRACE_ENDS: usize = 5;
+
+fn line(locale: Locale, span: Option<Span>) -> String {
+    match span {
+        Some(span) => locale.text(", line {}", &[&span.line]),
+        None => String::new(),
+    }
+}
+
+// the line and column of `span`, as in `line 2, column 5`
+fn position(locale: Locale, span: &Span) -> String {
+    locale.text("line {}, column {}", &[&span.line, &span.column])
+}
+
+impl Error for LogoError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LogoError::Lex(e) => Some(e),
+            LogoError::Parse { error, span: _ } => Some(error),
+            LogoError::Runtime { error, .. } => Some(error),
+        }
     }
 }
 
 #[derive(Debug)]
 pub enum LexError {
-    UnrecognizedToken,
+    UnrecognizedToken { found: char, span: Span },
+}
+
+impl LexError {
+    /// Returns the number of the error, see `RuntimeError::code`.
+    pub fn code(&self) -> usize {
+        match self {
+            LexError::UnrecognizedToken { .. } => 100,
+        }
+    }
+
+    /// Returns the message of the error in the language of `locale`.
+    pub fn message(&self, locale: Locale) -> String {
+        match self {
+            LexError::UnrecognizedToken { found, span } => locale.text(
+                "Found unexpected character '{}' at {}",
+                &[found, &position(locale, span)],
+            ),
+        }
+    }
 }
 
 impl fmt::Display for LexError {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        write!(
-            formatter,
-            "{}",
-            match self {
-                LexError::UnrecognizedToken => {
-                    String::from("Found unexpected token during lexing phase")
-                }
-            }
-        )
+        formatter.write_str(&self.message(Locale::English))
     }
 }
 
+impl Error for LexError {}
+
 #[derive(Debug)]
 pub enum ParseError {
     TypeMismatch { expected: String },
@@ -45,66 +192,434 @@ pub enum ParseError {
     UnexpectedToken(Token, Vec<Token>),
     ParseInteger(String),
     UnbalancedParens,
+    NotEnoughInputs { name: String },
+    // the default number of inputs on the title line of `name` is out of range
+    InvalidDefaultArity { name: String, arity: usize },
+}
+
+impl ParseError {
+    /// Returns the number of the error, see `RuntimeError::code`.
+    pub fn code(&self) -> usize {
+        match self {
+            ParseError::NotEnoughInputs { name: _ } => 6,
+            ParseError::UnbalancedParens => 10,
+            ParseError::UnexpectedToken(Token::RParen, _) => 12,
+            ParseError::UnexpectedToken(Token::RBracket, _) => 26,
+            ParseError::EOF => 101,
+            ParseError::UnexpectedToken(_, _) => 102,
+            ParseError::TypeMismatch { expected: _ } => 103,
+            ParseError::ParseInteger(_) => 104,
+            ParseError::InvalidDefaultArity { .. } => 105,
+        }
+    }
+
+    /// Returns the message of the error in the language of `locale`.
+    pub fn message(&self, locale: Locale) -> String {
+        match self {
+            ParseError::EOF => locale.text("Reached EOF (End of file) while parsing", &[]),
+            ParseError::UnexpectedToken(unexpected, expected) => {
+                let expected: Vec<_> = expected.iter().map(|tok| tok.to_string()).collect();
+                locale.text(
+                    "Unexpected token: {}. Expected: {}",
+                    &[unexpected, &expected.join(", ")],
+                )
+            }
+            ParseError::TypeMismatch { expected } => locale.text(
+                "Found unexpected type while parsing, expected {}",
+                &[expected],
+            ),
+            ParseError::ParseInteger(n) => locale.text("Error while parsing integer: {}", &[n]),
+            ParseError::UnbalancedParens => {
+                locale.text("Found unbalanced parentheses while parsing", &[])
+            }
+            ParseError::NotEnoughInputs { name } => {
+                locale.text("Not enough inputs to '{}'", &[name])
+            }
+            ParseError::InvalidDefaultArity { name, arity } => locale.text(
+                "Procedure '{}' can't take {} inputs by default",
+                &[name, arity],
+            ),
+        }
+    }
 }
 
 impl fmt::Display for ParseError {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        write!(
-            formatter,
-            "{}",
-            match self {
-                ParseError::EOF => String::from("Reached EOF (End of file) while parsing"),
-                ParseError::UnexpectedToken(unexpected, expected) => {
-                    let mut s = format!("Unexpected token: {}. Expected: ", unexpected);
-                    for tok in expected {
-                        s.push_str(&tok.to_string());
-                        s.push_str(", ");
-                    }
-                    // strip trailing comma for readability
-                    s.truncate(s.len()-2);
-                    s
-                },
-                ParseError::TypeMismatch { expected } => {
-                    format!("Found unexpected type while parsing, expected {}", expected)
-                },
-                ParseError::ParseInteger(n) => format!("Error while parsing integer: {}", n),
-                ParseError::UnbalancedParens => {
-                    String::from("Found unbalanced parentheses while parsing")
-                },
-            }
-        )
+        formatter.write_str(&self.message(Locale::English))
     }
 }
 
+impl Error for ParseError {}
+
 #[derive(Debug)]
 pub enum RuntimeError {
-    RedeclaredProcedure { name: String },
-    ProcedureNotFound { name: String },
-    VariableNotFound { name: String },
-    ArgCountMismatch { expected: usize },
+    RedeclaredProcedure {
+        name: String,
+    },
+    RedefinedPrimitive {
+        name: String,
+    },
+    // `suggestion` is a similar name that exists, for typos
+    ProcedureNotFound {
+        name: String,
+        suggestion: Option<String>,
+    },
+    VariableNotFound {
+        name: String,
+        suggestion: Option<String>,
+    },
+    NoValue {
+        name: String,
+    },
+    InvalidInput {
+        name: String,
+        value: String,
+    },
+    ArgCountMismatch {
+        name: String,
+        expected: usize,
+        given: usize,
+    },
+    // `name` is the operator or primitive that failed, as in the other errors
+    DivideByZero {
+        name: String,
+        dividend: isize,
+    },
+    Overflow {
+        name: String,
+        operands: Vec<isize>,
+    },
+    MalformedExpression {
+        name: String,
+    },
+    NoOutput {
+        name: String,
+    },
+    UnusedOutput {
+        name: String,
+    },
+    OutsideProcedure {
+        name: String,
+    },
+    FileError {
+        path: String,
+        message: String,
+    },
+    ModuleNotFound {
+        name: String,
+    },
+    ImportCycle {
+        chain: Vec<String>,
+    },
+    InvalidInstructionList {
+        name: String,
+        message: String,
+    },
+    NotInTemplate {
+        name: String,
+    },
+    // the slot `name`, such as `?3`, is past the `inputs` of the template
+    SlotNotFound {
+        name: String,
+        inputs: usize,
+    },
+    Template {
+        template: String,
+        error: Box<RuntimeError>,
+    },
+    Throw {
+        tag: String,
+        value: Option<Value>,
+    },
+    NotPaused,
+    // calls of procedures and primitives that run code nested `depth` deep, `name`
+    // being the one that went past it
+    StackOverflow {
+        name: String,
+        depth: usize,
+    },
     Other(String),
 }
 
-impl fmt::Display for RuntimeError {
-    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        write!(
-            formatter,
-            "{}",
-            match self {
-                RuntimeError::RedeclaredProcedure { name } => {
-                    format!("Procedure '{}' has already been declared", name)
-                }
-                RuntimeError::ProcedureNotFound { name } => {
-                    format!("Procedure '{}' does not exist", name)
+impl RuntimeError {
+    /// Returns the number of the error, output by `error` after an error has been
+    /// caught. Errors with an equivalent in UCBLogo have its number, so several
+    /// kinds may share one, as dividing by zero and `first []` share UCBLogo's 7
+    /// for bad inputs. The others have their own number from 100. The numbers are
+    /// stable, so embedders can rely on them.
+    pub fn code(&self) -> usize {
+        use RuntimeError::*;
+        match self {
+            // a procedure declared by another module
+            RedeclaredProcedure { name: _ } => 15,
+            RedefinedPrimitive { name: _ } => 22,
+            StackOverflow { name: _, depth: _ } => 2,
+            ProcedureNotFound { .. } => 13,
+            VariableNotFound { .. } => 11,
+            // UCBLogo's recoverable "doesn't like X as input"
+            InvalidInput { name: _, value: _ } => 7,
+            DivideByZero { .. } => 7,
+            // calls whose inputs aren't counted by the parser, such as parenthesized
+            // calls and `apply`, are given too few or too many
+            ArgCountMismatch {
+                name: _,
+                expected,
+                given,
+            } if given < expected => 6,
+            ArgCountMismatch { .. } => 8,
+            NoOutput { name: _ } => 5,
+            UnusedOutput { name: _ } => 9,
+            OutsideProcedure { name: _ } => 31,
+            FileError {
+                path: _,
+                message: _,
+            } => 18,
+            Template { template: _, error } => error.code(),
+            Throw { tag, value } if tag == "error" => {
+                if value.is_some() {
+                    35
+                } else {
+                    21
                 }
-                RuntimeError::VariableNotFound { name } => {
-                    format!("Variable :{} has not been declared", name)
-                }
-                RuntimeError::ArgCountMismatch { expected } => {
-                    format!("Wrong number of arguments, expected {}", expected)
-                }
-                RuntimeError::Other(message) => message.to_string(),
             }
-        )
+            Throw { tag: _, value: _ } => 14,
+            NoValue { name: _ } => 11,
+            Overflow { .. } => 108,
+            MalformedExpression { name: _ } => 109,
+            ModuleNotFound { name: _ } => 110,
+            ImportCycle { chain: _ } => 111,
+            InvalidInstructionList {
+                name: _,
+                message: _,
+            } => 112,
+            NotInTemplate { name: _ } => 113,
+            NotPaused => 114,
+            Other(_) => 115,
+            SlotNotFound { name: _, inputs: _ } => 116,
+        }
+    }
+
+    /// Returns the message of the error in the language of `locale`.
+    pub fn message(&self, locale: Locale) -> String {
+        use RuntimeError::*;
+        match self {
+            RedeclaredProcedure { name } => {
+                locale.text("Procedure '{}' has already been declared", &[name])
+            }
+            RedefinedPrimitive { name } => locale.text("'{}' is a primitive", &[name]),
+            ProcedureNotFound { name, suggestion } => match suggestion {
+                Some(suggestion) => locale.text(
+                    "Procedure '{}' does not exist; did you mean '{}'?",
+                    &[name, suggestion],
+                ),
+                None => locale.text("Procedure '{}' does not exist", &[name]),
+            },
+            VariableNotFound { name, suggestion } => match suggestion {
+                Some(suggestion) => locale.text(
+                    "Variable :{} has not been declared; did you mean :{}?",
+                    &[name, suggestion],
+                ),
+                None => locale.text("Variable :{} has not been declared", &[name]),
+            },
+            NoValue { name } => locale.text("Variable :{} has no value", &[name]),
+            InvalidInput { name, value } => {
+                locale.text("'{}' doesn't like {} as input", &[name, value])
+            }
+            ArgCountMismatch {
+                name,
+                expected,
+                given,
+            } => locale.text(
+                "Wrong number of arguments to '{}', expected {} but got {}",
+                &[name, expected, given],
+            ),
+            DivideByZero { name, dividend } => {
+                locale.text("'{}' can't divide {} by zero", &[name, dividend])
+            }
+            Overflow { name, operands } => {
+                let operands: Vec<_> = operands.iter().map(|n| n.to_string()).collect();
+                locale.text(
+                    "'{}' overflowed with inputs {}",
+                    &[name, &operands.join(" ")],
+                )
+            }
+            MalformedExpression { name } => {
+                locale.text("Malformed arithmetic expression at '{}'", &[name])
+            }
+            NoOutput { name } => locale.text("Procedure '{}' did not output a value", &[name]),
+            UnusedOutput { name } => {
+                locale.text("You don't say what to do with the output of '{}'", &[name])
+            }
+            OutsideProcedure { name } => {
+                locale.text("'{}' can only be used inside a procedure", &[name])
+            }
+            FileError { path, message } => {
+                locale.text("Error with file '{}': {}", &[path, message])
+            }
+            ModuleNotFound { name } => {
+                locale.text("Could not find module '{}' on the search path", &[name])
+            }
+            ImportCycle { chain } => locale.text("Import cycle: {}", &[&chain.join(" -> ")]),
+            InvalidInstructionList { name, message } => locale.text(
+                "Invalid instruction list given to '{}': {}",
+                &[name, message],
+            ),
+            NotInTemplate { name } => {
+                locale.text("'{}' can only be used inside a template", &[name])
+            }
+            SlotNotFound { name, inputs } => locale.text(
+                "Template input {} doesn't exist, the template has {} inputs",
+                &[name, inputs],
+            ),
+            Template { template, error } => {
+                locale.text("{}, in template {}", &[&error.message(locale), template])
+            }
+            // `(throw "error message)` raises an error with a custom message
+            Throw { tag, value } if tag == "error" => match value {
+                Some(message) => message.to_string(),
+                None => String::from("Throw \"error"),
+            },
+            Throw { tag, value: _ } => locale.text("Can't find catch tag for {}", &[tag]),
+            NotPaused => locale.text("'continue' can only be used while paused", &[]),
+            StackOverflow { name, depth } => locale.text(
+                "Stack overflow calling '{}', calls are nested {} deep",
+                &[name, depth],
+            ),
+            Other(message) => message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        formatter.write_str(&self.message(Locale::English))
+    }
+}
+
+impl Error for RuntimeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RuntimeError::Template { template: _, error } => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_code_test() {
+        let name = || "f".to_string();
+        let runtime = vec![
+            RuntimeError::RedeclaredProcedure { name: name() },
+            RuntimeError::RedefinedPrimitive { name: name() },
+            RuntimeError::ProcedureNotFound {
+                name: name(),
+                suggestion: None,
+            },
+            RuntimeError::VariableNotFound {
+                name: name(),
+                suggestion: None,
+            },
+            RuntimeError::NoValue { name: name() },
+            RuntimeError::InvalidInput {
+                name: name(),
+                value: name(),
+            },
+            RuntimeError::ArgCountMismatch {
+                name: name(),
+                expected: 1,
+                given: 2,
+            },
+            RuntimeError::DivideByZero {
+                name: name(),
+                dividend: 1,
+            },
+            RuntimeError::Overflow {
+                name: name(),
+                operands: vec![1],
+            },
+            RuntimeError::MalformedExpression { name: name() },
+            RuntimeError::NoOutput { name: name() },
+            RuntimeError::UnusedOutput { name: name() },
+            RuntimeError::OutsideProcedure { name: name() },
+            RuntimeError::FileError {
+                path: name(),
+                message: name(),
+            },
+            RuntimeError::ModuleNotFound { name: name() },
+            RuntimeError::ImportCycle { chain: vec![] },
+            RuntimeError::InvalidInstructionList {
+                name: name(),
+                message: name(),
+            },
+            RuntimeError::NotInTemplate { name: name() },
+            RuntimeError::SlotNotFound {
+                name: name(),
+                inputs: 1,
+            },
+            RuntimeError::Throw {
+                tag: "error".to_string(),
+                value: None,
+            },
+            RuntimeError::Throw {
+                tag: "error".to_string(),
+                value: Some(Value::Number(1)),
+            },
+            RuntimeError::Throw {
+                tag: name(),
+                value: None,
+            },
+            RuntimeError::NotPaused,
+            RuntimeError::StackOverflow {
+                name: name(),
+                depth: 1,
+            },
+            RuntimeError::Other(name()),
+        ];
+        let parse = vec![
+            ParseError::TypeMismatch { expected: name() },
+            ParseError::EOF,
+            ParseError::UnexpectedToken(Token::RParen, vec![]),
+            ParseError::UnexpectedToken(Token::RBracket, vec![]),
+            ParseError::UnexpectedToken(Token::LBracket, vec![]),
+            ParseError::ParseInteger(name()),
+            ParseError::UnbalancedParens,
+            ParseError::NotEnoughInputs { name: name() },
+            ParseError::InvalidDefaultArity {
+                name: name(),
+                arity: 1,
+            },
+        ];
+
+        // errors with an equivalent in UCBLogo have its number
+        let codes: Vec<_> = runtime.iter().map(|e| e.code()).collect();
+        assert_eq!(codes[..8], [15, 22, 13, 11, 11, 7, 8, 7]);
+        let too_few = RuntimeError::ArgCountMismatch {
+            name: name(),
+            expected: 2,
+            given: 1,
+        };
+        assert_eq!(too_few.code(), 6);
+
+        // the others can be told apart by their numbers
+        let mut codes: Vec<_> = codes.into_iter().filter(|&code| code >= 100).collect();
+        codes.extend(parse.iter().map(|e| e.code()).filter(|&code| code >= 100));
+        let count = codes.len();
+        codes.sort_unstable();
+        codes.dedup();
+        assert_eq!(codes.len(), count);
+
+        // errors in templates have the number of the error
+        let error = RuntimeError::Template {
+            template: "[? / 0]".to_string(),
+            error: Box::new(RuntimeError::DivideByZero {
+                name: "/".to_string(),
+                dividend: 1,
+            }),
+        };
+        assert_eq!(error.code(), 7);
     }
 }
@@ -1,26 +1,330 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
 
 use crate::command::Command;
-use crate::error::RuntimeError;
-use crate::lexer::Operator;
+use crate::debugger::{Breakpoint, Debugger, StopReason};
+use crate::dialect::Dialect;
+use crate::error::{Frame, LogoError, RuntimeError};
+use crate::formatter;
+use crate::lexer::{Operator, Span};
+use crate::locale::{Locale, Naming};
+use crate::module::{self, Modules};
 use crate::parser::{Expression, Statement, AST};
+use crate::primitive::Primitive;
+use crate::stdlib;
+use crate::value::Value;
+
+// how deeply calls may nest by default, see `Evaluator::descend`. Each call takes
+// several times more of the native stack in debug builds, and these leave room on
+// the 8 MB stack of the main thread
+const MAX_DEPTH: usize = if cfg!(debug_assertions) { 300 } else { 1000 };
 
 #[derive(Debug, PartialEq)]
 pub struct Instruction {
     pub command: Command,
-    pub args: Vec<isize>,
+    pub args: Vec<Value>,
 }
 
 struct Procedure {
     ast: AST,
     params: Vec<String>,
+    // inputs that may be left out with their default value, the input given any
+    // extra inputs, and the number of inputs of unparenthesized calls if it isn't
+    // the number of required inputs
+    optional: Vec<(String, Expression)>,
+    rest: Option<String>,
+    default_arity: Option<usize>,
+    // the module that declared the procedure, None if it wasn't imported
+    module: Option<PathBuf>,
+}
+
+impl Procedure {
+    /// Returns the Logo source of the procedure's definition. `arities` holds the
+    /// arities of the procedures it may call, and `naming` how names are matched.
+    fn source(&self, name: &str, arities: HashMap<String, usize>, naming: Naming) -> String {
+        let ast = AST::from(vec![self.declaration(name)]);
+        formatter::format(&ast, arities, naming)
+            .trim_end()
+            .to_string()
+    }
+
+    /// Returns the title line of the procedure's definition, e.g. `to square :size`
+    fn title(&self, name: &str, naming: Naming) -> String {
+        let source = self.source(name, HashMap::new(), naming);
+        source.lines().next().unwrap_or_default().to_string()
+    }
+
+    /// Returns the statement declaring the procedure as `name`.
+    fn declaration(&self, name: &str) -> Statement {
+        Statement::ProcedureDeclaration {
+            name: name.to_string(),
+            body: self.ast.clone(),
+            params: self.params.clone(),
+            optional: self.optional.clone(),
+            rest: self.rest.clone(),
+            default_arity: self.default_arity,
+        }
+    }
+}
+
+/// A program paused by `pause`, handed to the host's pause handler. The handler
+/// typically reads instructions from the user and runs them until `continue` is
+/// run, see `Interpreter::set_pause_handler`.
+pub struct Pause<'a> {
+    evaluator: &'a mut Evaluator,
+    instructions: &'a mut Vec<Instruction>,
+}
+
+impl<'a> Pause<'a> {
+    /// Returns the name of the procedure that paused, or None at the top level.
+    pub fn procedure(&self) -> Option<&str> {
+        self.evaluator
+            .call_stack
+            .last()
+            .map(|frame| frame.name.as_str())
+    }
+
+    /// Removes and returns the instructions produced by the paused program so far,
+    /// so that the frontend can run them before the program continues.
+    pub fn take_instructions(&mut self) -> Vec<Instruction> {
+        std::mem::take(self.instructions)
+    }
+
+    /// Runs `source` in the paused procedure, where its local variables are visible.
+    pub fn run(&mut self, source: &str) -> Result<Vec<Instruction>, LogoError> {
+        let evaluator = &*self.evaluator;
+        let ast = crate::parse_program(source, evaluator.procedure_arities(), evaluator.naming)?;
+        self.evaluator
+            .evaluate_ast(&ast)
+            .map_err(|e| self.evaluator.locate_error(e))
+    }
+
+    /// Whether `continue` has been run, after which the handler should return.
+    pub fn resumed(&self) -> bool {
+        self.evaluator.resumed
+    }
+}
+
+/// Called by `pause`, see `Interpreter::set_pause_handler`
+pub type PauseHandler = Box<dyn FnMut(&mut Pause)>;
+
+/// Something that happened in a procedure that is being traced or stepped, see
+/// `Interpreter::set_trace_handler`. `depth` is the number of procedure calls the
+/// event is nested in, used to indent trace lines.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TraceEvent {
+    /// A traced procedure was called.
+    Call { frame: Frame, depth: usize },
+    /// A traced procedure finished, outputting `value` or stopping.
+    Exit {
+        name: String,
+        value: Option<Value>,
+        depth: usize,
+    },
+    /// A stepped procedure is about to run `statement`, formatted as source.
+    Step {
+        name: String,
+        statement: String,
+        span: Option<Span>,
+        depth: usize,
+    },
+}
+
+/// Trace lines are written like UCBLogo's, e.g. ` ( tree 50 3 )`, ` tree stops`
+/// and ` tree outputs 5`.
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            TraceEvent::Call { frame, depth } => {
+                write!(formatter, "{}( {} )", " ".repeat(*depth), frame)
+            }
+            TraceEvent::Exit {
+                name,
+                value: Some(value),
+                depth,
+            } => write!(
+                formatter,
+                "{}{} outputs {}",
+                " ".repeat(*depth),
+                name,
+                value
+            ),
+            TraceEvent::Exit {
+                name,
+                value: None,
+                depth,
+            } => write!(formatter, "{}{} stops", " ".repeat(*depth), name),
+            TraceEvent::Step {
+                statement, depth, ..
+            } => write!(formatter, "{}{}", " ".repeat(*depth), statement),
+        }
+    }
+}
+
+/// An event handed to the host's trace handler, along with the instructions
+/// produced by the program so far.
+pub struct Trace<'a> {
+    event: TraceEvent,
+    instructions: &'a mut Vec<Instruction>,
+}
+
+impl<'a> Trace<'a> {
+    pub fn event(&self) -> &TraceEvent {
+        &self.event
+    }
+
+    /// Removes and returns the instructions produced by the program so far, so
+    /// that the frontend can run them before the program continues.
+    pub fn take_instructions(&mut self) -> Vec<Instruction> {
+        std::mem::take(self.instructions)
+    }
+}
+
+/// Called for every trace event, see `Interpreter::set_trace_handler`
+pub type TraceHandler = Box<dyn FnMut(&mut Trace)>;
+
+/// A program stopped by its debugger, handed to the debug handler. The variables
+/// of the stopped program can be inspected and expressions evaluated where it
+/// stopped, see `Interpreter::set_debugger`.
+pub struct Stopped<'a> {
+    evaluator: &'a mut Evaluator,
+    instructions: &'a mut Vec<Instruction>,
+    breakpoints: &'a mut Vec<Breakpoint>,
+    // the local scopes of the procedure calls, see `Debugger::enter`
+    scopes: &'a [usize],
+    reason: StopReason,
+}
+
+impl<'a> Stopped<'a> {
+    pub fn reason(&self) -> StopReason {
+        self.reason
+    }
+
+    /// Returns the span of the statement the program stopped before.
+    pub fn span(&self) -> Option<Span> {
+        self.evaluator.current_span
+    }
+
+    /// Returns the calls of the procedures being run, innermost last.
+    pub fn call_stack(&self) -> &[Frame] {
+        &self.evaluator.call_stack
+    }
+
+    /// Returns the variables of the local scopes, innermost last, sorted by name.
+    /// Every procedure call has a scope, as do templates that name their inputs.
+    /// Variables declared with `local` but not given a value have no value.
+    pub fn locals(&self) -> Vec<Vec<(String, Option<Value>)>> {
+        self.evaluator.locals.iter().map(sorted_scope).collect()
+    }
+
+    /// Returns the local variables of the procedure call `depth` deep in the call
+    /// stack, the outermost call being 1, sorted by name. None for the top level.
+    /// The scopes of the templates the call runs aren't included.
+    pub fn procedure_locals(&self, depth: usize) -> Option<Vec<(String, Option<Value>)>> {
+        let scope = *self.scopes.get(depth.checked_sub(1)?)?;
+        self.evaluator.locals.get(scope).map(sorted_scope)
+    }
+
+    /// Returns the global variables, sorted by name.
+    pub fn globals(&self) -> Vec<(String, Option<Value>)> {
+        sorted_scope(&self.evaluator.globals)
+    }
+
+    /// Runs `source` where the program stopped, returning the value of its last
+    /// expression if it has one, e.g. `:size * 2` or `first :list`.
+    pub fn evaluate(&mut self, source: &str) -> Result<Option<Value>, LogoError> {
+        let evaluator = &mut *self.evaluator;
+        let (ast, expr) =
+            crate::parse_instruction_list(source, evaluator.procedure_arities(), evaluator.naming)?;
+        let mut result = evaluator
+            .evaluate_block(&ast, self.instructions)
+            .map(|_| None);
+        if let (Ok(_), Some(expr)) = (&result, expr) {
+            result = evaluator
+                .evaluate_expression(&expr, self.instructions)
+                .map(Some);
+        }
+        result.map_err(|e| evaluator.locate_error(e))
+    }
+
+    /// Removes and returns the instructions produced by the program so far, so
+    /// that the frontend can run them while the program is stopped.
+    pub fn take_instructions(&mut self) -> Vec<Instruction> {
+        std::mem::take(self.instructions)
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        self.breakpoints
+    }
+
+    pub fn set_breakpoints(&mut self, breakpoints: Vec<Breakpoint>) {
+        *self.breakpoints = breakpoints;
+    }
+}
+
+fn sorted_scope(scope: &Scope) -> Vec<(String, Option<Value>)> {
+    let mut variables: Vec<_> = scope
+        .iter()
+        .map(|(name, val)| (name.to_string(), val.clone()))
+        .collect();
+    variables.sort_by(|a, b| a.0.cmp(&b.0));
+    variables
+}
+
+/// How evaluation continues after a statement has been evaluated
+enum Flow {
+    Next,
+    Stop,
+    Output(Value),
 }
 
+// a variable binding is None when the variable has been declared with
+// `local` or `global` but not yet given a value
+type Scope = HashMap<String, Option<Value>>;
+
 pub struct Evaluator {
-    globals: HashMap<String, Expression>,
+    globals: Scope,
     // stack of local scopes
-    locals: Vec<HashMap<String, Expression>>,
+    locals: Vec<Scope>,
+    // inputs of the templates being applied, see `?`
+    slots: Vec<Vec<Value>>,
+    // calls of the user defined procedures being run, innermost last
+    call_stack: Vec<Frame>,
+    // number of nested calls of procedures and of primitives that run code, and
+    // how many may be nested, see `descend`
+    depth: usize,
+    max_depth: usize,
+    // the span of the innermost statement being run that has one
+    current_span: Option<Span>,
+    // where the error being propagated was raised, recorded as it starts to
+    // propagate: the call stack at the time, and the span and source of the
+    // failing statement
+    error_stack: Option<Vec<Frame>>,
+    error_span: Option<Span>,
+    error_line: Option<String>,
+    // the last error caught by `catch "error`, as output by `error`
+    last_error: Option<Value>,
+    pause_handler: Option<PauseHandler>,
+    // procedures named by `trace` and `step`, and the handler told about them
+    traced: HashSet<String>,
+    stepped: HashSet<String>,
+    trace_handler: Option<TraceHandler>,
+    debugger: Option<Debugger>,
+    // number of nested pauses, and whether `continue` was run in the innermost one
+    paused: usize,
+    resumed: bool,
     procedures: HashMap<String, Procedure>,
+    // property lists by name, properties are kept in the order they were first set
+    plists: HashMap<String, Vec<(String, Value)>>,
+    // whether redefining a procedure prints a message
+    warn_on_redefine: bool,
+    // how names are matched, and how every name was spelled when it was first
+    // declared, by key, see `key`
+    naming: Naming,
+    spellings: HashMap<String, String>,
+    modules: Modules,
 }
 
 impl Evaluator {
@@ -30,342 +334,3426 @@ impl Evaluator {
         Evaluator {
             globals: HashMap::new(),
             locals: Vec::new(),
+            slots: Vec::new(),
+            call_stack: Vec::new(),
+            depth: 0,
+            max_depth: MAX_DEPTH,
+            current_span: None,
+            error_stack: None,
+            error_span: None,
+            error_line: None,
+            last_error: None,
+            pause_handler: None,
+            traced: HashSet::new(),
+            stepped: HashSet::new(),
+            trace_handler: None,
+            debugger: None,
+            paused: 0,
+            resumed: false,
             procedures: HashMap::new(),
+            plists: HashMap::new(),
+            warn_on_redefine: false,
+            naming: Naming::default(),
+            spellings: HashMap::new(),
+            modules: Modules::new(),
         }
     }
 
+    pub fn set_warn_on_redefine(&mut self, enabled: bool) {
+        self.warn_on_redefine = enabled;
+    }
+
+    pub fn set_case_sensitive(&mut self, enabled: bool) {
+        self.naming.case_sensitive = enabled;
+    }
+
+    pub fn set_max_depth(&mut self, depth: usize) {
+        self.max_depth = depth;
+    }
+
+    pub fn set_locale(&mut self, locale: Locale) {
+        self.naming.locale = locale;
+    }
+
+    pub fn set_dialect(&mut self, dialect: Dialect) {
+        self.naming.dialect = dialect;
+    }
+
+    /// Returns how names are matched to keywords, primitives and procedures, for
+    /// parsing programs the way they will be run.
+    pub(crate) fn naming(&self) -> Naming {
+        self.naming
+    }
+
+    pub fn add_search_path(&mut self, dir: PathBuf) {
+        self.modules.add_search_path(dir);
+    }
+
+    pub fn set_pause_handler(&mut self, handler: PauseHandler) {
+        self.pause_handler = Some(handler);
+    }
+
+    pub fn set_trace_handler(&mut self, handler: TraceHandler) {
+        self.trace_handler = Some(handler);
+    }
+
+    pub fn set_debugger(&mut self, debugger: Option<Debugger>) {
+        self.debugger = debugger;
+    }
+
+    /// Returns the number of inputs taken by unparenthesized calls of every user
+    /// defined procedure, so that later programs can be parsed with knowledge of
+    /// their arities.
+    pub fn procedure_arities(&self) -> HashMap<String, usize> {
+        self.procedures
+            .iter()
+            .map(|(name, procedure)| {
+                let arity = procedure.default_arity.unwrap_or(procedure.params.len());
+                (name.to_string(), arity)
+            })
+            .collect()
+    }
+
     pub fn evaluate_ast(&mut self, ast: &AST) -> Result<Vec<Instruction>, RuntimeError> {
+        self.error_stack = None;
+        self.error_span = None;
+        self.error_line = None;
         let mut instructions = Vec::new();
-        for stmt in ast.statements.iter() {
-            self.evaluate_statement(stmt, &mut instructions)?;
+        self.evaluate_top_level(ast, &mut instructions)?;
+        Ok(instructions)
+    }
+
+    /// Evaluates statements in order until one of them stops or outputs from the
+    /// enclosing procedure.
+    fn evaluate_block(
+        &mut self,
+        ast: &AST,
+        instructions: &mut Vec<Instruction>,
+    ) -> Result<Flow, RuntimeError> {
+        for (i, stmt) in ast.statements.iter().enumerate() {
+            // statements of lists run as code don't have spans, they are located at
+            // the statement that runs the list
+            let outer_span = self.current_span;
+            if let Some(span) = ast.spans.get(i) {
+                self.current_span = Some(*span);
+            }
+
+            if self.debugger.is_some() {
+                self.debug(instructions);
+            }
+
+            self.step(stmt, instructions);
+
+            let flow = self.evaluate_statement(stmt, instructions);
+            if flow.is_err() && self.error_stack.is_none() {
+                self.error_stack = Some(self.call_stack.clone());
+                self.error_span = self.current_span;
+                self.error_line = Some(self.statement_source(stmt));
+            }
+            self.current_span = outer_span;
+
+            match flow? {
+                Flow::Next => (),
+                flow => return Ok(flow),
+            }
         }
 
-        Ok(instructions)
+        Ok(Flow::Next)
+    }
+
+    /// Reports `stmt` to the trace handler before it runs, if the procedure it is
+    /// part of is being stepped. Kept out of `evaluate_block`, which every call
+    /// nests, so its stack frame stays small.
+    fn step(&mut self, stmt: &Statement, instructions: &mut Vec<Instruction>) {
+        if let Some(frame) = self.call_stack.last() {
+            if self.stepped.contains(&self.key(&frame.name)) {
+                let event = TraceEvent::Step {
+                    name: frame.name.clone(),
+                    statement: self.statement_source(stmt),
+                    span: self.current_span,
+                    depth: self.call_stack.len(),
+                };
+                self.report(event, instructions);
+            }
+        }
+    }
+
+    /// Returns `stmt` formatted as source, as it is shown when stepping through a
+    /// procedure.
+    fn statement_source(&self, stmt: &Statement) -> String {
+        let ast = AST::from(vec![stmt.clone()]);
+        let source = formatter::format(&ast, self.procedure_arities(), self.naming);
+        source.trim_end().to_string()
+    }
+
+    /// Wraps an error returned by the evaluator with where it was raised.
+    pub fn locate_error(&mut self, error: RuntimeError) -> LogoError {
+        self.error_line = None;
+        LogoError::Runtime {
+            error,
+            span: self.error_span.take(),
+            call_stack: self.error_stack.take().unwrap_or_default(),
+        }
     }
 
     fn evaluate_statement(
         &mut self,
         stmt: &Statement,
         instructions: &mut Vec<Instruction>,
-    ) -> Result<(), RuntimeError> {
-        // currently does not handle varying argument types,
-        // only accept LOGO number values as command arguments
+    ) -> Result<Flow, RuntimeError> {
         match stmt {
-            Statement::ProcedureDeclaration { name, body, params } => {
-                if let Some(_) = self.procedures.get(name) {
-                    return Err(RuntimeError::RedeclaredProcedure {
+            Statement::ProcedureDeclaration {
+                name,
+                body,
+                params,
+                optional,
+                rest,
+                default_arity,
+            } => {
+                // primitives can't be redefined, user procedures can
+                let key = self.key(name);
+                if Command::from_string(&key).is_some() || Primitive::from_string(&key).is_some() {
+                    return Err(RuntimeError::RedefinedPrimitive {
                         name: name.to_string(),
                     });
                 }
 
+                let module = self.modules.current().cloned();
+                if let Some(existing) = self.procedures.get(&key) {
+                    // a module may not replace procedures it didn't declare, it
+                    // should be imported into a namespace instead
+                    if module.is_some() && existing.module != module {
+                        return Err(RuntimeError::RedeclaredProcedure {
+                            name: name.to_string(),
+                        });
+                    }
+
+                    if self.warn_on_redefine {
+                        instructions.push(show(Value::Word(format!("{} redefined", name))));
+                    }
+                }
+
+                let key = self.declare(key, name);
                 self.procedures.insert(
-                    name.to_string(),
+                    key,
                     Procedure {
                         ast: body.clone(),
                         params: params.clone(),
+                        optional: optional.clone(),
+                        rest: rest.clone(),
+                        default_arity: *default_arity,
+                        module,
                     },
                 );
             }
 
-            Statement::ProcedureCall { name, args } => {
-                if let Some(command) = Command::from_string(name) {
-                    if command.arity() != args.len() {
-                        return Err(RuntimeError::ArgCountMismatch {
-                            expected: command.arity(),
-                        });
-                    }
-
-                    let mut _args: Vec<isize> = Vec::new();
-                    for i in 0..args.len() {
-                        _args.push(self.evaluate_expression(&args[i])?);
+            // `run` and `catch` as statements run their list as part of the enclosing
+            // block, so `stop` and `output` in the list leave the enclosing procedure
+            Statement::ProcedureCall { name, args } if runs_in_block(&self.key(name), args) => {
+                let mut values = Vec::new();
+                for arg in args.iter() {
+                    values.push(self.evaluate_expression(arg, instructions)?);
+                }
+                match self.run_in_block(name, values, instructions)? {
+                    (Flow::Next, Some(_)) => {
+                        return Err(RuntimeError::UnusedOutput {
+                            name: name.to_string(),
+                        })
                     }
+                    (flow, _) => return Ok(flow),
+                }
+            }
 
-                    instructions.push(Instruction {
-                        command,
-                        args: _args,
+            Statement::ProcedureCall { name, args } => {
+                if self.call_procedure(name, args, instructions)?.is_some() {
+                    return Err(RuntimeError::UnusedOutput {
+                        name: name.to_string(),
                     });
-                } else {
-                    let procedure = match self.procedures.get(name) {
-                        Some(p) => p,
-                        None => {
-                            return Err(RuntimeError::ProcedureNotFound {
-                                name: name.to_string(),
-                            })
-                        }
-                    };
-
-                    if args.len() != procedure.params.len() {
-                        return Err(RuntimeError::ArgCountMismatch {
-                            expected: procedure.params.len(),
-                        });
-                    }
-
-                    let ast = procedure.ast.clone();
-
-                    let mut local_vars = HashMap::<String, Expression>::new();
-                    for i in 0..args.len() {
-                        local_vars.insert(procedure.params[i].to_string(), args[i].clone());
-                    }
-
-                    // begin procedure scope
-                    self.locals.push(local_vars);
-
-                    // evaluate the ast and append the result to 'instructions'
-                    instructions.extend(self.evaluate_ast(&ast)?);
-
-                    // end procedure scope
-                    self.locals.pop();
                 }
             }
 
             Statement::VariableDeclaration { name, val } => {
-                let _val = (**val).clone();
-                let expr = Expression::Number {
-                    val: self.evaluate_expression(&_val)?,
-                };
-
-                // check for whether the variable is local or global
-                let scope_depth = self.locals.len();
-                if scope_depth > 0 {
-                    self.locals[scope_depth - 1].insert(name.to_string(), expr);
-                } else {
-                    self.globals.insert(name.to_string(), expr);
-                }
+                let val = self.evaluate_expression(val, instructions)?;
+                self.set_variable(name, val);
             }
 
             Statement::Repeat { count, body } => {
-                let _count = self.evaluate_expression(count)?;
-                for _ in 0.._count {
-                    instructions.extend(self.evaluate_ast(body)?);
+                let count = self.evaluate_expression(count, instructions)?;
+                for _ in 0..expect_number("repeat", count)? {
+                    match self.evaluate_block(body, instructions)? {
+                        Flow::Next => (),
+                        flow => return Ok(flow),
+                    }
                 }
             }
-        }
 
-        Ok(())
-    }
-
-    fn evaluate_expression(&self, expr: &Expression) -> Result<isize, RuntimeError> {
-        match expr {
-            Expression::Number { val } => Ok(*val),
-            Expression::Variable { name } => {
-                // check for variable in local scope first
-                for i in 0..self.locals.len() {
-                    match self.locals[i].get(name) {
-                        Some(e) => return self.evaluate_expression(e),
-                        None => (),
+            Statement::If {
+                condition,
+                body,
+                else_body,
+            } => {
+                let name = if else_body.is_some() { "ifelse" } else { "if" };
+                let condition = self.evaluate_expression(condition, instructions)?;
+                let body = if expect_boolean(name, condition, self.naming)? {
+                    Some(body)
+                } else {
+                    else_body.as_ref()
+                };
+                if let Some(body) = body {
+                    match self.evaluate_block(body, instructions)? {
+                        Flow::Next => (),
+                        flow => return Ok(flow),
                     }
                 }
+            }
 
-                // check in global scope if variable wasn't found
-                match self.globals.get(name) {
-                    Some(e) => self.evaluate_expression(e),
-                    None => Err(RuntimeError::VariableNotFound {
-                        name: name.to_string(),
-                    }),
-                }
+            Statement::Output { val } => {
+                return Ok(Flow::Output(self.evaluate_expression(val, instructions)?));
             }
-            Expression::ArithmeticExpression { postfix } => Ok(self.evaluate_postfix(postfix)?),
 
-            // this case should not be reached under normal circumstances
-            Expression::Operator { op } => Err(RuntimeError::Other(format!(
-                "Encountered unexpected operator {:?}",
-                op
-            ))),
+            Statement::Stop => return Ok(Flow::Stop),
         }
+
+        Ok(Flow::Next)
     }
 
-    /// Evaluates an arithmetic expression in postfix notation. The arithmetic expression is
-    /// represented as a Vec of Expressions. Returns a Result of either the resulting number
-    /// or any encountered RuntimeErrors.
-    fn evaluate_postfix(&self, postfix: &Vec<Expression>) -> Result<isize, RuntimeError> {
-        let mut stack: Vec<isize> = Vec::new();
-        for expr in postfix.iter() {
-            match expr {
-                Expression::Number { val: _ } | Expression::Variable { name: _ } => {
-                    stack.push(self.evaluate_expression(expr)?)
-                }
-                Expression::Operator { op } => {
-                    let operand_2 = stack.pop().unwrap();
-                    let operand_1 = stack.pop().unwrap();
-
-                    let result = match op {
-                        Operator::Addition => operand_1 + operand_2,
-                        Operator::Subtraction => operand_1 - operand_2,
-                        Operator::Multiplication => operand_1 * operand_2,
-                        Operator::Division => operand_1 / operand_2,
-                    };
-                    stack.push(result);
-                }
-                _ => {
-                    return Err(RuntimeError::Other(
-                        "reverse polish notation should only contain numbers,
-                        variables and operators"
-                            .to_string(),
-                    ))
-                }
+    /// Assigns `val` to the innermost existing binding of `name`, searching the
+    /// local scopes of the active procedures before the global scope. A global
+    /// variable is created if there is no binding yet.
+    fn set_variable(&mut self, name: &str, val: Value) {
+        let key = self.variable_key(name);
+        for scope in self.locals.iter_mut().rev() {
+            if let Some(binding) = scope.get_mut(&key) {
+                *binding = Some(val);
+                return;
             }
         }
-        Ok(stack[0])
-    }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let key = self.declare(self.variable_key(name), name);
+        self.globals.insert(key, Some(val));
+    }
 
-    #[test]
-    fn evaluate_movement_commands_test() {
-        let mut evaluator = Evaluator::new();
+    /// Returns the binding of `name` in the innermost scope that declares it.
+    fn get_variable(&self, name: &str) -> Option<&Option<Value>> {
+        let key = self.variable_key(name);
+        self.locals
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&key))
+            .or_else(|| self.globals.get(&key))
+    }
 
-        let ast = AST {
-            statements: vec![
-                Statement::ProcedureCall {
-                    name: "fd".to_string(),
-                    args: vec![Expression::Number { val: 10 }],
-                },
-                Statement::ProcedureCall {
-                    name: "backward".to_string(),
-                    args: vec![Expression::Number { val: 4321 }],
-                },
-                Statement::ProcedureCall {
-                    name: "right".to_string(),
-                    args: vec![Expression::Number { val: 100 }],
-                },
-                Statement::ProcedureCall {
-                    name: "left".to_string(),
-                    args: vec![Expression::Number { val: -100 }],
-                },
-            ],
-        };
+    /// Returns the key the procedure `name` is stored under. Names are compared
+    /// ignoring case, so the key is the lowercase name, unless the evaluator is
+    /// case sensitive. Primitives called by their name in the locale have their
+    /// English name as key.
+    fn key(&self, name: &str) -> String {
+        self.naming.key(name)
+    }
 
-        let instructions = match evaluator.evaluate_ast(&ast) {
-            Ok(i) => i,
-            Err(e) => panic!(e),
-        };
+    /// Returns the key the variable or property list `name` is stored under, as
+    /// `key` does but without translating names of primitives.
+    fn variable_key(&self, name: &str) -> String {
+        self.naming.variable_key(name)
+    }
 
-        assert_eq!(
-            instructions,
-            vec![
-                Instruction {
-                    command: Command::Forward,
-                    args: vec![10],
-                },
-                Instruction {
-                    command: Command::Backward,
-                    args: vec![4321],
-                },
-                Instruction {
-                    command: Command::Right,
-                    args: vec![100],
-                },
-                Instruction {
-                    command: Command::Left,
-                    args: vec![-100],
-                },
-            ],
-        );
+    /// Returns `key`, the key of `name`, when a procedure, variable or property
+    /// list of that name is created, remembering how the name was spelled the
+    /// first time so that it is printed that way.
+    fn declare(&mut self, key: String, name: &str) -> String {
+        if !self.naming.case_sensitive && !self.spellings.contains_key(&key) {
+            self.spellings.insert(key.clone(), name.to_string());
+        }
+        key
     }
 
-    #[test]
-    fn evaluate_repeat_test() {
-        let mut evaluator = Evaluator::new();
+    /// Returns the name stored under `key` as it was first spelled.
+    fn spelling(&self, key: &str) -> String {
+        match self.spellings.get(key) {
+            Some(spelling) => spelling.to_string(),
+            None => key.to_string(),
+        }
+    }
 
-        let ast = AST {
-            statements: vec![
-                Statement::Repeat {
-                    count: Expression::Number {
-                        val: 3,
-                    },
-                    body: AST {
-                        statements: vec![
-                            Statement::ProcedureCall {
-                                name: "forward".to_string(),
-                                args: vec![
-                                    Expression::Number {
-                                        val: 10
-                                    }
-                                ],
-                            }
-                        ]
-                    }
-                }
-            ]
-        };
+    /// Returns the error for calling the missing procedure `name`, suggesting a
+    /// procedure with a similar name. Primitives and keywords are only suggested if
+    /// `primitives` is set, as names passed to `erase` or `trace` can't be theirs.
+    fn procedure_not_found(&self, name: &str, primitives: bool) -> RuntimeError {
+        let mut candidates: Vec<&str> = self.procedures.keys().map(|name| name.as_str()).collect();
+        if primitives {
+            candidates.extend(crate::analysis::builtin_names());
+            candidates.extend(self.naming.locale.aliases().iter().map(|(alias, _)| *alias));
+            candidates.extend(
+                self.naming
+                    .dialect
+                    .aliases()
+                    .iter()
+                    .map(|(alias, _)| *alias),
+            );
+        }
+        RuntimeError::ProcedureNotFound {
+            name: name.to_string(),
+            suggestion: closest_name(name, candidates),
+        }
+    }
 
-        let instructions = evaluator.evaluate_ast(&ast).unwrap();
-        assert_eq!(
-            instructions,
-            (0..3).map(|_| {
-                Instruction {
-                    command: Command::Forward,
-                    args: vec![10],
-                }
-            }).collect::<Vec<_>>()
-        );
+    /// Returns the error for using the missing variable `name`, suggesting a
+    /// variable in scope with a similar name.
+    fn variable_not_found(&self, name: &str) -> RuntimeError {
+        let locals = self.locals.iter().flat_map(|scope| scope.keys());
+        let candidates = locals.chain(self.globals.keys()).map(|name| name.as_str());
+        RuntimeError::VariableNotFound {
+            name: name.to_string(),
+            suggestion: closest_name(name, candidates.collect()),
+        }
     }
 
-    #[test]
-    fn evaluate_postfix_test() {
-        let mut evaluator = Evaluator::new();
-        evaluator
-            .globals
-            .insert("count".to_string(), Expression::Number { val: 10 });
-        evaluator
-            .globals
-            .insert("size".to_string(), Expression::Number { val: 50 });
+    /// Calls the primitive or user defined procedure `name`, appending any turtle
+    /// instructions it produces to `instructions`. Returns the value output by the
+    /// procedure, or None if it did not output anything.
+    fn call_procedure(
+        &mut self,
+        name: &str,
+        args: &[Expression],
+        instructions: &mut Vec<Instruction>,
+    ) -> Result<Option<Value>, RuntimeError> {
+        // arguments are evaluated in the caller's scope, before the procedure's own
+        // scope begins
+        let mut values = Vec::new();
+        for arg in args.iter() {
+            values.push(self.evaluate_expression(arg, instructions)?);
+        }
 
-        // 10 5 /
-        let postfix = vec![
-            Expression::Number { val: 10 },
-            Expression::Number { val: 5 },
-            Expression::Operator {
-                op: Operator::Division,
-            },
-        ];
+        self.apply_procedure(name, values, instructions)
+    }
 
-        assert_eq!(evaluator.evaluate_postfix(&postfix).unwrap(), 2);
+    /// Calls the primitive or user defined procedure `name` with arguments that
+    /// have already been evaluated, as `call_procedure` and `apply` do.
+    fn apply_procedure(
+        &mut self,
+        name: &str,
+        args: Vec<Value>,
+        instructions: &mut Vec<Instruction>,
+    ) -> Result<Option<Value>, RuntimeError> {
+        let key = self.key(name);
+        if let Some(command) = Command::from_string(&key) {
+            if command.arity() != args.len() {
+                return Err(arg_count_mismatch(name, command.arity(), args.len()));
+            }
 
-        // evaluating 10 * :count + :size / 10
-        // in postfix: '10 :count * :size 10 / +'
-        let postfix = vec![
-            Expression::Number { val: 10 },
-            Expression::Variable {
-                name: "count".to_string(),
-            },
-            Expression::Operator {
-                op: Operator::Multiplication,
-            },
-            Expression::Variable {
-                name: "size".to_string(),
-            },
-            Expression::Number { val: 10 },
-            Expression::Operator {
-                op: Operator::Division,
-            },
-            Expression::Operator {
-                op: Operator::Addition,
-            },
-        ];
+            // turtle commands only accept numbers, show accepts any value
+            if command != Command::Show {
+                for arg in args.iter() {
+                    expect_number(name, arg.clone())?;
+                }
+            }
 
-        assert_eq!(evaluator.evaluate_postfix(&postfix).unwrap(), 105);
+            instructions.push(Instruction { command, args });
+            return Ok(None);
+        }
 
-        // 10 7 8 * + 2 -
-        let postfix = vec![
-            Expression::Number { val: 10 },
-            Expression::Number { val: 7 },
-            Expression::Number { val: 8 },
-            Expression::Operator {
-                op: Operator::Multiplication,
+        if let Some(primitive) = Primitive::from_string(&key) {
+            if !primitive.is_variadic() && primitive.arity() != args.len() {
+                return Err(arg_count_mismatch(name, primitive.arity(), args.len()));
+            }
+
+            if primitive.runs_code() {
+                self.descend(name)?;
+                let result = self.evaluate_code_primitive(name, &primitive, args, instructions);
+                self.depth -= 1;
+                return result;
+            }
+            return self.evaluate_primitive(name, &primitive, args, instructions);
+        }
+
+        let (params, optional, rest, ast) = match self.procedures.get(&key) {
+            Some(p) => (
+                p.params.clone(),
+                p.optional.clone(),
+                p.rest.clone(),
+                p.ast.clone(),
+            ),
+            None => return Err(self.procedure_not_found(name, true)),
+        };
+
+        // optional inputs may be left out, and a rest input takes any extra inputs
+        if args.len() < params.len() {
+            return Err(arg_count_mismatch(name, params.len(), args.len()));
+        }
+        let most = params.len() + optional.len();
+        if rest.is_none() && args.len() > most {
+            return Err(arg_count_mismatch(name, most, args.len()));
+        }
+
+        self.descend(name)?;
+        let frame = Frame {
+            name: self.spelling(&key),
+            args: args.clone(),
+            span: self.current_span,
+        };
+        let mut args = args.into_iter();
+        let local_vars: Scope = params
+            .iter()
+            .zip(args.by_ref())
+            .map(|(param, val)| (self.variable_key(param), Some(val)))
+            .collect();
+
+        if let Some(debugger) = &mut self.debugger {
+            debugger.enter(&frame.name, self.locals.len());
+        }
+
+        let traced = self.traced.contains(&key);
+        if traced {
+            let event = TraceEvent::Call {
+                frame: frame.clone(),
+                depth: self.call_stack.len(),
+            };
+            self.report(event, instructions);
+        }
+
+        // begin procedure scope
+        self.locals.push(local_vars);
+        self.call_stack.push(frame);
+
+        let result = self
+            .bind_optional_inputs(&optional, &rest, args.collect(), instructions)
+            .and_then(|_| self.evaluate_block(&ast, instructions));
+
+        // end procedure scope, even if the body failed
+        self.call_stack.pop();
+        self.locals.pop();
+        self.depth -= 1;
+        if let Some(debugger) = &mut self.debugger {
+            debugger.exit();
+        }
+
+        let output = match result? {
+            Flow::Output(val) => Some(val),
+            Flow::Next | Flow::Stop => None,
+        };
+        if traced {
+            let event = TraceEvent::Exit {
+                name: self.spelling(&key),
+                value: output.clone(),
+                depth: self.call_stack.len(),
+            };
+            self.report(event, instructions);
+        }
+        Ok(output)
+    }
+
+    /// Counts a call of `name` that is about to nest inside the calls being run,
+    /// raising an error if it would nest them deeper than the maximum depth. Every
+    /// recursion goes through procedures or primitives that run code, so this stops
+    /// runaway recursion before it overflows the native stack.
+    fn descend(&mut self, name: &str) -> Result<(), RuntimeError> {
+        if self.depth >= self.max_depth {
+            return Err(RuntimeError::StackOverflow {
+                name: name.to_string(),
+                depth: self.max_depth,
+            });
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Binds the optional and rest inputs of the procedure being called to `args`,
+    /// the inputs given after its required ones. Optional inputs that weren't given
+    /// take their default value, which is evaluated in the procedure's scope so that
+    /// it may use the inputs before it, as in `[:size :sides * 10]`.
+    fn bind_optional_inputs(
+        &mut self,
+        optional: &[(String, Expression)],
+        rest: &Option<String>,
+        args: Vec<Value>,
+        instructions: &mut Vec<Instruction>,
+    ) -> Result<(), RuntimeError> {
+        let mut args = args.into_iter();
+        for (param, default) in optional.iter() {
+            let val = match args.next() {
+                Some(val) => val,
+                None => self.evaluate_expression(default, instructions)?,
+            };
+            let key = self.variable_key(param);
+            self.locals.last_mut().unwrap().insert(key, Some(val));
+        }
+        if let Some(param) = rest {
+            let key = self.variable_key(param);
+            let val = Value::List(args.collect());
+            self.locals.last_mut().unwrap().insert(key, Some(val));
+        }
+        Ok(())
+    }
+
+    fn evaluate_primitive(
+        &mut self,
+        name: &str,
+        primitive: &Primitive,
+        args: Vec<Value>,
+        instructions: &mut Vec<Instruction>,
+    ) -> Result<Option<Value>, RuntimeError> {
+        use Primitive::*;
+        match primitive {
+            Sum | Difference | Product | Quotient | Remainder | Minus => {
+                let mut numbers = Vec::new();
+                for arg in args.into_iter() {
+                    numbers.push(expect_number(name, arg)?);
+                }
+                Ok(Some(Value::Number(evaluate_arithmetic(
+                    name, primitive, &numbers,
+                )?)))
+            }
+
+            // unlike `show`, lists are printed without their outer brackets, as in
+            // UCBLogo, and several inputs are separated by spaces
+            Print => {
+                let text: Vec<_> = args
+                    .iter()
+                    .map(|arg| match arg {
+                        Value::List(items) => {
+                            let items: Vec<_> = items.iter().map(|item| item.to_string()).collect();
+                            items.join(" ")
+                        }
+                        val => val.to_string(),
+                    })
+                    .collect();
+                instructions.push(show(Value::Word(text.join(" "))));
+                Ok(None)
+            }
+
+            Local => {
+                if self.locals.is_empty() {
+                    return Err(RuntimeError::OutsideProcedure {
+                        name: name.to_string(),
+                    });
+                }
+                for arg in args.into_iter() {
+                    let var = self.variable_key(&expect_word(name, arg)?);
+                    self.locals.last_mut().unwrap().insert(var, None);
+                }
+                Ok(None)
+            }
+
+            LocalMake => {
+                let mut args = args.into_iter();
+                let var = self.variable_key(&expect_word(name, args.next().unwrap())?);
+                let val = args.next().unwrap();
+                match self.locals.last_mut() {
+                    Some(scope) => {
+                        scope.insert(var, Some(val));
+                        Ok(None)
+                    }
+                    None => Err(RuntimeError::OutsideProcedure {
+                        name: name.to_string(),
+                    }),
+                }
+            }
+
+            Global => {
+                for arg in args.into_iter() {
+                    let var = expect_word(name, arg)?;
+                    let var = self.declare(self.variable_key(&var), &var);
+                    self.globals.entry(var).or_insert(None);
+                }
+                Ok(None)
+            }
+
+            Thing => {
+                let var = expect_word(name, args[0].clone())?;
+                match self.get_variable(&var) {
+                    Some(Some(val)) => Ok(Some(val.clone())),
+                    Some(None) => Err(RuntimeError::NoValue { name: var }),
+                    None => Err(self.variable_not_found(&var)),
+                }
+            }
+
+            NameP => {
+                let var = expect_word(name, args[0].clone())?;
+                let has_value = matches!(self.get_variable(&var), Some(Some(_)));
+                Ok(Some(Value::boolean(has_value)))
+            }
+
+            Erase => {
+                for arg in args.into_iter() {
+                    let procedure = expect_word(name, arg)?;
+                    let key = self.key(&procedure);
+                    if self.procedures.remove(&key).is_none() {
+                        return Err(self.procedure_not_found(&procedure, false));
+                    }
+                    // a procedure declared again is a new one, neither traced nor
+                    // spelled as before, while the spelling of a variable or
+                    // property list of the same name is kept
+                    self.traced.remove(&key);
+                    self.stepped.remove(&key);
+                    if !self.globals.contains_key(&key) && !self.plists.contains_key(&key) {
+                        self.spellings.remove(&key);
+                    }
+                }
+                Ok(None)
+            }
+
+            ErAll => {
+                self.procedures.clear();
+                self.globals.clear();
+                self.plists.clear();
+                self.spellings.clear();
+                self.traced.clear();
+                self.stepped.clear();
+                Ok(None)
+            }
+
+            Po => {
+                for arg in args.into_iter() {
+                    let procedure = expect_word(name, arg)?;
+                    match self.procedures.get(&self.key(&procedure)) {
+                        Some(p) => {
+                            let spelling = self.spelling(&self.key(&procedure));
+                            let source = p.source(&spelling, self.procedure_arities(), self.naming);
+                            instructions.push(show(Value::Word(source)));
+                        }
+                        None => return Err(self.procedure_not_found(&procedure, false)),
+                    }
+                }
+                Ok(None)
+            }
+
+            Pots => {
+                for procedure in self.procedure_names() {
+                    let spelling = self.spelling(&procedure);
+                    let title = self.procedures[&procedure].title(&spelling, self.naming);
+                    instructions.push(show(Value::Word(title)));
+                }
+                Ok(None)
+            }
+
+            Pons => {
+                for var in self.variable_names() {
+                    if let Some(Some(val)) = self.globals.get(&var) {
+                        let source = format!("make \"{} {}", self.spelling(&var), val.source());
+                        instructions.push(show(Value::Word(source)));
+                    }
+                }
+                Ok(None)
+            }
+
+            PProp => {
+                let plist = expect_word(name, args[0].clone())?;
+                let plist = self.declare(self.variable_key(&plist), &plist);
+                let property = expect_word(name, args[1].clone())?;
+                let val = args[2].clone();
+                let case_sensitive = self.naming.case_sensitive;
+                let properties = self.plists.entry(plist).or_default();
+                match properties
+                    .iter_mut()
+                    .find(|(p, _)| same_name(p, &property, case_sensitive))
+                {
+                    Some((_, existing)) => *existing = val,
+                    None => properties.push((property, val)),
+                }
+                Ok(None)
+            }
+
+            // a missing property is the empty list
+            GProp => {
+                let plist = self.variable_key(&expect_word(name, args[0].clone())?);
+                let property = expect_word(name, args[1].clone())?;
+                let val = self
+                    .plists
+                    .get(&plist)
+                    .and_then(|properties| {
+                        let mut properties = properties.iter();
+                        properties
+                            .find(|(p, _)| same_name(p, &property, self.naming.case_sensitive))
+                    })
+                    .map(|(_, val)| val.clone());
+                Ok(Some(val.unwrap_or_else(|| Value::List(Vec::new()))))
+            }
+
+            RemProp => {
+                let plist = self.variable_key(&expect_word(name, args[0].clone())?);
+                let property = expect_word(name, args[1].clone())?;
+                let case_sensitive = self.naming.case_sensitive;
+                if let Some(properties) = self.plists.get_mut(&plist) {
+                    properties.retain(|(p, _)| !same_name(p, &property, case_sensitive));
+                    if properties.is_empty() {
+                        self.plists.remove(&plist);
+                    }
+                }
+                Ok(None)
+            }
+
+            PList => {
+                let plist = self.variable_key(&expect_word(name, args[0].clone())?);
+                let mut items = Vec::new();
+                for (property, val) in self.plists.get(&plist).into_iter().flatten() {
+                    items.push(Value::Word(property.to_string()));
+                    items.push(val.clone());
+                }
+                Ok(Some(Value::List(items)))
+            }
+
+            PLists => Ok(Some(Value::List(
+                self.plist_names()
+                    .into_iter()
+                    .map(|plist| Value::Word(self.spelling(&plist)))
+                    .collect(),
+            ))),
+
+            Pops => {
+                for source in self.property_sources() {
+                    instructions.push(show(Value::Word(source)));
+                }
+                Ok(None)
+            }
+
+            ErPls => {
+                self.plists.clear();
+                Ok(None)
+            }
+
+            Save => {
+                let path = expect_word(name, args[0].clone())?;
+                match fs::write(&path, self.workspace_source()) {
+                    Ok(()) => Ok(None),
+                    Err(e) => Err(RuntimeError::FileError {
+                        path,
+                        message: e.to_string(),
+                    }),
+                }
+            }
+
+            Load => {
+                let path = expect_word(name, args[0].clone())?;
+                // files that aren't found directly are looked up like modules
+                let path = match self.modules.resolve(&path) {
+                    Ok(resolved) => resolved.to_string_lossy().to_string(),
+                    Err(_) => path,
+                };
+                let ast = self.parse_file(&path)?;
+                self.evaluate_top_level(&ast, instructions)?;
+                Ok(None)
+            }
+
+            Throw => {
+                if args.len() > 2 {
+                    return Err(arg_count_mismatch(name, 2, args.len()));
+                }
+                let mut args = args.into_iter();
+                let tag = match args.next() {
+                    Some(tag) => expect_word(name, tag)?,
+                    None => return Err(arg_count_mismatch(name, 1, 0)),
+                };
+                // `throw "ERROR` raises an error as `throw "error` does
+                let tag = match self.variable_key(&tag) {
+                    key if key == "error" => key,
+                    _ => tag,
+                };
+                Err(RuntimeError::Throw {
+                    tag,
+                    value: args.next(),
+                })
+            }
+
+            Error => Ok(Some(
+                self.last_error
+                    .take()
+                    .unwrap_or_else(|| Value::List(Vec::new())),
+            )),
+
+            Pause => {
+                self.pause(instructions);
+                Ok(None)
+            }
+
+            Continue => {
+                if self.paused == 0 {
+                    return Err(RuntimeError::NotPaused);
+                }
+                self.resumed = true;
+                Ok(None)
+            }
+
+            Trace | Untrace | Step | Unstep => {
+                for arg in args.into_iter() {
+                    let word = expect_word(name, arg)?;
+                    let procedure = self.key(&word);
+                    if !self.procedures.contains_key(&procedure) {
+                        return Err(self.procedure_not_found(&word, false));
+                    }
+                    match primitive {
+                        Trace => self.traced.insert(procedure),
+                        Untrace => self.traced.remove(&procedure),
+                        Step => self.stepped.insert(procedure),
+                        _ => self.stepped.remove(&procedure),
+                    };
+                }
+                Ok(None)
+            }
+
+            First | Last | ButFirst | ButLast => match list_part(primitive, args[0].clone()) {
+                Some(val) => Ok(Some(val)),
+                None => Err(RuntimeError::InvalidInput {
+                    name: name.to_string(),
+                    value: args[0].to_string(),
+                }),
             },
-            Expression::Operator {
-                op: Operator::Addition,
+
+            Count | EmptyP => {
+                let count = match &args[0] {
+                    Value::List(items) => items.len(),
+                    Value::Word(word) => word.chars().count(),
+                    Value::Number(n) => n.to_string().len(),
+                };
+                if *primitive == Count {
+                    Ok(Some(Value::Number(count as isize)))
+                } else {
+                    Ok(Some(Value::boolean(count == 0)))
+                }
+            }
+
+            Item => {
+                let index = expect_number(name, args[0].clone())?;
+                let items = expect_list(name, args[1].clone())?;
+                // items are numbered from 1
+                let item = if index >= 1 {
+                    items.get((index - 1) as usize)
+                } else {
+                    None
+                };
+                match item {
+                    Some(item) => Ok(Some(item.clone())),
+                    _ => Err(RuntimeError::InvalidInput {
+                        name: name.to_string(),
+                        value: index.to_string(),
+                    }),
+                }
+            }
+
+            FPut | LPut => {
+                let mut items = expect_list(name, args[1].clone())?;
+                if *primitive == FPut {
+                    items.insert(0, args[0].clone());
+                } else {
+                    items.push(args[0].clone());
+                }
+                Ok(Some(Value::List(items)))
+            }
+
+            List => Ok(Some(Value::List(args))),
+
+            Slot => {
+                let inputs = match self.slots.last() {
+                    Some(inputs) => inputs,
+                    None => {
+                        return Err(RuntimeError::NotInTemplate {
+                            name: name.to_string(),
+                        })
+                    }
+                };
+                // `?` is `?1`, and there is no `?0`
+                let index = match &name[1..] {
+                    "" => Some(1),
+                    digits => digits.parse::<usize>().ok().filter(|&index| index > 0),
+                };
+                match index.and_then(|index| inputs.get(index - 1)) {
+                    Some(val) => Ok(Some(val.clone())),
+                    None => Err(RuntimeError::SlotNotFound {
+                        name: name.to_string(),
+                        inputs: inputs.len(),
+                    }),
+                }
+            }
+
+            Import => {
+                if args.is_empty() || args.len() > 2 {
+                    return Err(arg_count_mismatch(name, 1, args.len()));
+                }
+                let mut args = args.into_iter();
+                let module = expect_word(name, args.next().unwrap())?;
+                let namespace = match args.next() {
+                    Some(arg) => Some(expect_word(name, arg)?),
+                    None => None,
+                };
+                self.import(&module, namespace, instructions)?;
+                Ok(None)
+            }
+
+            Procedures => Ok(Some(Value::List(
+                self.procedure_names()
+                    .into_iter()
+                    .map(|procedure| Value::Word(self.spelling(&procedure)))
+                    .collect(),
+            ))),
+
+            Names => Ok(Some(Value::List(
+                self.variable_names()
+                    .into_iter()
+                    .map(|var| Value::Word(self.spelling(&var)))
+                    .collect(),
+            ))),
+
+            // see `evaluate_code_primitive`
+            _ => unreachable!(),
+        }
+    }
+
+    /// Evaluates the primitives that run code, see `Primitive::runs_code`. Recursion
+    /// goes through them, so they are kept out of `evaluate_primitive`, whose stack
+    /// frame is many times larger.
+    fn evaluate_code_primitive(
+        &mut self,
+        name: &str,
+        primitive: &Primitive,
+        args: Vec<Value>,
+        instructions: &mut Vec<Instruction>,
+    ) -> Result<Option<Value>, RuntimeError> {
+        use Primitive::*;
+        match primitive {
+            Run | Catch => match self.run_in_block(name, args, instructions)? {
+                (Flow::Next, val) => Ok(val),
+                (flow, _) => top_level(flow).map(|_| None),
             },
-            Expression::Number { val: 2 },
-            Expression::Operator {
-                op: Operator::Subtraction,
+
+            RunResult => match self.run_list(name, args[0].clone(), instructions)? {
+                (Flow::Next, val) => Ok(Some(Value::List(val.into_iter().collect()))),
+                (flow, _) => top_level(flow).map(|_| None),
             },
-        ];
 
-        assert_eq!(evaluator.evaluate_postfix(&postfix).unwrap(), 64);
+            Map | CrossMap => {
+                if args.len() < 2 {
+                    return Err(arg_count_mismatch(name, 2, args.len()));
+                }
+                let mut args = args.into_iter();
+                let template = args.next().unwrap();
+                let mut lists = Vec::new();
+                for arg in args {
+                    lists.push(expect_list(name, arg)?);
+                }
+
+                let inputs = if *primitive == Map {
+                    zip_lists(name, lists)?
+                } else {
+                    // a single list holds the lists to combine
+                    if lists.len() == 1 {
+                        let mut inner = Vec::new();
+                        for list in lists.remove(0) {
+                            inner.push(expect_list(name, list)?);
+                        }
+                        lists = inner;
+                    }
+                    cross_lists(&lists)
+                };
+
+                let mut results = Vec::new();
+                for inputs in inputs {
+                    match self.apply_template(name, &template, inputs, instructions)? {
+                        Some(val) => results.push(val),
+                        None => {
+                            return Err(RuntimeError::NoOutput {
+                                name: template.to_string(),
+                            })
+                        }
+                    }
+                }
+                Ok(Some(Value::List(results)))
+            }
+
+            Filter => {
+                let mut results = Vec::new();
+                for item in expect_list(name, args[1].clone())? {
+                    let keep =
+                        self.apply_template(name, &args[0], vec![item.clone()], instructions)?;
+                    let keep = keep.ok_or_else(|| RuntimeError::NoOutput {
+                        name: args[0].to_string(),
+                    })?;
+                    if expect_boolean(name, keep, self.naming)? {
+                        results.push(item);
+                    }
+                }
+                Ok(Some(Value::List(results)))
+            }
+
+            Reduce => {
+                if args.len() < 2 || args.len() > 3 {
+                    return Err(arg_count_mismatch(name, 2, args.len()));
+                }
+                let mut items = expect_list(name, args[1].clone())?;
+                // the template is applied from the end of the list, starting with the
+                // initial value if one is given
+                let mut result = match args.get(2) {
+                    Some(initial) => initial.clone(),
+                    None => match items.pop() {
+                        Some(last) => last,
+                        None => {
+                            return Err(RuntimeError::InvalidInput {
+                                name: name.to_string(),
+                                value: args[1].to_string(),
+                            })
+                        }
+                    },
+                };
+                for item in items.into_iter().rev() {
+                    let inputs = vec![item, result];
+                    result = self
+                        .apply_template(name, &args[0], inputs, instructions)?
+                        .ok_or_else(|| RuntimeError::NoOutput {
+                            name: args[0].to_string(),
+                        })?;
+                }
+                Ok(Some(result))
+            }
+
+            ForEach => {
+                for item in expect_list(name, args[0].clone())? {
+                    if self
+                        .apply_template(name, &args[1], vec![item], instructions)?
+                        .is_some()
+                    {
+                        return Err(RuntimeError::UnusedOutput {
+                            name: args[1].to_string(),
+                        });
+                    }
+                }
+                Ok(None)
+            }
+
+            Apply => {
+                let procedure = expect_word(name, args[0].clone())?;
+                match args[1].clone() {
+                    Value::List(inputs) => self.apply_procedure(&procedure, inputs, instructions),
+                    val => Err(RuntimeError::InvalidInput {
+                        name: name.to_string(),
+                        value: val.to_string(),
+                    }),
+                }
+            }
+
+            Invoke => {
+                let mut args = args.into_iter();
+                let procedure = match args.next() {
+                    Some(arg) => expect_word(name, arg)?,
+                    None => return Err(arg_count_mismatch(name, 1, 0)),
+                };
+                self.apply_procedure(&procedure, args.collect(), instructions)
+            }
+
+            _ => unreachable!(),
+        }
+    }
+
+    /// Runs statements outside of any procedure, such as a program or the top
+    /// level of a loaded file, where `stop` and `output` can't be used.
+    fn evaluate_top_level(
+        &mut self,
+        ast: &AST,
+        instructions: &mut Vec<Instruction>,
+    ) -> Result<(), RuntimeError> {
+        let flow = self.evaluate_block(ast, instructions)?;
+        top_level(flow)
+    }
+
+    /// Applies `template` to `inputs` for the primitive `name`, returning the value
+    /// it outputs. A template is either
+    /// - the name of a procedure: `map "minus [1 2]`
+    /// - an instruction list using `?`, `?1`, `?2`... for its inputs: `map [? * 2] [1 2]`
+    /// - a list of input names followed by instructions: `map [[x] :x * 2] [1 2]`
+    ///
+    /// Errors raised by a template list are wrapped with the template's source.
+    fn apply_template(
+        &mut self,
+        name: &str,
+        template: &Value,
+        inputs: Vec<Value>,
+        instructions: &mut Vec<Instruction>,
+    ) -> Result<Option<Value>, RuntimeError> {
+        let items = match template {
+            Value::Word(procedure) => return self.apply_procedure(procedure, inputs, instructions),
+            Value::List(items) => items,
+            Value::Number(_) => {
+                return Err(RuntimeError::InvalidInput {
+                    name: name.to_string(),
+                    value: template.to_string(),
+                })
+            }
+        };
+
+        let result = match items.first() {
+            // the inputs are bound to local variables, like a procedure's
+            Some(Value::List(names)) => {
+                if names.len() != inputs.len() {
+                    return Err(arg_count_mismatch(name, names.len(), inputs.len()));
+                }
+                let mut scope = Scope::new();
+                for (input_name, val) in names.iter().zip(inputs) {
+                    let input_name = expect_word(name, input_name.clone())?;
+                    scope.insert(self.variable_key(&input_name), Some(val));
+                }
+
+                self.locals.push(scope);
+                let body = Value::List(items[1..].to_vec());
+                let result = self.run_list(name, body, instructions);
+                self.locals.pop();
+                result.map(|(flow, val)| match flow {
+                    Flow::Output(val) => Some(val),
+                    Flow::Stop => None,
+                    Flow::Next => val,
+                })
+            }
+            _ => {
+                self.slots.push(inputs);
+                let result = self.run_list(name, template.clone(), instructions);
+                self.slots.pop();
+                result.and_then(|(flow, val)| top_level(flow).map(|_| val))
+            }
+        };
+
+        result.map_err(|error| match error {
+            // a throw isn't an error yet, it may be caught outside the template
+            RuntimeError::Throw { tag, value } => RuntimeError::Throw { tag, value },
+            // raised inside as many templates as calls were nested, which would
+            // bury its message
+            error @ RuntimeError::StackOverflow { .. } => error,
+            error => RuntimeError::Template {
+                template: template.to_string(),
+                error: Box::new(error),
+            },
+        })
+    }
+
+    /// Runs the list given to `run` or `catch`, see `run_list`.
+    fn run_in_block(
+        &mut self,
+        name: &str,
+        args: Vec<Value>,
+        instructions: &mut Vec<Instruction>,
+    ) -> Result<(Flow, Option<Value>), RuntimeError> {
+        let mut args = args.into_iter();
+        let first = args.next().ok_or_else(|| arg_count_mismatch(name, 1, 0))?;
+        match args.next() {
+            Some(list) => self.catch(name, first, list, instructions),
+            None => self.run_list(name, first, instructions),
+        }
+    }
+
+    /// Runs `list` for `catch "tag list`, stopping early if a `throw "tag` is run.
+    /// The catch outputs the value thrown, if any. The tag `error` catches errors as
+    /// well, which can be inspected with `error` afterwards.
+    fn catch(
+        &mut self,
+        name: &str,
+        tag: Value,
+        list: Value,
+        instructions: &mut Vec<Instruction>,
+    ) -> Result<(Flow, Option<Value>), RuntimeError> {
+        // tags are words, compared ignoring case unless names are case sensitive
+        let tag = self.variable_key(&expect_word(name, tag)?);
+        self.error_stack = None;
+        self.error_span = None;
+        self.error_line = None;
+
+        match self.run_list(name, list, instructions) {
+            // a throw of `error` is an error rather than a value for the catch
+            Err(RuntimeError::Throw { tag: thrown, value })
+                if self.variable_key(&thrown) == tag && tag != "error" =>
+            {
+                self.error_stack = None;
+                self.error_span = None;
+                self.error_line = None;
+                Ok((Flow::Next, value))
+            }
+            // throws of other tags go on to their own catch
+            Err(RuntimeError::Throw { tag: thrown, value }) if thrown != "error" => {
+                Err(RuntimeError::Throw { tag: thrown, value })
+            }
+            Err(error) if tag == "error" => {
+                self.error_span = None;
+                let mut stack = self
+                    .error_stack
+                    .take()
+                    .unwrap_or_else(|| self.call_stack.clone());
+                let procedure = match stack.pop() {
+                    Some(frame) => Value::Word(frame.name),
+                    None => Value::List(Vec::new()),
+                };
+                // the line is the failing statement as a list, as `run` would take it
+                let line = self.error_line.take().and_then(|line| {
+                    let list = format!("[{}]", line);
+                    match crate::parse_instruction_list(&list, HashMap::new(), self.naming) {
+                        Ok((_, Some(Expression::List { items }))) => Some(items),
+                        _ => None,
+                    }
+                });
+                self.last_error = Some(Value::List(vec![
+                    Value::Number(error.code() as isize),
+                    Value::Word(error.message(self.naming.locale)),
+                    procedure,
+                    Value::List(line.unwrap_or_default()),
+                ]));
+                Ok((Flow::Next, None))
+            }
+            result => result,
+        }
+    }
+
+    /// Asks the debugger whether the program stops before the statement about to
+    /// be run, handing control to its handler if it does.
+    fn debug(&mut self, instructions: &mut Vec<Instruction>) {
+        let mut debugger = match self.debugger.take() {
+            Some(debugger) => debugger,
+            None => return,
+        };
+
+        // line breakpoints only apply to the program being debugged, not to
+        // imported modules or to procedures they declare
+        let in_program = self.modules.current().is_none()
+            && self.call_stack.last().is_none_or(|frame| {
+                self.procedures
+                    .get(&self.key(&frame.name))
+                    .is_none_or(|procedure| procedure.module.is_none())
+            });
+        let line = self
+            .current_span
+            .filter(|_| in_program)
+            .map(|span| span.line);
+
+        let depth = self.call_stack.len();
+        if let Some(reason) = debugger.check(line, depth) {
+            let resume = (debugger.handler)(&mut Stopped {
+                evaluator: self,
+                instructions,
+                breakpoints: &mut debugger.breakpoints,
+                scopes: &debugger.scopes,
+                reason,
+            });
+            debugger.resume(resume, depth);
+        }
+        self.debugger = Some(debugger);
+    }
+
+    /// Hands a trace event to the trace handler. Without a handler, the event is
+    /// shown as a trace line.
+    fn report(&mut self, event: TraceEvent, instructions: &mut Vec<Instruction>) {
+        match self.trace_handler.take() {
+            Some(mut handler) => {
+                handler(&mut Trace {
+                    event,
+                    instructions,
+                });
+                self.trace_handler = Some(handler);
+            }
+            None => instructions.push(show(Value::Word(event.to_string()))),
+        }
+    }
+
+    /// Hands control to the pause handler, if there is one, which can run
+    /// instructions with access to the local variables of the paused procedure
+    /// until it returns.
+    fn pause(&mut self, instructions: &mut Vec<Instruction>) {
+        if let Some(mut handler) = self.pause_handler.take() {
+            self.paused += 1;
+            handler(&mut Pause {
+                evaluator: self,
+                instructions,
+            });
+            self.paused -= 1;
+            self.resumed = false;
+            self.pause_handler = Some(handler);
+        }
+    }
+
+    /// Runs `list` as a list of instructions for the primitive `name`. Returns how
+    /// evaluation continues after the list, and the value of the expression or
+    /// procedure call it ends with, if any.
+    fn run_list(
+        &mut self,
+        name: &str,
+        list: Value,
+        instructions: &mut Vec<Instruction>,
+    ) -> Result<(Flow, Option<Value>), RuntimeError> {
+        let source = match list {
+            Value::List(items) => items
+                .iter()
+                .map(Value::to_string)
+                .collect::<Vec<_>>()
+                .join(" "),
+            Value::Word(word) => word,
+            val => {
+                return Err(RuntimeError::InvalidInput {
+                    name: name.to_string(),
+                    value: val.to_string(),
+                })
+            }
+        };
+
+        let arities = self.procedure_arities();
+        let (ast, result) =
+            crate::parse_instruction_list(&source, arities, self.naming).map_err(|e| {
+                RuntimeError::InvalidInstructionList {
+                    name: name.to_string(),
+                    message: e.message(self.naming.locale),
+                }
+            })?;
+
+        match self.evaluate_block(&ast, instructions)? {
+            Flow::Next => (),
+            flow => return Ok((flow, None)),
+        }
+
+        let val = match result {
+            Some(Expression::ProcedureCall { name, args }) => {
+                self.call_procedure(&name, &args, instructions)
+            }
+            Some(expr) => self.evaluate_expression(&expr, instructions).map(Some),
+            None => Ok(None),
+        };
+        // the expression the list ends with isn't a statement, so an error raised by
+        // it is recorded here, with the list as its line
+        if val.is_err() && self.error_stack.is_none() {
+            self.error_stack = Some(self.call_stack.clone());
+            self.error_span = self.current_span;
+            self.error_line = Some(source);
+        }
+        Ok((Flow::Next, val?))
+    }
+
+    /// Runs the module `name` unless it has already been imported into `namespace`.
+    /// With a namespace, the procedures declared by the module are prefixed with
+    /// it, e.g. `(import "shapes "s)` declares `s.square`.
+    fn import(
+        &mut self,
+        name: &str,
+        namespace: Option<String>,
+        instructions: &mut Vec<Instruction>,
+    ) -> Result<(), RuntimeError> {
+        // files on the search path take precedence over the bundled libraries
+        let (path, mut ast) = match self.modules.resolve(name) {
+            Ok(path) => {
+                if self.modules.is_imported(&path, &namespace) {
+                    return Ok(());
+                }
+                let ast = self.parse_file(&path.to_string_lossy())?;
+                (path, ast)
+            }
+            Err(e) => {
+                let source = stdlib::source(name).ok_or(e)?;
+                let path = PathBuf::from(format!("<stdlib>/{}.lgo", name));
+                if self.modules.is_imported(&path, &namespace) {
+                    return Ok(());
+                }
+                let ast = self.parse_source(&path.to_string_lossy(), source)?;
+                (path, ast)
+            }
+        };
+
+        if let Some(namespace) = &namespace {
+            module::namespace_procedures(&mut ast, namespace, self.naming);
+        }
+
+        self.modules.begin_import(&path)?;
+        let result = self.evaluate_top_level(&ast, instructions);
+        self.modules.end_import(namespace, result.is_ok());
+        result
+    }
+
+    /// Imports the module `name` as `import "name` does.
+    pub fn import_module(&mut self, name: &str) -> Result<Vec<Instruction>, RuntimeError> {
+        let mut instructions = Vec::new();
+        self.import(name, None, &mut instructions)?;
+        Ok(instructions)
+    }
+
+    /// Runs the program in the file at `path`, see the `load` command.
+    pub fn load_file(&mut self, path: &str) -> Result<Vec<Instruction>, RuntimeError> {
+        let ast = self.parse_file(path)?;
+        self.evaluate_ast(&ast)
+    }
+
+    fn parse_file(&self, path: &str) -> Result<AST, RuntimeError> {
+        let file_error = |message: String| RuntimeError::FileError {
+            path: path.to_string(),
+            message,
+        };
+        let source = fs::read_to_string(path).map_err(|e| file_error(e.to_string()))?;
+        self.parse_source(path, &source)
+    }
+
+    /// Parses the contents of the file at `path`.
+    fn parse_source(&self, path: &str, source: &str) -> Result<AST, RuntimeError> {
+        crate::parse_program(source, self.procedure_arities(), self.naming).map_err(|e| {
+            RuntimeError::FileError {
+                path: path.to_string(),
+                message: e.message(self.naming.locale),
+            }
+        })
+    }
+
+    /// Returns Logo source that recreates every user defined procedure and global
+    /// variable when it is run, as written by `save`.
+    fn workspace_source(&self) -> String {
+        let mut statements = Vec::new();
+        for name in self.procedure_names() {
+            let procedure = &self.procedures[&name];
+            statements.push(procedure.declaration(&self.spelling(&name)));
+        }
+
+        let mut source = formatter::format(&AST::from(statements), HashMap::new(), self.naming);
+        let variables = self.variable_names();
+        if !source.is_empty() && !variables.is_empty() {
+            source.push('\n');
+        }
+        for var in variables {
+            if let Some(Some(val)) = self.globals.get(&var) {
+                source.push_str(&format!(
+                    "make \"{} {}\n",
+                    self.spelling(&var),
+                    val.source()
+                ));
+            }
+        }
+
+        let properties = self.property_sources();
+        if !source.is_empty() && !properties.is_empty() {
+            source.push('\n');
+        }
+        for property in properties {
+            source.push_str(&property);
+            source.push('\n');
+        }
+        source
+    }
+
+    /// Returns a `pprop` statement for every property of every property list, as
+    /// printed by `pops`.
+    fn property_sources(&self) -> Vec<String> {
+        let mut sources = Vec::new();
+        for plist in self.plist_names() {
+            for (property, val) in self.plists[&plist].iter() {
+                let plist = self.spelling(&plist);
+                sources.push(format!("pprop \"{} \"{} {}", plist, property, val.source()));
+            }
+        }
+        sources
+    }
+
+    /// Returns the names of all property lists in alphabetical order.
+    fn plist_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.plists.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Returns the names of all user defined procedures in alphabetical order.
+    fn procedure_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.procedures.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Returns the names of all global variables that have a value, in
+    /// alphabetical order.
+    fn variable_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .globals
+            .iter()
+            .filter(|(_, val)| val.is_some())
+            .map(|(name, _)| name.to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    fn evaluate_expression(
+        &mut self,
+        expr: &Expression,
+        instructions: &mut Vec<Instruction>,
+    ) -> Result<Value, RuntimeError> {
+        match expr {
+            Expression::Number { val } => Ok(Value::Number(*val)),
+            Expression::Word { literal } => Ok(Value::Word(literal.to_string())),
+            Expression::List { items } => Ok(Value::List(items.clone())),
+            Expression::Variable { name } => match self.get_variable(name) {
+                Some(Some(val)) => Ok(val.clone()),
+                Some(None) => Err(RuntimeError::NoValue {
+                    name: name.to_string(),
+                }),
+                None => Err(self.variable_not_found(name)),
+            },
+            Expression::ArithmeticExpression { postfix } => {
+                self.evaluate_postfix(postfix, instructions)
+            }
+            Expression::ProcedureCall { name, args } => {
+                match self.call_procedure(name, args, instructions)? {
+                    Some(val) => Ok(val),
+                    None => Err(RuntimeError::NoOutput {
+                        name: name.to_string(),
+                    }),
+                }
+            }
+
+            // this case should not be reached under normal circumstances
+            Expression::Operator { op } => Err(malformed(op.literal())),
+        }
+    }
+
+    /// Evaluates an arithmetic expression in postfix notation. The arithmetic expression is
+    /// represented as a slice of Expressions. Returns a Result of either the resulting number
+    /// or any encountered RuntimeErrors.
+    fn evaluate_postfix(
+        &mut self,
+        postfix: &[Expression],
+        instructions: &mut Vec<Instruction>,
+    ) -> Result<Value, RuntimeError> {
+        let mut stack: Vec<Value> = Vec::new();
+        for expr in postfix.iter() {
+            match expr {
+                Expression::Operator { op } => {
+                    let literal = op.literal();
+                    let operand_2 = stack.pop().ok_or_else(|| malformed(literal))?;
+                    if *op == Operator::Negation {
+                        let operand_2 = expect_number(literal, operand_2)?;
+                        let result =
+                            operand_2
+                                .checked_neg()
+                                .ok_or_else(|| RuntimeError::Overflow {
+                                    name: literal.to_string(),
+                                    operands: vec![operand_2],
+                                })?;
+                        stack.push(Value::Number(result));
+                        continue;
+                    }
+                    let operand_1 = stack.pop().ok_or_else(|| malformed(literal))?;
+
+                    // equality applies to any value, the other operators to numbers
+                    match op {
+                        Operator::Equal => stack.push(Value::boolean(operand_1 == operand_2)),
+                        Operator::NotEqual => stack.push(Value::boolean(operand_1 != operand_2)),
+                        _ => {
+                            let operand_1 = expect_number(literal, operand_1)?;
+                            let operand_2 = expect_number(literal, operand_2)?;
+                            stack.push(evaluate_operator(op, operand_1, operand_2)?);
+                        }
+                    }
+                }
+                Expression::ArithmeticExpression { postfix: _ } => return Err(malformed("(")),
+                _ => stack.push(self.evaluate_expression(expr, instructions)?),
+            }
+        }
+
+        // a well formed postfix expression leaves exactly one value on the stack,
+        // the error is at the first value left over otherwise
+        match (stack.pop(), stack.first()) {
+            (Some(result), None) => Ok(result),
+            (_, Some(extra)) => Err(malformed(&extra.to_string())),
+            (None, None) => Err(malformed("")),
+        }
+    }
+}
+
+/// Returns whether names `a` and `b` match, ignoring case unless `case_sensitive` is set.
+fn same_name(a: &str, b: &str, case_sensitive: bool) -> bool {
+    if case_sensitive {
+        a == b
+    } else {
+        a.to_lowercase() == b.to_lowercase()
+    }
+}
+
+/// Whether `name` is `run` or `catch` with the right number of arguments, which
+/// run their list as part of the enclosing block when used as statements.
+fn runs_in_block(name: &str, args: &[Expression]) -> bool {
+    match Primitive::from_string(name) {
+        Some(primitive @ Primitive::Run) | Some(primitive @ Primitive::Catch) => {
+            primitive.arity() == args.len()
+        }
+        _ => false,
+    }
+}
+
+/// Checks the flow after evaluating statements outside of any procedure, where
+/// `stop` and `output` can't be used.
+fn top_level(flow: Flow) -> Result<(), RuntimeError> {
+    match flow {
+        Flow::Next => Ok(()),
+        Flow::Stop => Err(RuntimeError::OutsideProcedure {
+            name: "stop".to_string(),
+        }),
+        Flow::Output(_) => Err(RuntimeError::OutsideProcedure {
+            name: "output".to_string(),
+        }),
+    }
+}
+
+fn arg_count_mismatch(name: &str, expected: usize, given: usize) -> RuntimeError {
+    RuntimeError::ArgCountMismatch {
+        name: name.to_string(),
+        expected,
+        given,
+    }
+}
+
+/// Returns the candidate closest to `name`, ignoring case, if it is close enough
+/// to be a typo: at most one edit away for names of up to five letters, two for up
+/// to eight and so on, and fewer edits than `name` has letters. Ties go to the
+/// candidate that sorts first.
+fn closest_name(name: &str, mut candidates: Vec<&str>) -> Option<String> {
+    let length = name.chars().count();
+    let max_distance = (1 + length.saturating_sub(3) / 3).min(length.saturating_sub(1));
+    candidates.sort_unstable();
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate.to_string())
+}
+
+/// Returns the number of characters that have to be inserted, removed, replaced
+/// or swapped with their neighbour to turn `a` into `b`, ignoring case.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+
+    // distances[i][j] is the distance between the first i characters of a and the
+    // first j characters of b
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    distances[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+    distances[a.len()][b.len()]
+}
+
+/// Returns an instruction that prints `val` in the frontend.
+fn show(val: Value) -> Instruction {
+    Instruction {
+        command: Command::Show,
+        args: vec![val],
+    }
+}
+
+fn expect_number(name: &str, val: Value) -> Result<isize, RuntimeError> {
+    match val {
+        Value::Number(n) => Ok(n),
+        _ => Err(RuntimeError::InvalidInput {
+            name: name.to_string(),
+            value: val.to_string(),
+        }),
+    }
+}
+
+/// Applies the binary operator `op` to two numbers.
+fn evaluate_operator(
+    op: &Operator,
+    operand_1: isize,
+    operand_2: isize,
+) -> Result<Value, RuntimeError> {
+    let result = match op {
+        Operator::Addition => operand_1.checked_add(operand_2),
+        Operator::Subtraction => operand_1.checked_sub(operand_2),
+        Operator::Multiplication => operand_1.checked_mul(operand_2),
+        Operator::Division => {
+            if operand_2 == 0 {
+                return Err(RuntimeError::DivideByZero {
+                    name: op.literal().to_string(),
+                    dividend: operand_1,
+                });
+            }
+            operand_1.checked_div(operand_2)
+        }
+        Operator::LessThan => return Ok(Value::boolean(operand_1 < operand_2)),
+        Operator::GreaterThan => return Ok(Value::boolean(operand_1 > operand_2)),
+        Operator::LessEqual => return Ok(Value::boolean(operand_1 <= operand_2)),
+        Operator::GreaterEqual => return Ok(Value::boolean(operand_1 >= operand_2)),
+        Operator::Negation | Operator::Equal | Operator::NotEqual => {
+            return Err(malformed(op.literal()))
+        }
+    };
+    let result = result.ok_or_else(|| RuntimeError::Overflow {
+        name: op.literal().to_string(),
+        operands: vec![operand_1, operand_2],
+    })?;
+    Ok(Value::Number(result))
+}
+
+/// Returns the error for an arithmetic expression that is malformed at `name`.
+fn malformed(name: &str) -> RuntimeError {
+    RuntimeError::MalformedExpression {
+        name: name.to_string(),
+    }
+}
+
+/// Returns the truth value of `val`, which has to be the word `true` or `false`.
+// `true` and `false` are words, so `TRUE` is true unless names are case sensitive
+fn expect_boolean(name: &str, val: Value, naming: Naming) -> Result<bool, RuntimeError> {
+    match val {
+        Value::Word(ref w) if naming.variable_key(w) == "true" => Ok(true),
+        Value::Word(ref w) if naming.variable_key(w) == "false" => Ok(false),
+        _ => Err(RuntimeError::InvalidInput {
+            name: name.to_string(),
+            value: val.to_string(),
+        }),
+    }
+}
+
+fn expect_list(name: &str, val: Value) -> Result<Vec<Value>, RuntimeError> {
+    match val {
+        Value::List(items) => Ok(items),
+        _ => Err(RuntimeError::InvalidInput {
+            name: name.to_string(),
+            value: val.to_string(),
+        }),
+    }
+}
+
+/// Returns the first, last, all but the first or all but the last member of a list,
+/// or character of a word, depending on `primitive`. Returns None if `val` is empty
+/// or a number.
+fn list_part(primitive: &Primitive, val: Value) -> Option<Value> {
+    match val {
+        Value::List(mut items) => {
+            if items.is_empty() {
+                return None;
+            }
+            match primitive {
+                Primitive::First => Some(items.remove(0)),
+                Primitive::Last => items.pop(),
+                Primitive::ButFirst => {
+                    items.remove(0);
+                    Some(Value::List(items))
+                }
+                _ => {
+                    items.pop();
+                    Some(Value::List(items))
+                }
+            }
+        }
+        Value::Word(word) => {
+            let mut chars: Vec<char> = word.chars().collect();
+            if chars.is_empty() {
+                return None;
+            }
+            let part = match primitive {
+                Primitive::First => chars.remove(0).to_string(),
+                Primitive::Last => chars.pop()?.to_string(),
+                Primitive::ButFirst => chars[1..].iter().collect(),
+                _ => chars[..chars.len() - 1].iter().collect(),
+            };
+            Some(Value::Word(part))
+        }
+        Value::Number(_) => None,
+    }
+}
+
+/// Returns the inputs for each application of a `map` template: the first members
+/// of every list, then the second members and so on. The lists must be equally long.
+fn zip_lists(name: &str, lists: Vec<Vec<Value>>) -> Result<Vec<Vec<Value>>, RuntimeError> {
+    let len = lists[0].len();
+    if let Some(list) = lists.iter().find(|list| list.len() != len) {
+        return Err(RuntimeError::InvalidInput {
+            name: name.to_string(),
+            value: Value::List(list.clone()).to_string(),
+        });
+    }
+
+    let mut iters: Vec<_> = lists.into_iter().map(Vec::into_iter).collect();
+    Ok((0..len)
+        .map(|_| iters.iter_mut().filter_map(Iterator::next).collect())
+        .collect())
+}
+
+/// Returns every combination of one member from each list, as used by `crossmap`.
+/// Combinations vary in their last member first.
+fn cross_lists(lists: &[Vec<Value>]) -> Vec<Vec<Value>> {
+    let mut combinations = vec![Vec::new()];
+    for list in lists.iter() {
+        combinations = combinations
+            .into_iter()
+            .flat_map(|combination| {
+                list.iter().map(move |item| {
+                    let mut combination = combination.clone();
+                    combination.push(item.clone());
+                    combination
+                })
+            })
+            .collect();
+    }
+    combinations
+}
+
+fn expect_word(name: &str, val: Value) -> Result<String, RuntimeError> {
+    match val {
+        Value::Word(w) => Ok(w),
+        _ => Err(RuntimeError::InvalidInput {
+            name: name.to_string(),
+            value: val.to_string(),
+        }),
+    }
+}
+
+/// Applies the arithmetic primitive `primitive`, called as `name`, to numbers.
+fn evaluate_arithmetic(
+    name: &str,
+    primitive: &Primitive,
+    args: &[isize],
+) -> Result<isize, RuntimeError> {
+    use Primitive::*;
+    let result = match primitive {
+        Sum => args.iter().try_fold(0isize, |acc, n| acc.checked_add(*n)),
+        Product => args.iter().try_fold(1isize, |acc, n| acc.checked_mul(*n)),
+        Difference => args[0].checked_sub(args[1]),
+        Quotient | Remainder => {
+            if args[1] == 0 {
+                return Err(RuntimeError::DivideByZero {
+                    name: name.to_string(),
+                    dividend: args[0],
+                });
+            }
+            if *primitive == Quotient {
+                args[0].checked_div(args[1])
+            } else {
+                args[0].checked_rem(args[1])
+            }
+        }
+        Minus => args[0].checked_neg(),
+        _ => return Err(malformed(name)),
+    };
+    result.ok_or_else(|| RuntimeError::Overflow {
+        name: name.to_string(),
+        operands: args.to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::debugger::Breakpoint;
+
+    #[test]
+    fn evaluate_movement_commands_test() {
+        let mut evaluator = Evaluator::new();
+
+        let ast = AST::from(vec![
+            Statement::ProcedureCall {
+                name: "fd".to_string(),
+                args: vec![Expression::Number { val: 10 }],
+            },
+            Statement::ProcedureCall {
+                name: "backward".to_string(),
+                args: vec![Expression::Number { val: 4321 }],
+            },
+            Statement::ProcedureCall {
+                name: "right".to_string(),
+                args: vec![Expression::Number { val: 100 }],
+            },
+            Statement::ProcedureCall {
+                name: "left".to_string(),
+                args: vec![Expression::Number { val: -100 }],
+            },
+        ]);
+
+        let instructions = match evaluator.evaluate_ast(&ast) {
+            Ok(i) => i,
+            Err(e) => panic!("{}", e),
+        };
+
+        assert_eq!(
+            instructions,
+            vec![
+                Instruction {
+                    command: Command::Forward,
+                    args: vec![Value::Number(10)],
+                },
+                Instruction {
+                    command: Command::Backward,
+                    args: vec![Value::Number(4321)],
+                },
+                Instruction {
+                    command: Command::Right,
+                    args: vec![Value::Number(100)],
+                },
+                Instruction {
+                    command: Command::Left,
+                    args: vec![Value::Number(-100)],
+                },
+            ],
+        );
+    }
+
+    #[test]
+    fn evaluate_repeat_test() {
+        let mut evaluator = Evaluator::new();
+
+        let ast = AST::from(vec![
+            Statement::Repeat {
+                count: Expression::Number {
+                    val: 3,
+                },
+                body: AST::from(vec![
+                    Statement::ProcedureCall {
+                        name: "forward".to_string(),
+                        args: vec![
+                            Expression::Number {
+                                val: 10
+                            }
+                        ],
+                    }
+                ])
+            }
+        ]);
+
+        let instructions = evaluator.evaluate_ast(&ast).unwrap();
+        assert_eq!(
+            instructions,
+            (0..3).map(|_| {
+                Instruction {
+                    command: Command::Forward,
+                    args: vec![Value::Number(10)],
+                }
+            }).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn evaluate_postfix_test() {
+        let mut evaluator = Evaluator::new();
+        evaluator
+            .globals
+            .insert("count".to_string(), Some(Value::Number(10)));
+        evaluator
+            .globals
+            .insert("size".to_string(), Some(Value::Number(50)));
+
+        // 10 5 /
+        let postfix = vec![
+            Expression::Number { val: 10 },
+            Expression::Number { val: 5 },
+            Expression::Operator {
+                op: Operator::Division,
+            },
+        ];
+
+        assert_eq!(
+            evaluator
+                .evaluate_postfix(&postfix, &mut Vec::new())
+                .unwrap(),
+            Value::Number(2)
+        );
+
+        // evaluating 10 * :count + :size / 10
+        // in postfix: '10 :count * :size 10 / +'
+        let postfix = vec![
+            Expression::Number { val: 10 },
+            Expression::Variable {
+                name: "count".to_string(),
+            },
+            Expression::Operator {
+                op: Operator::Multiplication,
+            },
+            Expression::Variable {
+                name: "size".to_string(),
+            },
+            Expression::Number { val: 10 },
+            Expression::Operator {
+                op: Operator::Division,
+            },
+            Expression::Operator {
+                op: Operator::Addition,
+            },
+        ];
+
+        assert_eq!(
+            evaluator
+                .evaluate_postfix(&postfix, &mut Vec::new())
+                .unwrap(),
+            Value::Number(105)
+        );
+
+        // 10 7 8 * + 2 -
+        let postfix = vec![
+            Expression::Number { val: 10 },
+            Expression::Number { val: 7 },
+            Expression::Number { val: 8 },
+            Expression::Operator {
+                op: Operator::Multiplication,
+            },
+            Expression::Operator {
+                op: Operator::Addition,
+            },
+            Expression::Number { val: 2 },
+            Expression::Operator {
+                op: Operator::Subtraction,
+            },
+        ];
+
+        assert_eq!(
+            evaluator
+                .evaluate_postfix(&postfix, &mut Vec::new())
+                .unwrap(),
+            Value::Number(64)
+        );
+
+        // 3 4 + - 2 *, i.e. -(3 + 4) * 2
+        let postfix = vec![
+            Expression::Number { val: 3 },
+            Expression::Number { val: 4 },
+            Expression::Operator {
+                op: Operator::Addition,
+            },
+            Expression::Operator {
+                op: Operator::Negation,
+            },
+            Expression::Number { val: 2 },
+            Expression::Operator {
+                op: Operator::Multiplication,
+            },
+        ];
+
+        assert_eq!(
+            evaluator
+                .evaluate_postfix(&postfix, &mut Vec::new())
+                .unwrap(),
+            Value::Number(-14)
+        );
+    }
+
+    #[test]
+    fn evaluate_postfix_error_test() {
+        let mut evaluator = Evaluator::new();
+
+        // 10 0 /
+        let postfix = vec![
+            Expression::Number { val: 10 },
+            Expression::Number { val: 0 },
+            Expression::Operator {
+                op: Operator::Division,
+            },
+        ];
+        match evaluator.evaluate_postfix(&postfix, &mut Vec::new()) {
+            Err(e @ RuntimeError::DivideByZero { .. }) => {
+                assert_eq!(e.to_string(), "'/' can't divide 10 by zero")
+            }
+            other => panic!("expected DivideByZero, got {:?}", other),
+        }
+
+        // isize::MAX 1 +
+        let postfix = vec![
+            Expression::Number { val: isize::MAX },
+            Expression::Number { val: 1 },
+            Expression::Operator {
+                op: Operator::Addition,
+            },
+        ];
+        match evaluator.evaluate_postfix(&postfix, &mut Vec::new()) {
+            Err(RuntimeError::Overflow { name, operands }) => {
+                assert_eq!((name.as_str(), operands), ("+", vec![isize::MAX, 1]))
+            }
+            other => panic!("expected Overflow, got {:?}", other),
+        }
+
+        // operator without enough operands, and operands without an operator
+        let malformed = [
+            vec![
+                Expression::Number { val: 10 },
+                Expression::Operator {
+                    op: Operator::Multiplication,
+                },
+            ],
+            vec![Expression::Number { val: 1 }, Expression::Number { val: 2 }],
+        ];
+        for (postfix, at) in malformed.iter().zip(["*", "1"].iter()) {
+            match evaluator.evaluate_postfix(postfix, &mut Vec::new()) {
+                Err(RuntimeError::MalformedExpression { name }) => assert_eq!(name, *at),
+                other => panic!("expected MalformedExpression, got {:?}", other),
+            }
+        }
+        match evaluator.evaluate_postfix(&[], &mut Vec::new()) {
+            Err(RuntimeError::MalformedExpression { name: _ }) => (),
+            other => panic!("expected MalformedExpression, got {:?}", other),
+        }
+    }
+
+    // lexes, parses and evaluates `source` with a fresh evaluator
+    fn run(source: &str) -> Result<Vec<Instruction>, RuntimeError> {
+        let tokens = crate::lexer::Lexer::new(source).collect_tokens().unwrap();
+        let ast = crate::parser::Parser::new(&tokens).build_ast().unwrap();
+        Evaluator::new().evaluate_ast(&ast)
+    }
+
+    #[test]
+    fn evaluate_reporter_test() {
+        assert_eq!(
+            run("fd sum 1 2 rt (sum 1 2 3 4) bk quotient 7 2 lt minus remainder 7 2").unwrap(),
+            vec![
+                Instruction {
+                    command: Command::Forward,
+                    args: vec![Value::Number(3)],
+                },
+                Instruction {
+                    command: Command::Right,
+                    args: vec![Value::Number(10)],
+                },
+                Instruction {
+                    command: Command::Backward,
+                    args: vec![Value::Number(3)],
+                },
+                Instruction {
+                    command: Command::Left,
+                    args: vec![Value::Number(-1)],
+                },
+            ]
+        );
+
+        match run("sum 1 2") {
+            Err(RuntimeError::UnusedOutput { name: _ }) => (),
+            other => panic!("expected UnusedOutput, got {:?}", other),
+        }
+        match run("fd quotient 1 0") {
+            Err(RuntimeError::DivideByZero { name, dividend }) => {
+                assert_eq!((name.as_str(), dividend), ("quotient", 1))
+            }
+            other => panic!("expected DivideByZero, got {:?}", other),
+        }
+        match run("fd minus (-9223372036854775807 - 1)") {
+            Err(e @ RuntimeError::Overflow { .. }) => assert_eq!(
+                e.to_string(),
+                "'minus' overflowed with inputs -9223372036854775808"
+            ),
+            other => panic!("expected Overflow, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn evaluate_user_reporter_test() {
+        let instructions = run("
+            to double :n
+            output :n * 2
+            end
+
+            to spiral :n
+            repeat 10 [
+                fd :n
+                make \"n :n - 1
+                repeat 1 - :n [ stop ]
+            ]
+            end
+
+            fd double double 3
+            spiral 3
+        ")
+        .unwrap();
+
+        assert_eq!(
+            instructions,
+            vec![12, 3, 2, 1]
+                .into_iter()
+                .map(|n| Instruction {
+                    command: Command::Forward,
+                    args: vec![Value::Number(n)],
+                })
+                .collect::<Vec<_>>()
+        );
+
+        match run("to square :x fd :x end fd square 5") {
+            Err(RuntimeError::NoOutput { name: _ }) => (),
+            other => panic!("expected NoOutput, got {:?}", other),
+        }
+        match run("output 5") {
+            Err(RuntimeError::OutsideProcedure { name: _ }) => (),
+            other => panic!("expected OutsideProcedure, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn evaluate_variable_scope_test() {
+        let forward = |n| Instruction {
+            command: Command::Forward,
+            args: vec![Value::Number(n)],
+        };
+        let show = |val| Instruction {
+            command: Command::Show,
+            args: vec![val],
+        };
+
+        let instructions = run("
+            make \"x 1
+            make \"counter 0
+
+            to bump
+            make \"counter :counter + 1
+            local \"x
+            make \"x 100
+            localmake \"y 5
+            fd :x + :y
+            make \"created 7
+            end
+
+            to outer :x
+            inner
+            fd :x
+            end
+
+            to inner
+            make \"x :x + 1
+            end
+
+            bump bump
+            fd :counter
+            fd :x
+            fd :created
+            outer 10
+            fd :x
+            show namep \"y
+            show namep \"counter
+            show thing \"counter
+        ")
+        .unwrap();
+
+        assert_eq!(
+            instructions,
+            vec![
+                forward(105),
+                forward(105),
+                forward(2),
+                forward(1),
+                forward(7),
+                // inner updates the parameter of its caller, not the global
+                forward(11),
+                forward(1),
+                show(Value::boolean(false)),
+                show(Value::boolean(true)),
+                show(Value::Number(2)),
+            ]
+        );
+
+        match run("to f local \"z fd :z end f") {
+            Err(RuntimeError::NoValue { name: _ }) => (),
+            other => panic!("expected NoValue, got {:?}", other),
+        }
+        match run("local \"z") {
+            Err(RuntimeError::OutsideProcedure { name: _ }) => (),
+            other => panic!("expected OutsideProcedure, got {:?}", other),
+        }
+        match run("global \"g show namep \"g fd :g") {
+            Err(RuntimeError::NoValue { name: _ }) => (),
+            other => panic!("expected NoValue, got {:?}", other),
+        }
+        match run("fd \"ten") {
+            Err(RuntimeError::InvalidInput { name: _, value: _ }) => (),
+            other => panic!("expected InvalidInput, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn evaluate_workspace_test() {
+        let word = |w: &str| Value::Word(w.to_string());
+        let instructions = run("
+            to square :size
+            repeat 4 [fd :size rt 90]
+            end
+            to square :size
+            repeat 4 [fd :size * 2 rt 90]
+            end
+            to dot
+            fd 1
+            end
+            make \"size 10
+            make \"name \"turtle
+            po \"square
+            pots
+            pons
+            show procedures
+            erase \"dot
+            show procedures
+            erall
+            show procedures
+            show names
+        ")
+        .unwrap();
+
+        let shown: Vec<Value> = instructions
+            .into_iter()
+            .map(|i| i.args[0].clone())
+            .collect();
+        assert_eq!(
+            shown,
+            vec![
+                "to square :size\n    repeat 4 [fd :size * 2 rt 90]\nend",
+                "to dot",
+                "to square :size",
+                "make \"name \"turtle",
+                "make \"size 10",
+            ]
+            .into_iter()
+            .map(word)
+            .chain(vec![
+                Value::List(vec![word("dot"), word("square")]),
+                Value::List(vec![word("square")]),
+                Value::List(vec![]),
+                Value::List(vec![]),
+            ])
+            .collect::<Vec<_>>()
+        );
+
+        match run("to fd :x end") {
+            Err(e @ RuntimeError::RedefinedPrimitive { name: _ }) => assert_eq!(e.code(), 22),
+            other => panic!("expected RedefinedPrimitive, got {:?}", other),
+        }
+        match run("erase \"missing") {
+            Err(RuntimeError::ProcedureNotFound { .. }) => (),
+            other => panic!("expected ProcedureNotFound, got {:?}", other),
+        }
+
+        // a procedure declared again after erase is neither spelled nor traced as
+        // the erased one was
+        let instructions = run("
+            to Dot
+            fd 1
+            end
+            trace \"dot
+            erase \"DOT
+            to dot
+            fd 2
+            end
+            dot
+            show procedures
+        ")
+        .unwrap();
+        assert_eq!(
+            instructions,
+            vec![
+                Instruction {
+                    command: Command::Forward,
+                    args: vec![Value::Number(2)],
+                },
+                show(Value::List(vec![word("dot")])),
+            ]
+        );
+    }
+
+    #[test]
+    fn evaluate_catch_test() {
+        let forward = |n| Instruction {
+            command: Command::Forward,
+            args: vec![Value::Number(n)],
+        };
+        let word = |w: &str| Value::Word(w.to_string());
+        let empty = || Value::List(vec![]);
+
+        assert_eq!(
+            run("catch \"done [fd 1 throw \"done fd 2] fd 3 \
+                 fd catch \"found [(throw \"found 4)] \
+                 catch \"outer [catch \"error [throw \"outer] fd 5] \
+                 fd catch \"found [show map [(throw \"found ?)] [6 7]]")
+            .unwrap(),
+            vec![forward(1), forward(3), forward(4), forward(6)]
+        );
+        assert_eq!(
+            run("to f catch \"x [output 5] output 0 end fd f").unwrap(),
+            vec![forward(5)]
+        );
+
+        // caught errors are output by `error`, once
+        assert_eq!(
+            run(
+                "to check :x if :x < 0 [(throw \"error \"negative)] output :x end \
+                 catch \"error [fd 1 / 0] show error show error \
+                 catch \"error [fd check -1] show error \
+                 catch \"error [fd 1 throw \"error fd 2] show first error"
+            )
+            .unwrap(),
+            vec![
+                show(Value::List(vec![
+                    Value::Number(7),
+                    word("'/' can't divide 1 by zero"),
+                    empty(),
+                    Value::List(vec![
+                        word("fd"),
+                        Value::Number(1),
+                        word("/"),
+                        Value::Number(0)
+                    ]),
+                ])),
+                show(empty()),
+                show(Value::List(vec![
+                    Value::Number(35),
+                    word("negative"),
+                    word("check"),
+                    Value::List(vec![
+                        word("("),
+                        word("throw"),
+                        word("\"error"),
+                        word("\"negative"),
+                        word(")"),
+                    ]),
+                ])),
+                forward(1),
+                show(Value::Number(21)),
+            ]
+        );
+
+        // the line is the failing statement of a procedure, as a list
+        assert_eq!(
+            run("to f :x\nrepeat 2 [fd 10 / :x]\nend catch \"error [f 0] show last error").unwrap(),
+            vec![show(Value::List(vec![
+                word("fd"),
+                Value::Number(10),
+                word("/"),
+                word(":x"),
+            ]))]
+        );
+
+        for slot in ["?2", "?0"].iter() {
+            match run(&format!("show map [{}] [1]", slot)) {
+                Err(RuntimeError::Template { template: _, error }) => match *error {
+                    RuntimeError::SlotNotFound { name, inputs } => {
+                        assert_eq!((name.as_str(), inputs), (*slot, 1))
+                    }
+                    other => panic!("expected SlotNotFound, got {:?}", other),
+                },
+                other => panic!("expected Template, got {:?}", other),
+            }
+        }
+
+        match run("catch \"error [throw \"nothing]") {
+            Err(error @ RuntimeError::Throw { tag: _, value: _ }) => {
+                assert_eq!(error.code(), 14);
+                assert_eq!(error.to_string(), "Can't find catch tag for nothing");
+            }
+            other => panic!("expected Throw, got {:?}", other),
+        }
+        match run("pause continue") {
+            Err(RuntimeError::NotPaused) => (),
+            other => panic!("expected NotPaused, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn evaluate_pause_test() {
+        let tokens = crate::lexer::Lexer::new("to p :n fd 1 pause fd :n end p 7")
+            .collect_tokens()
+            .unwrap();
+        let ast = crate::parser::Parser::new(&tokens).build_ast().unwrap();
+
+        let mut evaluator = Evaluator::new();
+        evaluator.set_pause_handler(Box::new(|pause: &mut Pause| {
+            assert_eq!(pause.procedure(), Some("p"));
+            assert_eq!(pause.take_instructions().len(), 1);
+            assert_eq!(pause.run("show :n").unwrap(), vec![show(Value::Number(7))]);
+            pause.run("make \"n 9 continue").unwrap();
+            assert!(pause.resumed());
+        }));
+
+        assert_eq!(
+            evaluator.evaluate_ast(&ast).unwrap(),
+            vec![Instruction {
+                command: Command::Forward,
+                args: vec![Value::Number(9)],
+            }]
+        );
+    }
+
+    #[test]
+    fn evaluate_trace_test() {
+        let line = |l: &str| show(Value::Word(l.to_string()));
+        assert_eq!(
+            run("to f :x if :x > 0 [fd f :x - 1] output :x end \
+                 trace \"f show f 1 untrace \"f show f 0")
+            .unwrap(),
+            vec![
+                line("( f 1 )"),
+                line(" ( f 0 )"),
+                line(" f outputs 0"),
+                Instruction {
+                    command: Command::Forward,
+                    args: vec![Value::Number(0)],
+                },
+                line("f outputs 1"),
+                show(Value::Number(1)),
+                show(Value::Number(0)),
+            ]
+        );
+        match run("trace \"g") {
+            Err(RuntimeError::ProcedureNotFound { name, .. }) => assert_eq!(name, "g"),
+            other => panic!("expected ProcedureNotFound, got {:?}", other),
+        }
+
+        // the handler is told about each statement of a stepped procedure before
+        // it runs, and can run the instructions produced so far
+        let tokens = crate::lexer::Lexer::new("to p :n fd :n repeat 2 [rt 90] end step \"p p 5")
+            .collect_tokens()
+            .unwrap();
+        let ast = crate::parser::Parser::new(&tokens).build_ast().unwrap();
+
+        let events = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let handler_events = std::rc::Rc::clone(&events);
+        let mut evaluator = Evaluator::new();
+        evaluator.set_trace_handler(Box::new(move |trace: &mut Trace| {
+            let taken = trace.take_instructions().len();
+            handler_events
+                .borrow_mut()
+                .push(format!("{} {}", trace.event(), taken));
+        }));
+        assert_eq!(evaluator.evaluate_ast(&ast).unwrap().len(), 1);
+        assert_eq!(
+            *events.borrow(),
+            vec![" fd :n 0", " repeat 2 [rt 90] 1", " rt 90 0", " rt 90 1"]
+        );
+    }
+
+    #[test]
+    fn evaluate_debugger_test() {
+        use crate::debugger::Resume;
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let source = "to square :size\n  repeat 4 [fd :size rt 90]\nend\n\
+                      make \"n 5\nsquare :n\nsquare :n * 2\nshow :n";
+        let ast = crate::parse_program(source, HashMap::new(), Naming::default()).unwrap();
+
+        // each stop is recorded as the stopped line, the procedure and the local
+        // variables, and the program is resumed with the next of `resumes`
+        let stops = Rc::new(RefCell::new(Vec::new()));
+        let handler_stops = Rc::clone(&stops);
+        let mut resumes = vec![
+            Resume::StepInto,
+            Resume::StepOver,
+            Resume::StepOut,
+            Resume::StepOver,
+            Resume::Continue,
+        ]
+        .into_iter();
+        let mut debugger = Debugger::new(move |stopped: &mut Stopped| {
+            let procedure = stopped.call_stack().last().map(|frame| frame.name.clone());
+            let size = stopped.evaluate(":size * 2").ok().flatten();
+            handler_stops.borrow_mut().push((
+                stopped.reason(),
+                stopped.span().unwrap().line,
+                procedure,
+                size,
+                stopped.take_instructions().len(),
+            ));
+            resumes.next().unwrap_or(Resume::Continue)
+        });
+        debugger.set_breakpoints(vec![
+            Breakpoint::Line(5),
+            Breakpoint::Procedure("square".to_string()),
+        ]);
+
+        let mut evaluator = Evaluator::new();
+        evaluator.set_debugger(Some(debugger));
+        evaluator.evaluate_ast(&ast).unwrap();
+
+        let square = || Some("square".to_string());
+        assert_eq!(
+            *stops.borrow(),
+            vec![
+                // the line breakpoint, then into square where its breakpoint is
+                (StopReason::Breakpoint, 5, None, None, 0),
+                (
+                    StopReason::Breakpoint,
+                    2,
+                    square(),
+                    Some(Value::Number(10)),
+                    0
+                ),
+                // over the repeat, stopping at its first statement
+                (StopReason::Step, 2, square(), Some(Value::Number(10)), 0),
+                // out of square, after its 4 sides
+                (StopReason::Step, 6, None, None, 8),
+                // the procedure breakpoint hides the step over the call
+                (
+                    StopReason::Breakpoint,
+                    2,
+                    square(),
+                    Some(Value::Number(20)),
+                    0
+                ),
+            ]
+        );
+
+        // procedure calls are told apart from the scopes of their templates
+        let source = "to outer :a\nforeach [1] [[x] inner :x]\nend\n\
+                      to inner :b\nfd :b\nend\nouter 5";
+        let ast = crate::parse_program(source, HashMap::new(), Naming::default()).unwrap();
+        let scopes = Rc::new(RefCell::new(Vec::new()));
+        let handler_scopes = Rc::clone(&scopes);
+        let mut debugger = Debugger::new(move |stopped: &mut Stopped| {
+            let depths = 0..=stopped.call_stack().len() + 1;
+            let locals = depths
+                .map(|depth| stopped.procedure_locals(depth))
+                .collect();
+            handler_scopes
+                .borrow_mut()
+                .push((stopped.locals().len(), locals));
+            Resume::Continue
+        });
+        debugger.set_breakpoints(vec![Breakpoint::Procedure("inner".to_string())]);
+        let mut evaluator = Evaluator::new();
+        evaluator.set_debugger(Some(debugger));
+        evaluator.evaluate_ast(&ast).unwrap();
+
+        let variable = |name: &str, n| vec![(name.to_string(), Some(Value::Number(n)))];
+        assert_eq!(
+            *scopes.borrow(),
+            vec![(
+                3,
+                vec![None, Some(variable("a", 5)), Some(variable("b", 1)), None]
+            )]
+        );
+    }
+
+    #[test]
+    fn evaluate_property_list_test() {
+        let word = |w: &str| Value::Word(w.to_string());
+        assert_eq!(
+            run("pprop \"mars \"radius 3390 pprop \"mars \"color \"red \
+                 pprop \"earth \"radius 6371 pprop \"mars \"radius 3389 \
+                 show gprop \"mars \"radius show gprop \"mars \"moons \
+                 show plist \"mars show plists pops \
+                 remprop \"earth \"radius show plists \
+                 erpls show plist \"mars")
+            .unwrap(),
+            vec![
+                show(Value::Number(3389)),
+                show(Value::List(vec![])),
+                show(Value::List(vec![
+                    word("radius"),
+                    Value::Number(3389),
+                    word("color"),
+                    word("red"),
+                ])),
+                show(Value::List(vec![word("earth"), word("mars")])),
+                show(word("pprop \"earth \"radius 6371")),
+                show(word("pprop \"mars \"radius 3389")),
+                show(word("pprop \"mars \"color \"red")),
+                show(Value::List(vec![word("mars")])),
+                show(Value::List(vec![])),
+            ]
+        );
+    }
+
+    #[test]
+    fn evaluate_save_load_test() {
+        let path = std::env::temp_dir().join(format!("logo_save_test_{}.lgo", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let tokens = crate::lexer::Lexer::new(&format!(
+            "to square :size repeat 4 [fd :size rt 90] end \
+             to twice :n output :n * 2 end \
+             make \"size 25 make \"color \"red \
+             pprop \"earth \"moons [luna] \
+             save \"{}",
+            path
+        ))
+        .collect_tokens()
+        .unwrap();
+        let ast = crate::parser::Parser::new(&tokens).build_ast().unwrap();
+        Evaluator::new().evaluate_ast(&ast).unwrap();
+
+        let mut evaluator = Evaluator::new();
+        evaluator.load_file(path).unwrap();
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(evaluator.procedure_names(), vec!["square", "twice"]);
+        assert_eq!(evaluator.variable_names(), vec!["color", "size"]);
+        assert_eq!(
+            evaluator.globals["color"],
+            Some(Value::Word("red".to_string()))
+        );
+        assert_eq!(
+            evaluator.plists["earth"],
+            vec![(
+                "moons".to_string(),
+                Value::List(vec![Value::Word("luna".to_string())])
+            )]
+        );
+
+        match evaluator.load_file(path) {
+            Err(RuntimeError::FileError {
+                path: _,
+                message: _,
+            }) => (),
+            other => panic!("expected FileError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn evaluate_if_test() {
+        let forward = |n| Instruction {
+            command: Command::Forward,
+            args: vec![Value::Number(n)],
+        };
+        assert_eq!(
+            run("to countdown :n if :n > 0 [fd :n countdown :n - 1] end countdown 3").unwrap(),
+            vec![forward(3), forward(2), forward(1)]
+        );
+        assert_eq!(
+            run("ifelse \"a = \"b [fd 1] [fd 2] ifelse 1 + 1 <> 3 [fd 3] [fd 4] if 2 <= 1 [fd 5]")
+                .unwrap(),
+            vec![forward(2), forward(3)]
+        );
+        assert_eq!(
+            run("to sign :n ifelse :n < 0 [output -1] [output 1] end fd sign -5 show 1 >= 1")
+                .unwrap(),
+            vec![forward(-1), show(Value::boolean(true))]
+        );
+
+        for source in ["if 1 [fd 1]", "fd 1 < 2", "show \"a < 2"].iter() {
+            match run(source) {
+                Err(RuntimeError::InvalidInput { name: _, value: _ }) => (),
+                other => panic!("{}: expected InvalidInput, got {:?}", source, other),
+            }
+        }
+    }
+
+    #[test]
+    fn evaluate_run_test() {
+        let forward = |n| Instruction {
+            command: Command::Forward,
+            args: vec![Value::Number(n)],
+        };
+        let word = |w: &str| Value::Word(w.to_string());
+
+        assert_eq!(
+            run("make \"code [fd 10 repeat 2 [rt 90]] run :code")
+                .unwrap()
+                .len(),
+            3
+        );
+        assert_eq!(
+            run("make \"x 4 fd run [:x * 2] show runresult [sum 1 2] show runresult [fd 1]")
+                .unwrap(),
+            vec![
+                forward(8),
+                show(Value::List(vec![Value::Number(3)])),
+                forward(1),
+                show(Value::List(vec![])),
+            ]
+        );
+        assert_eq!(
+            run("to check :n run [if :n > 0 [output 1]] output 0 end fd check 5 fd check -5")
+                .unwrap(),
+            vec![forward(1), forward(0)]
+        );
+        assert_eq!(
+            run("fd apply \"sum [1 2 3] fd (invoke \"difference 5 2) apply \"fd [4]").unwrap(),
+            vec![forward(6), forward(3), forward(4)]
+        );
+        assert_eq!(
+            run("show [a \"b :c -:d [e 1]]").unwrap(),
+            vec![show(Value::List(vec![
+                word("a"),
+                word("\"b"),
+                word(":c"),
+                word("-:d"),
+                Value::List(vec![word("e"), Value::Number(1)]),
+            ]))]
+        );
+
+        match run("run [fd]") {
+            Err(RuntimeError::InvalidInstructionList { name, message: _ }) => {
+                assert_eq!(name, "run")
+            }
+            other => panic!("expected InvalidInstructionList, got {:?}", other),
+        }
+        match run("run [sum 1 2]") {
+            Err(RuntimeError::UnusedOutput { name }) => assert_eq!(name, "run"),
+            other => panic!("expected UnusedOutput, got {:?}", other),
+        }
+        match run("fd apply \"sum 5") {
+            Err(RuntimeError::InvalidInput { name, value: _ }) => assert_eq!(name, "apply"),
+            other => panic!("expected InvalidInput, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn evaluate_list_primitives_test() {
+        let list = |items: Vec<isize>| Value::List(items.into_iter().map(Value::Number).collect());
+        let word = |w: &str| Value::Word(w.to_string());
+        assert_eq!(
+            run(
+                "make \"xs [1 2 3] show first :xs show last :xs show bf :xs show bl :xs \
+                 show count :xs show item 2 :xs show fput 0 :xs show lput 4 :xs \
+                 show (list 1 2 3) show emptyp [] show first \"abc show bf \"abc"
+            )
+            .unwrap(),
+            vec![
+                show(Value::Number(1)),
+                show(Value::Number(3)),
+                show(list(vec![2, 3])),
+                show(list(vec![1, 2])),
+                show(Value::Number(3)),
+                show(Value::Number(2)),
+                show(list(vec![0, 1, 2, 3])),
+                show(list(vec![1, 2, 3, 4])),
+                show(list(vec![1, 2, 3])),
+                show(Value::boolean(true)),
+                show(word("a")),
+                show(word("bc")),
+            ]
+        );
+
+        let sources = [
+            "show first []",
+            "show item 4 [1 2 3]",
+            "show item 0 [1 2 3]",
+            "show item (-9223372036854775807 - 1) [1 2]",
+            "show fput 1 2",
+        ];
+        for source in sources.iter() {
+            match run(source) {
+                Err(RuntimeError::InvalidInput { name: _, value: _ }) => (),
+                other => panic!("{}: expected InvalidInput, got {:?}", source, other),
+            }
+        }
+    }
+
+    #[test]
+    fn evaluate_template_test() {
+        let list = |items: Vec<isize>| Value::List(items.into_iter().map(Value::Number).collect());
+        assert_eq!(
+            run("show map [? * 2] [1 2 3] \
+                 show (map [?1 + ?2] [1 2] [10 20]) \
+                 show map [[x] :x - 1] [1 2] \
+                 show map \"minus [1 2] \
+                 show filter [? > 1] [1 2 3] \
+                 show reduce \"sum [1 2 3] \
+                 show (reduce [?1 - ?2] [10 4] 1) \
+                 show crossmap [?1 * ?2] [[1 2] [10 100]] \
+                 show (crossmap [list ?1 ?2] [1] [2 3])")
+            .unwrap(),
+            vec![
+                show(list(vec![2, 4, 6])),
+                show(list(vec![11, 22])),
+                show(list(vec![0, 1])),
+                show(list(vec![-1, -2])),
+                show(list(vec![2, 3])),
+                show(Value::Number(6)),
+                show(Value::Number(7)),
+                show(list(vec![10, 100, 20, 200])),
+                show(Value::List(vec![list(vec![1, 2]), list(vec![1, 3])])),
+            ]
+        );
+
+        let instructions = run("foreach [[1 2] [3 4]] [setxy first ? last ?]").unwrap();
+        assert_eq!(
+            instructions,
+            vec![
+                Instruction {
+                    command: Command::SetXY,
+                    args: vec![Value::Number(1), Value::Number(2)],
+                },
+                Instruction {
+                    command: Command::SetXY,
+                    args: vec![Value::Number(3), Value::Number(4)],
+                },
+            ]
+        );
+
+        // errors in a template report the template they occurred in
+        match run("show map [? / 0] [1]") {
+            Err(RuntimeError::Template { template, error }) => {
+                assert_eq!(template, "[? / 0]");
+                match *error {
+                    RuntimeError::DivideByZero { .. } => (),
+                    other => panic!("expected DivideByZero, got {:?}", other),
+                }
+            }
+            other => panic!("expected Template, got {:?}", other),
+        }
+        match run("show ?") {
+            Err(RuntimeError::NotInTemplate { name }) => assert_eq!(name, "?"),
+            other => panic!("expected NotInTemplate, got {:?}", other),
+        }
+        match run("show (map [?1 + ?2] [1 2] [1])") {
+            Err(RuntimeError::InvalidInput { name, value: _ }) => assert_eq!(name, "map"),
+            other => panic!("expected InvalidInput, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn evaluate_import_test() {
+        let dir = std::env::temp_dir().join(format!("logo_import_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let modules = [
+            (
+                "shapes.lgo",
+                "show \"loading to square :size repeat 4 [fd :size rt 90] end",
+            ),
+            (
+                "boxes.logo",
+                "to side :size fd :size rt 90 end to square :size repeat 4 [side :size] end",
+            ),
+            (
+                "mixed.lgo",
+                "to Square :s fd :s end to twice :s square :s SQUARE :s end",
+            ),
+            (
+                "defaults.lgo",
+                "to five output 5 end to box [:size five] fd :size end",
+            ),
+            (
+                "runs.lgo",
+                "to side :n fd :n end to twice :n output :n * 2 end to draw run [side 1] \
+                 foreach map \"twice [1] [side ?] foreach [3] [[x] side :x] end",
+            ),
+            ("a.lgo", "import \"b"),
+            ("b.lgo", "import \"a"),
+        ];
+        for (file, source) in modules.iter() {
+            fs::write(dir.join(file), source).unwrap();
+        }
+
+        let mut evaluator = Evaluator::new();
+        evaluator.add_search_path(dir.clone());
+        let mut run_in = |source: &str| {
+            let tokens = crate::lexer::Lexer::new(source).collect_tokens().unwrap();
+            let ast = crate::parser::Parser::with_arities(&tokens, evaluator.procedure_arities())
+                .build_ast()
+                .unwrap();
+            evaluator.evaluate_ast(&ast)
+        };
+
+        // modules are only run once
+        assert_eq!(
+            run_in("import \"shapes import \"shapes").unwrap(),
+            vec![show(Value::Word("loading".to_string()))]
+        );
+
+        // both modules declare square
+        match run_in("import \"boxes") {
+            Err(RuntimeError::RedeclaredProcedure { name }) => assert_eq!(name, "square"),
+            other => panic!("expected RedeclaredProcedure, got {:?}", other),
+        }
+        assert_eq!(run_in("(import \"boxes \"b) b.square 10").unwrap().len(), 8);
+
+        // calls are renamed whatever case the procedure is called with
+        assert_eq!(
+            run_in("(import \"mixed \"x) x.twice 3 X.Square 3")
+                .unwrap()
+                .len(),
+            3
+        );
+        // and so are calls in lists run as code, and procedures named by words
+        let forward = |n| Instruction {
+            command: Command::Forward,
+            args: vec![Value::Number(n)],
+        };
+        assert_eq!(
+            run_in("(import \"runs \"r) r.draw").unwrap(),
+            vec![forward(1), forward(2), forward(3)]
+        );
+
+        // and so are calls in the default values of optional inputs
+        assert_eq!(
+            run_in("(import \"defaults \"d) d.box").unwrap(),
+            vec![forward(5)]
+        );
+
+        match run_in("import \"a") {
+            Err(RuntimeError::ImportCycle { chain }) => assert_eq!(chain.len(), 3),
+            other => panic!("expected ImportCycle, got {:?}", other),
+        }
+        match run_in("import \"missing") {
+            Err(RuntimeError::ModuleNotFound { name }) => assert_eq!(name, "missing"),
+            other => panic!("expected ModuleNotFound, got {:?}", other),
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn evaluate_error_location_test() {
+        let mut evaluator = Evaluator::new();
+        let source = "to inner :x\nfd :x / 0\nend\nto outer\nrt 90 inner 5\nend\nfd 10\nouter";
+        let ast = crate::parse_program(source, HashMap::new(), Naming::default()).unwrap();
+        let error = evaluator.evaluate_ast(&ast).unwrap_err();
+        let error = evaluator.locate_error(error);
+        assert_eq!(
+            error.to_string(),
+            "'/' can't divide 5 by zero\n  in inner 5, line 2\n  called from outer, line 5\n  \
+             called from top level, line 8"
+        );
+        match error {
+            LogoError::Runtime {
+                error: RuntimeError::DivideByZero { .. },
+                span: Some(span),
+                call_stack,
+            } => {
+                assert_eq!(&source[span.start..span.end], "fd :x / 0");
+                let calls: Vec<_> = call_stack.iter().map(|frame| frame.to_string()).collect();
+                assert_eq!(calls, vec!["outer", "inner 5"]);
+                let lines: Vec<_> = call_stack
+                    .iter()
+                    .map(|frame| frame.span.unwrap().line)
+                    .collect();
+                assert_eq!(lines, vec![8, 5]);
+            }
+            other => panic!("expected DivideByZero, got {:?}", other),
+        }
+
+        // the location doesn't outlive the error, and the procedure and counts are
+        // part of the error
+        let source = "catch \"error [outer] apply \"setxy [1]";
+        let ast = crate::parse_program(source, HashMap::new(), Naming::default()).unwrap();
+        let error = evaluator.evaluate_ast(&ast).unwrap_err();
+        match evaluator.locate_error(error) {
+            error @ LogoError::Runtime { .. } => {
+                assert_eq!(error.code(), 6);
+                assert_eq!(error.span().map(|span| span.column), Some(22));
+                assert!(error.call_stack().is_empty());
+                match error {
+                    LogoError::Runtime {
+                        error:
+                            RuntimeError::ArgCountMismatch {
+                                name,
+                                expected: 2,
+                                given: 1,
+                            },
+                        ..
+                    } => assert_eq!(name, "setxy"),
+                    other => panic!("expected ArgCountMismatch, got {:?}", other),
+                }
+            }
+            other => panic!("expected a runtime error, got {:?}", other),
+        }
+
+        // only the ends of long traces are shown
+        let source = "to down :n\nif :n > 0 [down :n - 1]\nfd 1 / 0\nend\ndown 20";
+        let ast = crate::parse_program(source, HashMap::new(), Naming::default()).unwrap();
+        let error = evaluator.evaluate_ast(&ast).unwrap_err();
+        let message = evaluator.locate_error(error).to_string();
+        let trace: Vec<_> = message.lines().skip(1).collect();
+        assert_eq!(trace.len(), 12);
+        assert_eq!(trace[0], "  in down 0, line 3");
+        assert_eq!(trace[4], "  called from down 4, line 2");
+        assert_eq!(trace[5], "  ... 11 more calls");
+        assert_eq!(trace[6], "  called from down 16, line 2");
+        assert_eq!(trace[11], "  called from top level, line 5");
+    }
+
+    #[test]
+    fn evaluate_suggestion_test() {
+        let message = |source: &str| run(source).unwrap_err().to_string();
+
+        // primitives, keywords and procedures are suggested for misspelled calls
+        assert_eq!(
+            message("foward 10"),
+            "Procedure 'foward' does not exist; did you mean 'forward'?"
+        );
+        assert_eq!(
+            message("repaet 4"),
+            "Procedure 'repaet' does not exist; did you mean 'repeat'?"
+        );
+        assert_eq!(
+            message("to square :size\nfd :size\nend\nsqaure 10"),
+            "Procedure 'sqaure' does not exist; did you mean 'square'?"
+        );
+        assert_eq!(message("zigzag 10"), "Procedure 'zigzag' does not exist");
+
+        // variables in scope are suggested, including locals of callers
+        assert_eq!(
+            message("make \"length 5\nfd :lenght"),
+            "Variable :lenght has not been declared; did you mean :length?"
+        );
+        assert_eq!(
+            message("to inner\nfd :sise\nend\nto outer :size\ninner\nend\nouter 5"),
+            "Variable :sise has not been declared; did you mean :size?"
+        );
+        assert_eq!(
+            message("make \"y 1 fd :x"),
+            "Variable :x has not been declared"
+        );
+
+        // names given to erase and trace are only compared with procedures
+        assert_eq!(message("erase \"fd"), "Procedure 'fd' does not exist");
+        assert_eq!(edit_distance("Forward", "forward"), 0);
+        assert_eq!(edit_distance("ab", "ba"), 1);
+    }
+
+    #[test]
+    fn evaluate_case_test() {
+        let word = |w: &str| Value::Word(w.to_string());
+        let forward = || Instruction {
+            command: Command::Forward,
+            args: vec![Value::Number(10)],
+        };
+
+        // names match in any case, and are printed as they were first spelled
+        assert_eq!(
+            run("to Square :Size REPEAT 2 [FD :size] End SQUARE 10 \
+                 make \"Len 5 make \"LEN 6 show :len show procedures show names \
+                 pprop \"Mars \"Radius 3390 show gprop \"MARS \"radius show plists")
+            .unwrap(),
+            vec![
+                forward(),
+                forward(),
+                show(Value::Number(6)),
+                show(Value::List(vec![word("Square")])),
+                show(Value::List(vec![word("Len")])),
+                show(Value::Number(3390)),
+                show(Value::List(vec![word("Mars")])),
+            ]
+        );
+
+        // so do catch tags and the words true and false
+        assert_eq!(
+            run(
+                "if \"TRUE [fd 10] ifelse \"False [fd 20] [show catch \"X [(throw \"x 5)]] \
+                 catch \"ERROR [fd first []] catch \"error [throw \"Error] show first error"
+            )
+            .unwrap(),
+            vec![forward(), show(Value::Number(5)), show(Value::Number(21))]
+        );
+        let strict = |source: &str| {
+            let mut evaluator = Evaluator::new();
+            evaluator.set_case_sensitive(true);
+            let naming = Naming {
+                case_sensitive: true,
+                ..Naming::default()
+            };
+            let ast = crate::parse_program(source, HashMap::new(), naming).unwrap();
+            evaluator.evaluate_ast(&ast)
+        };
+        match strict("FD 10") {
+            Err(RuntimeError::ProcedureNotFound { name, suggestion }) => {
+                assert_eq!((name.as_str(), suggestion), ("FD", Some("fd".to_string())));
+            }
+            other => panic!("expected ProcedureNotFound, got {:?}", other),
+        }
+        match strict("make \"a 1 fd :A") {
+            Err(RuntimeError::VariableNotFound { name, .. }) => assert_eq!(name, "A"),
+            other => panic!("expected VariableNotFound, got {:?}", other),
+        }
+        assert!(strict("to Sq end to sq fd 1 end Sq sq").is_ok());
+        match strict("if \"TRUE [fd 10]") {
+            Err(RuntimeError::InvalidInput { name, value }) => {
+                assert_eq!((name.as_str(), value.as_str()), ("if", "TRUE"))
+            }
+            other => panic!("expected InvalidInput, got {:?}", other),
+        }
+        match strict("catch \"X [throw \"x]") {
+            Err(RuntimeError::Throw { tag, value: None }) => assert_eq!(tag, "x"),
+            other => panic!("expected Throw, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn evaluate_depth_test() {
+        let run_to_depth = |source: &str, depth| {
+            let tokens = crate::lexer::Lexer::new(source).collect_tokens().unwrap();
+            let ast = crate::parser::Parser::new(&tokens).build_ast().unwrap();
+            let mut evaluator = Evaluator::new();
+            evaluator.set_max_depth(depth);
+            evaluator.evaluate_ast(&ast)
+        };
+        let down = "to down :n if :n > 0 [down :n - 1] end ";
+
+        // calls may nest as deep as the limit
+        assert_eq!(
+            run_to_depth(&format!("{}down 19 fd 1", down), 20)
+                .unwrap()
+                .len(),
+            1
+        );
+        match run_to_depth(&format!("{}down 20", down), 20) {
+            Err(e @ RuntimeError::StackOverflow { .. }) => assert_eq!(
+                e.to_string(),
+                "Stack overflow calling 'down', calls are nested 20 deep"
+            ),
+            other => panic!("expected StackOverflow, got {:?}", other),
+        }
+
+        // recursion through primitives that run code, with or without procedures
+        match run_to_depth("make \"x [run :x] run :x", 20) {
+            Err(RuntimeError::StackOverflow { name, depth }) => {
+                assert_eq!((name.as_str(), depth), ("run", 20))
+            }
+            other => panic!("expected StackOverflow, got {:?}", other),
+        }
+        match run_to_depth("to each :n foreach [1] [each :n + 1] end each 1", 20) {
+            Err(RuntimeError::StackOverflow { name, depth: _ }) => assert_eq!(name, "each"),
+            other => panic!("expected StackOverflow, got {:?}", other),
+        }
+
+        // the error can be caught, and calls nest as deep again afterwards
+        assert_eq!(
+            run_to_depth(
+                &format!(
+                    "{}catch \"error [down 50] show first error down 19 fd 1",
+                    down
+                ),
+                20
+            )
+            .unwrap(),
+            vec![
+                show(Value::Number(2)),
+                Instruction {
+                    command: Command::Forward,
+                    args: vec![Value::Number(1)],
+                },
+            ]
+        );
+
+        // runaway recursion stops at the default limit rather than overflowing the
+        // 8 MB native stack of a main thread
+        let runaway = std::thread::Builder::new()
+            .stack_size(8 * 1024 * 1024)
+            .spawn(|| {
+                let forever = run("to forever :n forever :n + 1 end forever 1");
+                let each = run("to each :n foreach [1] [each :n + 1] end each 1");
+                vec![forever, each]
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+        for result in runaway {
+            match result {
+                Err(RuntimeError::StackOverflow { name: _, depth }) => {
+                    assert_eq!(depth, MAX_DEPTH)
+                }
+                other => panic!("expected StackOverflow, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn evaluate_locale_test() {
+        let french = |source: &str| {
+            let mut evaluator = Evaluator::new();
+            evaluator.set_locale(Locale::French);
+            let ast = crate::parse_program(source, HashMap::new(), evaluator.naming()).unwrap();
+            evaluator.evaluate_ast(&ast)
+        };
+        let forward = || Instruction {
+            command: Command::Forward,
+            args: vec![Value::Number(10)],
+        };
+
+        // translated names are used alongside the English ones
+        assert_eq!(
+            french("pour carré :côté répète 2 [avance :côté] fin carré 10 FD 10").unwrap(),
+            vec![forward(), forward(), forward()]
+        );
+
+        // suggestions include translated names, and errors are reported in French
+        match french("avanse 10") {
+            Err(e @ RuntimeError::ProcedureNotFound { .. }) => assert_eq!(
+                e.message(Locale::French),
+                "La procédure 'avanse' n'existe pas ; vouliez-vous dire 'avance' ?"
+            ),
+            other => panic!("expected ProcedureNotFound, got {:?}", other),
+        }
+
+        // variables and property lists named like a translated primitive aren't
+        // the variable named like the English primitive
+        let word = |w: &str| show(Value::Word(w.to_string()));
+        let source = "make \"av 1 make \"forward 2 print :av print :forward \
+                      pprop \"av \"x 3 pprop \"forward \"x 4 print gprop \"av \"x \
+                      print names";
+        assert_eq!(
+            french(source).unwrap(),
+            vec![word("1"), word("2"), word("3"), word("av forward")]
+        );
+    }
+
+    #[test]
+    fn evaluate_ucblogo_test() {
+        let ucblogo = |source: &str| {
+            let mut evaluator = Evaluator::new();
+            evaluator.set_dialect(Dialect::UCBLogo);
+            let ast = crate::parse_program(source, HashMap::new(), evaluator.naming()).unwrap();
+            evaluator.evaluate_ast(&ast)
+        };
+        let word = |w: &str| show(Value::Word(w.to_string()));
+
+        // optional inputs take their default unless given, which may use the inputs
+        // before them, and the rest input takes any extra inputs as a list
+        let source = "to poly :sides [:size :sides * 10] [:rest] ; a title line\n\
+                      print (list :sides :size :rest)\n\
+                      end\n\
+                      poly 3 (poly 4) (poly 5 6) (poly 7 8 9 10)";
+        assert_eq!(
+            ucblogo(source).unwrap(),
+            vec![
+                word("3 30 []"),
+                word("4 40 []"),
+                word("5 6 []"),
+                word("7 8 [9 10]")
+            ]
+        );
+        assert_eq!(
+            ucblogo("to f :a [:b :a + 1] [:c] end pots").unwrap(),
+            vec![word("to f :a [:b :a + 1] [:c]")]
+        );
+        match ucblogo("to f :a [:b 1] end (f)") {
+            Err(RuntimeError::ArgCountMismatch {
+                expected, given, ..
+            }) => {
+                assert_eq!((expected, given), (1, 0))
+            }
+            other => panic!("expected ArgCountMismatch, got {:?}", other),
+        }
+        match ucblogo("to f :a [:b 1] end (f 1 2 3)") {
+            Err(RuntimeError::ArgCountMismatch {
+                expected, given, ..
+            }) => {
+                assert_eq!((expected, given), (2, 3))
+            }
+            other => panic!("expected ArgCountMismatch, got {:?}", other),
+        }
+
+        // UCBLogo names of primitives, and names with `?`
+        assert_eq!(
+            ucblogo("to even? :n output 0 = remainder :n 2 end print even? 4 print empty? []")
+                .unwrap(),
+            vec![word("true"), word("true")]
+        );
+        assert_eq!(
+            run("print [a [b c]] show [a [b c]]").unwrap(),
+            vec![
+                word("a [b c]"),
+                show(Value::List(vec![
+                    Value::Word("a".to_string()),
+                    Value::List(vec![
+                        Value::Word("b".to_string()),
+                        Value::Word("c".to_string())
+                    ]),
+                ])),
+            ]
+        );
+    }
+
+    #[test]
+    fn evaluate_default_arity_test() {
+        let word = |w: &str| show(Value::Word(w.to_string()));
+
+        // unparenthesized calls take the default number of inputs, parenthesized
+        // ones take any number the title line allows
+        let source = "to poly :sides [:size 50] [:rest] 2\n\
+                      print (list :sides :size :rest)\n\
+                      end\n\
+                      poly 5 30 (poly 5) (poly 5 30 40 50)";
+        assert_eq!(
+            run(source).unwrap(),
+            vec![word("5 30 []"), word("5 50 []"), word("5 30 [40 50]")]
+        );
+        assert_eq!(
+            run("to f :a [:b 1] [:c] 2 end pots").unwrap(),
+            vec![word("to f :a [:b 1] [:c] 2")]
+        );
     }
 }
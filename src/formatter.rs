@@ -0,0 +1,419 @@
+use std::collections::HashMap;
+
+use crate::command::Command;
+use crate::lexer::Operator;
+use crate::locale::Naming;
+use crate::parser::{Expression, Statement, AST};
+use crate::primitive::Primitive;
+use crate::value::Value;
+
+const INDENT: &str = "    ";
+
+// repeat bodies are kept on the same line as their repeat if the line fits
+const MAX_LINE_WIDTH: usize = 80;
+
+// operands that are not operators bind tighter than any operator
+const OPERAND_PRECEDENCE: usize = 4;
+
+/// Converts an AST back into canonical Logo source. Procedure bodies and long
+/// repeat bodies are indented, procedure declarations are separated by blank lines,
+/// and parentheses are only emitted where they are needed to parse back into the
+/// same AST.
+///
+/// `arities` holds the arities of user defined procedures that are declared
+/// outside of `ast`. Calls to procedures with an unknown arity are parenthesized.
+/// `naming` tells how names are matched to primitives.
+pub fn format(ast: &AST, mut arities: HashMap<String, usize>, naming: Naming) -> String {
+    for stmt in ast.statements.iter() {
+        if let Statement::ProcedureDeclaration {
+            name,
+            params,
+            default_arity,
+            ..
+        } = stmt
+        {
+            arities.insert(name.to_string(), default_arity.unwrap_or(params.len()));
+        }
+    }
+
+    let formatter = Formatter { arities, naming };
+    let mut output = String::new();
+    for (i, stmt) in ast.statements.iter().enumerate() {
+        if i > 0 && (is_declaration(stmt) || is_declaration(&ast.statements[i - 1])) {
+            output.push('\n');
+        }
+        formatter.write_statement(stmt, 0, &mut output);
+        output.push('\n');
+    }
+    output
+}
+
+fn is_declaration(stmt: &Statement) -> bool {
+    matches!(stmt, Statement::ProcedureDeclaration { .. })
+}
+
+struct Formatter {
+    arities: HashMap<String, usize>,
+    naming: Naming,
+}
+
+/// An arithmetic expression rebuilt as a tree from its postfix notation
+enum Node<'a> {
+    Operand(&'a Expression),
+    Negation(Box<Node<'a>>),
+    Binary(&'a Operator, Box<Node<'a>>, Box<Node<'a>>),
+}
+
+impl<'a> Node<'a> {
+    fn from_postfix(postfix: &'a [Expression]) -> Option<Self> {
+        let mut stack: Vec<Node> = Vec::new();
+        for expr in postfix.iter() {
+            match expr {
+                Expression::Operator {
+                    op: Operator::Negation,
+                } => {
+                    let operand = stack.pop()?;
+                    stack.push(Node::Negation(Box::new(operand)));
+                }
+                Expression::Operator { op } => {
+                    let operand_2 = stack.pop()?;
+                    let operand_1 = stack.pop()?;
+                    stack.push(Node::Binary(op, Box::new(operand_1), Box::new(operand_2)));
+                }
+                _ => stack.push(Node::Operand(expr)),
+            }
+        }
+
+        match (stack.pop(), stack.is_empty()) {
+            (Some(node), true) => Some(node),
+            _ => None,
+        }
+    }
+
+    fn precedence(&self) -> usize {
+        match self {
+            Node::Operand(_) => OPERAND_PRECEDENCE,
+            Node::Negation(_) => Operator::Negation.precedence(),
+            Node::Binary(op, _, _) => op.precedence(),
+        }
+    }
+}
+
+impl Formatter {
+    fn write_statement(&self, stmt: &Statement, depth: usize, output: &mut String) {
+        let indent = INDENT.repeat(depth);
+        output.push_str(&indent);
+
+        match stmt {
+            Statement::Repeat { .. } | Statement::If { .. } => {
+                // bracketed bodies stay on the statement's line if it fits
+                if let Some(line) = self.inline_statement(stmt) {
+                    if indent.len() + line.len() <= MAX_LINE_WIDTH {
+                        output.push_str(&line);
+                        return;
+                    }
+                }
+
+                let (header, bodies) = match stmt {
+                    Statement::Repeat { count, body } => (
+                        format!("repeat {}", self.expression(count, true)),
+                        vec![body],
+                    ),
+                    Statement::If {
+                        condition,
+                        body,
+                        else_body: Some(else_body),
+                    } => (
+                        format!("ifelse {}", self.expression(condition, true)),
+                        vec![body, else_body],
+                    ),
+                    Statement::If {
+                        condition, body, ..
+                    } => (
+                        format!("if {}", self.expression(condition, true)),
+                        vec![body],
+                    ),
+                    _ => unreachable!(),
+                };
+
+                output.push_str(&header);
+                for (i, body) in bodies.into_iter().enumerate() {
+                    output.push_str(if i == 0 { " [\n" } else { "] [\n" });
+                    self.write_block(&body.statements, depth + 1, output);
+                    output.push_str(&indent);
+                }
+                output.push(']');
+            }
+            Statement::ProcedureDeclaration {
+                name,
+                body,
+                params,
+                optional,
+                rest,
+                default_arity,
+            } => {
+                output.push_str("to ");
+                output.push_str(name);
+                for param in params.iter() {
+                    output.push_str(" :");
+                    output.push_str(param);
+                }
+                for (param, default) in optional.iter() {
+                    output.push_str(&format!(" [:{} {}]", param, self.expression(default, true)));
+                }
+                if let Some(rest) = rest {
+                    output.push_str(&format!(" [:{}]", rest));
+                }
+                if let Some(arity) = default_arity {
+                    output.push_str(&format!(" {}", arity));
+                }
+                output.push('\n');
+                self.write_block(&body.statements, depth + 1, output);
+                output.push_str(&indent);
+                output.push_str("end");
+            }
+            _ => output.push_str(&self.inline_statement(stmt).unwrap_or_default()),
+        }
+    }
+
+    fn write_block(&self, statements: &[Statement], depth: usize, output: &mut String) {
+        for stmt in statements.iter() {
+            self.write_statement(stmt, depth, output);
+            output.push('\n');
+        }
+    }
+
+    /// Returns the statements on a single line separated by spaces, or None if one
+    /// of them can't be written on a single line.
+    fn inline_block(&self, statements: &[Statement]) -> Option<String> {
+        let mut line = String::new();
+        for (i, stmt) in statements.iter().enumerate() {
+            if i > 0 {
+                line.push(' ');
+            }
+            line.push_str(&self.inline_statement(stmt)?);
+        }
+        Some(line)
+    }
+
+    fn inline_statement(&self, stmt: &Statement) -> Option<String> {
+        match stmt {
+            Statement::Repeat { count, body } => Some(format!(
+                "repeat {} [{}]",
+                self.expression(count, true),
+                self.inline_block(&body.statements)?
+            )),
+            Statement::If {
+                condition,
+                body,
+                else_body: Some(else_body),
+            } => Some(format!(
+                "ifelse {} [{}] [{}]",
+                self.expression(condition, true),
+                self.inline_block(&body.statements)?,
+                self.inline_block(&else_body.statements)?
+            )),
+            Statement::If {
+                condition, body, ..
+            } => Some(format!(
+                "if {} [{}]",
+                self.expression(condition, true),
+                self.inline_block(&body.statements)?
+            )),
+            Statement::VariableDeclaration { name, val } => {
+                Some(format!("make \"{} {}", name, self.expression(val, true)))
+            }
+            Statement::ProcedureDeclaration { .. } => None,
+            Statement::ProcedureCall { name, args } => Some(self.call(name, args, true)),
+            Statement::Output { val } => Some(format!("output {}", self.expression(val, true))),
+            Statement::Stop => Some("stop".to_string()),
+        }
+    }
+
+    /// Formats a procedure call, parenthesized if it takes a number of arguments
+    /// other than its arity or if it is not `rightmost`. A call is rightmost when no
+    /// binary operator follows it, otherwise the operator would become part of its
+    /// last argument.
+    fn call(&self, name: &str, args: &[Expression], rightmost: bool) -> String {
+        let key = self.naming.key(name);
+        let arity = if let Some(command) = Command::from_string(&key) {
+            Some(command.arity())
+        } else if let Some(primitive) = Primitive::from_string(&key) {
+            Some(primitive.arity())
+        } else {
+            self.arities
+                .get(name)
+                .or_else(|| self.arities.get(&key))
+                .copied()
+        };
+
+        let mut text = name.to_string();
+        for arg in args.iter() {
+            text.push(' ');
+            text.push_str(&self.expression(arg, true));
+        }
+
+        let needs_parens = match arity {
+            Some(arity) => arity != args.len() || (!rightmost && !args.is_empty()),
+            None => !args.is_empty(),
+        };
+        if needs_parens {
+            format!("({})", text)
+        } else {
+            text
+        }
+    }
+
+    fn expression(&self, expr: &Expression, rightmost: bool) -> String {
+        match expr {
+            Expression::ArithmeticExpression { postfix } => match Node::from_postfix(postfix) {
+                Some(node) => self.node(&node, rightmost),
+                // keep malformed expressions, which can only be built by hand, readable
+                None => postfix
+                    .iter()
+                    .map(|expr| self.expression(expr, false))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            },
+            Expression::Operator { op } => op.literal().to_string(),
+            Expression::Number { val } => val.to_string(),
+            Expression::Word { literal } => format!("\"{}", literal),
+            Expression::Variable { name } => format!(":{}", name),
+            Expression::List { items } => Value::List(items.clone()).to_string(),
+            Expression::ProcedureCall { name, args } => self.call(name, args, rightmost),
+        }
+    }
+
+    fn node(&self, node: &Node, rightmost: bool) -> String {
+        match node {
+            Node::Operand(expr) => self.expression(expr, rightmost),
+            Node::Negation(operand) => {
+                let needs_parens = operand.precedence() < Operator::Negation.precedence();
+                format!("-{}", self.operand(operand, needs_parens, rightmost))
+            }
+            Node::Binary(op, operand_1, operand_2) => {
+                // operators are left associative, so an operand of the same precedence
+                // on the right hand side has to be parenthesized
+                let left = self.operand(operand_1, operand_1.precedence() < op.precedence(), false);
+                let right = self.operand(
+                    operand_2,
+                    operand_2.precedence() <= op.precedence(),
+                    rightmost,
+                );
+                format!("{} {} {}", left, op.literal(), right)
+            }
+        }
+    }
+
+    fn operand(&self, node: &Node, needs_parens: bool, rightmost: bool) -> String {
+        if needs_parens {
+            format!("({})", self.node(node, true))
+        } else {
+            self.node(node, rightmost)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn parse(source: &str) -> AST {
+        let tokens = Lexer::new(source).collect_tokens().unwrap();
+        Parser::new(&tokens).build_ast().unwrap()
+    }
+
+    // formats `source` and checks both the output and that it parses back into
+    // the same AST
+    fn format_test(source: &str, expected: &str) {
+        let ast = parse(source);
+        let formatted = format(&ast, HashMap::new(), Naming::default());
+        assert_eq!(formatted, expected);
+        assert_eq!(parse(&formatted), ast);
+    }
+
+    #[test]
+    fn format_procedure_test() {
+        format_test(
+            "to square :size repeat 4 [ fd :size rt 90 ] end square 10 fd 20",
+            "to square :size\n    repeat 4 [fd :size rt 90]\nend\n\nsquare 10\nfd 20\n",
+        );
+    }
+
+    #[test]
+    fn format_long_repeat_test() {
+        format_test(
+            "repeat 36 [ repeat 4 [ fd 100 rt 90 ] rt 10 setpencolor 255 0 0 \
+             setscreencolor 0 0 255 fd 10 bk 10 ]",
+            "repeat 36 [\n    repeat 4 [fd 100 rt 90]\n    rt 10\n    setpencolor 255 0 0\n    \
+             setscreencolor 0 0 255\n    fd 10\n    bk 10\n]\n",
+        );
+    }
+
+    #[test]
+    fn format_minimal_parens_test() {
+        format_test("fd (((1 + 2)) * (3))", "fd (1 + 2) * 3\n");
+        format_test("fd (1 * 2) + (3 / 4)", "fd 1 * 2 + 3 / 4\n");
+        format_test("fd 1 - (2 - 3) - 4", "fd 1 - (2 - 3) - 4\n");
+        format_test("fd -(:x * 2) rt -(:y)", "fd -(:x * 2)\nrt -:y\n");
+    }
+
+    #[test]
+    fn format_nested_call_test() {
+        format_test("fd (sum 1 2)", "fd sum 1 2\n");
+        format_test("fd (sum 1 2 3)", "fd (sum 1 2 3)\n");
+        format_test("fd (sum 1 2) * 3", "fd (sum 1 2) * 3\n");
+        format_test("fd 3 * (sum 1 2)", "fd 3 * sum 1 2\n");
+        format_test("setxy (sum 1 2) 3", "setxy sum 1 2 3\n");
+        format_test("(unknown 1 2)", "(unknown 1 2)\n");
+        format_test(
+            "to double :x output :x * 2 end fd double 2",
+            "to double :x\n    output :x * 2\nend\n\nfd double 2\n",
+        );
+    }
+
+    #[test]
+    fn format_optional_inputs_test() {
+        format_test(
+            "to poly :sides [ :size (:sides * 10) ] [:rest] 2 fd :size end poly 5 30 (poly 5)",
+            "to poly :sides [:size :sides * 10] [:rest] 2\n    fd :size\nend\n\n\
+             poly 5 30\n(poly 5)\n",
+        );
+    }
+
+    #[test]
+    fn format_if_test() {
+        format_test(
+            "if (:n < 1) [stop] ifelse :n = 2 + 1 [fd 1] [bk 1]",
+            "if :n < 1 [stop]\nifelse :n = 2 + 1 [fd 1] [bk 1]\n",
+        );
+        format_test("fd 1 + (2 < 3)", "fd 1 + (2 < 3)\n");
+        format_test(
+            "ifelse :size > 100 [ repeat 4 [ fd :size rt 90 ] setpencolor 255 0 0 ] \
+             [ setpencolor 0 0 255 repeat 3 [ fd :size rt 120 ] ]",
+            "ifelse :size > 100 [\n    repeat 4 [fd :size rt 90]\n    setpencolor 255 0 0\n] [\n    \
+             setpencolor 0 0 255\n    repeat 3 [fd :size rt 120]\n]\n",
+        );
+    }
+
+    #[test]
+    fn format_list_test() {
+        format_test("show [ a  \"b [ :c -:d ] ]", "show [a \"b [:c -:d]]\n");
+        format_test(
+            "run [repeat 4 [fd 10 rt 90]] show []",
+            "run [repeat 4 [fd 10 rt 90]]\nshow []\n",
+        );
+    }
+
+    #[test]
+    fn format_idempotent_test() {
+        let source = "to spiral :size :angle\n    \
+                      repeat 4 [fd -:size * (2 + 3) rt (sum :angle 1 2)]\n    \
+                      make \"size -(:size - 1) / 2\n    \
+                      output :size\n\
+                      end\n";
+        format_test(source, source);
+    }
+}
@@ -1,11 +1,14 @@
 use std::collections::HashMap;
 use std::default::Default;
-use std::mem;
 use std::fmt;
 #[allow(unused_imports)]
 use std::iter::FromIterator;
+use std::mem;
+use std::sync::OnceLock;
 
+use crate::dialect::Dialect;
 use crate::error::LexError;
+use crate::locale::Naming;
 
 use regex::Regex;
 
@@ -15,6 +18,13 @@ pub enum Operator {
     Subtraction,
     Multiplication,
     Division,
+    Negation,
+    LessThan,
+    GreaterThan,
+    LessEqual,
+    GreaterEqual,
+    Equal,
+    NotEqual,
 }
 
 impl Operator {
@@ -22,8 +32,10 @@ impl Operator {
         use Operator::*;
 
         match self {
+            Negation => 3,
             Multiplication | Division => 2,
             Addition | Subtraction => 1,
+            LessThan | GreaterThan | LessEqual | GreaterEqual | Equal | NotEqual => 0,
         }
     }
 
@@ -31,9 +43,15 @@ impl Operator {
         use Operator::*;
         match self {
             Addition => "+",
-            Subtraction => "-",
+            Subtraction | Negation => "-",
             Multiplication => "*",
             Division => "/",
+            LessThan => "<",
+            GreaterThan => ">",
+            LessEqual => "<=",
+            GreaterEqual => ">=",
+            Equal => "=",
+            NotEqual => "<>",
         }
     }
 }
@@ -51,6 +69,10 @@ pub enum Token {
     Make,
     To,
     End,
+    Output,
+    Stop,
+    If,
+    IfElse,
 
     LBracket,
     RBracket,
@@ -84,6 +106,10 @@ impl Token {
             Make => "make",
             To => "to",
             End => "end",
+            Output => "output",
+            Stop => "stop",
+            If => "if",
+            IfElse => "ifelse",
             LBracket => "[",
             RBracket => "]",
             LParen => "(",
@@ -102,6 +128,32 @@ impl Token {
     }
 }
 
+/// The location of a token or statement in the source it was read from. `start`
+/// and `end` are byte offsets, `line` and `column` locate `start` and count from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    /// Returns the span from the start of `self` to the end of `other`.
+    pub fn to(&self, other: &Span) -> Span {
+        Span {
+            end: other.end,
+            ..*self
+        }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(formatter, "line {}, column {}", self.line, self.column)
+    }
+}
+
 impl fmt::Display for Token {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         write!(formatter, "{}", self.to_string())
@@ -119,6 +171,11 @@ fn get_keywords() -> HashMap<String, Token> {
     keywords.insert("make".to_string(), Token::Make);
     keywords.insert("to".to_string(), Token::To);
     keywords.insert("end".to_string(), Token::End);
+    keywords.insert("output".to_string(), Token::Output);
+    keywords.insert("op".to_string(), Token::Output);
+    keywords.insert("stop".to_string(), Token::Stop);
+    keywords.insert("if".to_string(), Token::If);
+    keywords.insert("ifelse".to_string(), Token::IfElse);
 
     keywords
 }
@@ -138,14 +195,74 @@ impl TokenDef {
 }
 
 const NUMBER_REGEX: &str = r"^-?[0-9]+";
-const WORD_REGEX: &str = r#"^"[a-zA-Z][0-9a-zA-Z_]*"#;
-const VARIABLE_REGEX: &str = r"^:[a-zA-Z][0-9a-zA-Z_]*";
-const IDENT_REGEX: &str = r"^[a-zA-Z][0-9a-zA-Z_]*";
+// a quoted word extends up to the next whitespace, bracket or parenthesis, so that
+// words like "file.lgo can be written
+const WORD_REGEX: &str = r#"^"[^\s\[\]()]+"#;
+// names start with a letter of any alphabet, as in `vorwärts`
+const VARIABLE_REGEX: &str = r"^:\p{L}[\p{L}\p{N}_]*";
+// `?`, `?1`, `?2`... are the inputs of a template, see `map`
+const IDENT_REGEX: &str = r"^(\?[0-9]*|\p{L}[\p{L}\p{N}_.]*)";
+// in UCBLogo `\` and `|...|` put any character in a word, and `"` on its own is
+// the empty word
+const UCBLOGO_WORD_REGEX: &str = r#"^"(\\.|\|[^|]*\||[^\s\[\]()\\|])*"#;
+// in UCBLogo names may also contain `?`, as in `empty?` and `:done?`
+const UCBLOGO_VARIABLE_REGEX: &str = r"^:\p{L}[\p{L}\p{N}_?]*";
+const UCBLOGO_IDENT_REGEX: &str = r"^(\?[0-9]*|\p{L}[\p{L}\p{N}_.?]*)";
+
+// a character of a word in a UCBLogo list that doesn't start a token, or an
+// escaped one, see `Dialect::has_list_words`
+const LIST_PIECE_REGEX: &str = r"^(\\.|\|[^|]*\||[^\s\[\]])";
+
+const WHITESPACE_REGEX: &str = r"^[\n\t\x20]*";
+// a `;` comment runs to the end of its line
+const COMMENT_WHITESPACE_REGEX: &str = r"^([\n\t\x20]|;[^\n]*)*";
+
+// the definitions are only built once for each dialect, as compiling the regular
+// expressions for names in any alphabet is slow
+fn token_definitions(dialect: Dialect) -> &'static [TokenDef] {
+    static DEFINITIONS: OnceLock<Vec<TokenDef>> = OnceLock::new();
+    static UCBLOGO_DEFINITIONS: OnceLock<Vec<TokenDef>> = OnceLock::new();
+    if dialect.has_predicate_names() {
+        UCBLOGO_DEFINITIONS.get_or_init(|| {
+            get_token_definitions(
+                UCBLOGO_WORD_REGEX,
+                UCBLOGO_VARIABLE_REGEX,
+                UCBLOGO_IDENT_REGEX,
+            )
+        })
+    } else {
+        DEFINITIONS.get_or_init(|| get_token_definitions(WORD_REGEX, VARIABLE_REGEX, IDENT_REGEX))
+    }
+}
+
+fn list_piece_regex() -> &'static Regex {
+    static LIST_PIECE: OnceLock<Regex> = OnceLock::new();
+    LIST_PIECE.get_or_init(|| regex(LIST_PIECE_REGEX))
+}
+
+// removes the `\` and `|` escaping characters of a word, see
+// `Dialect::has_word_escapes`
+fn unescape(word: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = word.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            '|' => unescaped.extend(chars.by_ref().take_while(|&c| c != '|')),
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
 
 // returns a vector of the definition of every language token
 // a token definition consists of it's enumerated type and
 // it's regular expression used for parsing
-fn get_token_definitions() -> Vec<TokenDef> {
+fn get_token_definitions(
+    word_regex: &str,
+    variable_regex: &str,
+    ident_regex: &str,
+) -> Vec<TokenDef> {
     vec![
         TokenDef::new(
             Token::Number {
@@ -157,19 +274,19 @@ fn get_token_definitions() -> Vec<TokenDef> {
             Token::Word {
                 literal: Default::default(),
             },
-            WORD_REGEX,
+            word_regex,
         ),
         TokenDef::new(
             Token::Variable {
                 name: Default::default(),
             },
-            VARIABLE_REGEX,
+            variable_regex,
         ),
         TokenDef::new(
             Token::Identifier {
                 literal: "".to_string(),
             },
-            IDENT_REGEX,
+            ident_regex,
         ),
         // bracket characters
         TokenDef::new(Token::LBracket, r"^\["),
@@ -181,6 +298,13 @@ fn get_token_definitions() -> Vec<TokenDef> {
         TokenDef::new(Token::Operator(Operator::Subtraction), r"^-"),
         TokenDef::new(Token::Operator(Operator::Multiplication), r"^\*"),
         TokenDef::new(Token::Operator(Operator::Division), r"^/"),
+        // comparison operators, the two character ones have to come first
+        TokenDef::new(Token::Operator(Operator::LessEqual), r"^<="),
+        TokenDef::new(Token::Operator(Operator::GreaterEqual), r"^>="),
+        TokenDef::new(Token::Operator(Operator::NotEqual), r"^<>"),
+        TokenDef::new(Token::Operator(Operator::LessThan), r"^<"),
+        TokenDef::new(Token::Operator(Operator::GreaterThan), r"^>"),
+        TokenDef::new(Token::Operator(Operator::Equal), r"^="),
     ]
 }
 
@@ -192,9 +316,18 @@ type LexResult = Result<Token, LexError>;
 pub struct Lexer<'a> {
     source: &'a str,
     index: usize,
-    token_definitions: Vec<TokenDef>,
+    token_definitions: &'static [TokenDef],
     keywords: HashMap<String, Token>,
     whitespace_regex: Regex,
+    // the line being lexed and the index it starts at
+    line: usize,
+    line_start: usize,
+    // spans of the tokens lexed so far
+    spans: Vec<Span>,
+    // number of brackets opened and not yet closed
+    depth: usize,
+    // how identifiers are matched to keywords
+    naming: Naming,
 }
 
 impl<'a> Lexer<'a> {
@@ -202,15 +335,37 @@ impl<'a> Lexer<'a> {
         Self {
             source,
             index: 0,
-            token_definitions: get_token_definitions(),
+            token_definitions: token_definitions(Dialect::Standard),
             keywords: get_keywords(),
-            whitespace_regex: regex(r"^[\n\t\x20]*"),
+            whitespace_regex: regex(WHITESPACE_REGEX),
+            line: 1,
+            line_start: 0,
+            spans: Vec::new(),
+            depth: 0,
+            naming: Naming::default(),
+        }
+    }
+
+    /// Sets how identifiers are matched to keywords such as `repeat` and `to`, and
+    /// the dialect the source is written in. By default they match in any case, and
+    /// only by their English names.
+    pub(crate) fn naming(mut self, naming: Naming) -> Self {
+        self.naming = naming;
+        self.token_definitions = token_definitions(naming.dialect);
+        if naming.dialect.has_comments() {
+            self.whitespace_regex = regex(COMMENT_WHITESPACE_REGEX);
         }
+        self
+    }
+
+    /// Returns the span of every token lexed so far, in order.
+    pub fn spans(&self) -> &[Span] {
+        &self.spans
     }
 
     pub fn collect_tokens(&mut self) -> Result<Vec<Token>, LexError> {
         let mut tokens: Vec<Token> = Vec::new();
-        while let Some(lex_result) = self.next() {
+        for lex_result in self {
             match lex_result {
                 Ok(tok) => tokens.push(tok),
                 Err(e) => return Err(e),
@@ -222,29 +377,70 @@ impl<'a> Lexer<'a> {
     // increasing internal index to the first non-whitespace character
     fn skip_whitespace(&mut self) {
         if let Some(m) = self.whitespace_regex.find(&self.source[self.index..]) {
+            let whitespace = m.as_str();
+            if let Some(newline) = whitespace.rfind('\n') {
+                self.line += whitespace.matches('\n').count();
+                self.line_start = self.index + newline + 1;
+            }
             self.index += m.end();
         }
     }
 
+    // returns the span from `start` to the current index, `start` being on the
+    // current line
+    fn span_from(&self, start: usize) -> Span {
+        Span {
+            start,
+            end: self.index,
+            line: self.line,
+            column: self.source[self.line_start..start].chars().count() + 1,
+        }
+    }
+
+    // a minus sign is unary when it is preceded by whitespace, an opening bracket or
+    // another operator and is immediately followed by a non-space character, as in
+    // `fd -5`, `fd -:size` or `3 * -(1 + 2)`. Anywhere else it is binary subtraction,
+    // so `10 - 5` and `10-5` subtract while `10 -5` lexes as two separate numbers.
+    fn minus_is_unary(&self) -> bool {
+        let preceded_by_separator = match self.source[..self.index].chars().last() {
+            Some(c) => c.is_whitespace() || "([+-*/<>=".contains(c),
+            None => true,
+        };
+        let followed_by_operand = match self.source[self.index + 1..].chars().next() {
+            Some(c) => !c.is_whitespace(),
+            None => false,
+        };
+        preceded_by_separator && followed_by_operand
+    }
+
     // consumes n characters from the underlying slice, returns the consumed content
     fn consume(&mut self, n: usize) -> String {
-        let content = (&self.source[self.index..self.index + n]).to_string();
+        let content = self.source[self.index..self.index + n].to_string();
         self.index += n;
         content
     }
-}
-
-// the main functionality of the Lexer being implemented as an Iterator
-impl<'a> Iterator for Lexer<'a> {
-    type Item = LexResult;
-    fn next(&mut self) -> Option<Self::Item> {
-        self.skip_whitespace();
 
+    // lexes the token starting at the current index
+    fn next_token(&mut self) -> Option<LexResult> {
         // if we have reached the end of source, return None
         if self.index >= self.source.len() {
             return None;
         }
 
+        // negative number literals are matched by NUMBER_REGEX below, every other
+        // minus sign is resolved here depending on the surrounding whitespace
+        if self.source[self.index..].starts_with('-') {
+            let next_is_digit =
+                self.source[self.index + 1..].starts_with(|c: char| c.is_ascii_digit());
+            if !self.minus_is_unary() {
+                self.index += 1;
+                return Some(Ok(Token::Operator(Operator::Subtraction)));
+            } else if !next_is_digit {
+                self.index += 1;
+                return Some(Ok(Token::Operator(Operator::Negation)));
+            }
+        }
+
         for def in self.token_definitions.iter() {
             // if we find a match for the current token
             if let Some(m) = def.regex.find(&self.source[self.index..]) {
@@ -255,9 +451,14 @@ impl<'a> Iterator for Lexer<'a> {
                     Token::Word { literal: _ } => {
                         // advance 1 to ignore the leading " character
                         self.index += 1;
-                        Token::Word {
-                            // m.end() - 1 because we already skipped one character of the match
-                            literal: self.consume(m.end() - 1),
+                        // m.end() - 1 because we already skipped one character of the match
+                        let literal = self.consume(m.end() - 1);
+                        if self.naming.dialect.has_word_escapes() {
+                            Token::Word {
+                                literal: unescape(&literal),
+                            }
+                        } else {
+                            Token::Word { literal }
                         }
                     }
                     Token::Variable { name: _ } => {
@@ -270,10 +471,10 @@ impl<'a> Iterator for Lexer<'a> {
                     }
                     Token::Identifier { literal: _ } => {
                         let literal = self.consume(m.end());
-                        if let Some(tok) = self.keywords.get(&literal) {
+                        if let Some(tok) = self.keywords.get(&self.naming.key(&literal)) {
                             tok.clone()
                         } else {
-                            Token::Identifier { literal: literal }
+                            Token::Identifier { literal }
                         }
                     }
                     _ => {
@@ -285,19 +486,56 @@ impl<'a> Iterator for Lexer<'a> {
                 return Some(Ok(token));
             }
         }
+        // in a UCBLogo list, any other character is part of a word, as in `[a, b]`
+        if self.naming.dialect.has_list_words() && self.depth > 0 {
+            if let Some(m) = list_piece_regex().find(&self.source[self.index..]) {
+                let piece = self.consume(m.end());
+                return Some(Ok(Token::Identifier {
+                    literal: unescape(&piece),
+                }));
+            }
+        }
+
         // no match was found for any token definition
-        Some(Err(LexError::UnrecognizedToken))
+        let found = self.source[self.index..].chars().next().unwrap_or_default();
+        Some(Err(LexError::UnrecognizedToken {
+            found,
+            span: Span {
+                end: self.index + found.len_utf8(),
+                ..self.span_from(self.index)
+            },
+        }))
+    }
+}
+
+// the main functionality of the Lexer being implemented as an Iterator
+impl<'a> Iterator for Lexer<'a> {
+    type Item = LexResult;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.skip_whitespace();
+        let start = self.index;
+        let result = self.next_token()?;
+        match result {
+            Ok(Token::LBracket) => self.depth += 1,
+            Ok(Token::RBracket) => self.depth = self.depth.saturating_sub(1),
+            _ => (),
+        }
+        if result.is_ok() {
+            self.spans.push(self.span_from(start));
+        }
+        Some(result)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::locale::Locale;
 
     #[test]
     fn number_regex_test() {
         let number_regex = Regex::new(NUMBER_REGEX).unwrap();
-        let test_strings = vec!["1", "123456789", "-567", "-2943090"];
+        let test_strings = ["1", "123456789", "-567", "-2943090"];
 
         for input in test_strings.iter() {
             if let Some(m) = number_regex.find(input) {
@@ -312,7 +550,7 @@ mod tests {
     #[test]
     fn word_regex_test() {
         let word_regex = Regex::new(WORD_REGEX).unwrap();
-        let test_strings = vec!["\"size"];
+        let test_strings = ["\"size", "\"file.lgo", "\"../shapes/star.lgo"];
 
         for input in test_strings.iter() {
             if let Some(m) = word_regex.find(input) {
@@ -425,6 +663,109 @@ mod tests {
         );
     }
 
+    // Token's PartialEq only compares variants, so compare the debug output to also
+    // check literals and operators
+    fn lex_exact_test(input: &str, expected: Vec<Token>) {
+        let output_vec = Vec::from_iter(Lexer::new(input).map(|tok| tok.unwrap()));
+        assert_eq!(format!("{:?}", output_vec), format!("{:?}", expected));
+    }
+
+    #[test]
+    fn lex_minus_test() {
+        use Token::{Number, Operator as Op, Variable};
+        let num = |literal: &str| Number {
+            literal: literal.to_string(),
+        };
+
+        // binary subtraction, with and without surrounding whitespace
+        lex_exact_test(
+            "10 - 5",
+            vec![num("10"), Op(Operator::Subtraction), num("5")],
+        );
+        lex_exact_test("10-5", vec![num("10"), Op(Operator::Subtraction), num("5")]);
+
+        // a minus attached to a number after whitespace starts a negative literal
+        lex_exact_test("10 -5", vec![num("10"), num("-5")]);
+        lex_exact_test(
+            "3*-2",
+            vec![num("3"), Op(Operator::Multiplication), num("-2")],
+        );
+
+        // a minus attached to anything else is unary negation
+        lex_exact_test(
+            "-:size -(3)",
+            vec![
+                Op(Operator::Negation),
+                Variable {
+                    name: "size".to_string(),
+                },
+                Op(Operator::Negation),
+                Token::LParen,
+                num("3"),
+                Token::RParen,
+            ],
+        );
+    }
+
+    #[test]
+    fn lex_comparison_test() {
+        use Token::{Number, Operator as Op, Variable};
+        let num = |literal: &str| Number {
+            literal: literal.to_string(),
+        };
+
+        lex_exact_test(
+            "if :n<=-1 [stop] ifelse 1<>2",
+            vec![
+                Token::If,
+                Variable {
+                    name: "n".to_string(),
+                },
+                Op(Operator::LessEqual),
+                num("-1"),
+                Token::LBracket,
+                Token::Stop,
+                Token::RBracket,
+                Token::IfElse,
+                num("1"),
+                Op(Operator::NotEqual),
+                num("2"),
+            ],
+        );
+        lex_exact_test(
+            "1 < 2 > 3 = 4 >= 5",
+            vec![
+                num("1"),
+                Op(Operator::LessThan),
+                num("2"),
+                Op(Operator::GreaterThan),
+                num("3"),
+                Op(Operator::Equal),
+                num("4"),
+                Op(Operator::GreaterEqual),
+                num("5"),
+            ],
+        );
+    }
+
+    #[test]
+    fn lex_template_input_test() {
+        let ident = |literal: &str| Token::Identifier {
+            literal: literal.to_string(),
+        };
+        lex_exact_test(
+            "[?1 + ?] ?12",
+            vec![
+                Token::LBracket,
+                ident("?1"),
+                Token::Operator(Operator::Addition),
+                ident("?"),
+                Token::RBracket,
+                ident("?12"),
+            ],
+        );
+    }
+
     #[test]
     fn lex_bracket_paren_test() {
         lex_test(
@@ -494,4 +835,125 @@ mod tests {
             ],
         );
     }
+
+    #[test]
+    fn lex_span_test() {
+        let mut lexer = Lexer::new("fd 10\n  rt :angle\nshow \"é x");
+        lexer.collect_tokens().unwrap();
+        let positions: Vec<_> = lexer
+            .spans()
+            .iter()
+            .map(|span| (span.line, span.column, span.end - span.start))
+            .collect();
+        assert_eq!(
+            positions,
+            vec![
+                (1, 1, 2),
+                (1, 4, 2),
+                (2, 3, 2),
+                (2, 6, 6),
+                (3, 1, 4),
+                (3, 6, 3),
+                (3, 9, 1)
+            ]
+        );
+
+        match Lexer::new("fd 10\nrt $").collect_tokens() {
+            Err(LexError::UnrecognizedToken { found: '$', span }) => {
+                assert_eq!((span.line, span.column), (2, 4));
+            }
+            other => panic!("expected UnrecognizedToken, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn lex_case_test() {
+        let tokens = Lexer::new("REPEAT 2 [Fd 1] To").collect_tokens().unwrap();
+        assert_eq!(tokens[0], Token::Repeat);
+        assert_eq!(tokens[6], Token::To);
+        match &tokens[3] {
+            Token::Identifier { literal } => assert_eq!(literal, "Fd"),
+            other => panic!("expected Identifier, got {:?}", other),
+        }
+
+        let naming = Naming {
+            case_sensitive: true,
+            ..Naming::default()
+        };
+        let tokens = Lexer::new("REPEAT repeat")
+            .naming(naming)
+            .collect_tokens()
+            .unwrap();
+        assert_eq!(
+            tokens[0],
+            Token::Identifier {
+                literal: String::new()
+            }
+        );
+        assert_eq!(tokens[1], Token::Repeat);
+
+        // keywords and identifiers may be written in other languages
+        let naming = Naming {
+            locale: Locale::French,
+            ..Naming::default()
+        };
+        let tokens = Lexer::new("Répète 4 [avance :côté]")
+            .naming(naming)
+            .collect_tokens()
+            .unwrap();
+        assert_eq!(tokens[0], Token::Repeat);
+        match (&tokens[3], &tokens[4]) {
+            (Token::Identifier { literal }, Token::Variable { name }) => {
+                assert_eq!((literal.as_str(), name.as_str()), ("avance", "côté"));
+            }
+            other => panic!("expected Identifier and Variable, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn lex_ucblogo_test() {
+        let source = "; draws a square\nif empty? :done? [fd 10] ; unless done\nshow ?2";
+        let naming = Naming {
+            dialect: Dialect::UCBLogo,
+            ..Naming::default()
+        };
+        let mut lexer = Lexer::new(source).naming(naming);
+        let tokens = lexer.collect_tokens().unwrap();
+        assert_eq!(tokens.len(), 9);
+        match (&tokens[1], &tokens[2], &tokens[8]) {
+            (
+                Token::Identifier { literal },
+                Token::Variable { name },
+                Token::Identifier { literal: slot },
+            ) => {
+                assert_eq!(
+                    (literal.as_str(), name.as_str(), slot.as_str()),
+                    ("empty?", "done?", "?2")
+                );
+            }
+            other => panic!(
+                "expected Identifier, Variable and Identifier, got {:?}",
+                other
+            ),
+        }
+        assert_eq!((lexer.spans()[0].line, lexer.spans()[8].line), (2, 3));
+
+        // comments and names with `?` are UCBLogo syntax
+        assert!(Lexer::new("fd 10 ; ahead").collect_tokens().is_err());
+
+        // words may escape characters, and lists may hold any characters
+        let tokens = Lexer::new("show \"a\\[b \"|two words| \" [a, b]")
+            .naming(naming)
+            .collect_tokens()
+            .unwrap();
+        let literals: Vec<_> = tokens
+            .iter()
+            .map(|token| token.value().unwrap_or(""))
+            .collect();
+        assert_eq!(
+            literals,
+            vec!["show", "a[b", "two words", "", "", "a", ",", "b", ""]
+        );
+        assert!(Lexer::new("show [a, b]").collect_tokens().is_err());
+    }
 }
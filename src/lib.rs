@@ -1,23 +1,53 @@
+// `AST`, `EOF` and `ArithmeticExpression` are established names in this crate
+#![allow(clippy::upper_case_acronyms, clippy::enum_variant_names)]
+
+mod analysis;
 mod command;
+mod debugger;
+mod dialect;
 mod error;
 mod evaluator;
+mod formatter;
 mod lexer;
+mod lint;
+mod locale;
+mod module;
 mod parser;
+mod primitive;
+mod stdlib;
+mod value;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
 
-use error::LogoError;
 use evaluator::Evaluator;
 use lexer::Lexer;
-use parser::Parser;
+use locale::Naming;
+use parser::{Parser, AST};
 
 // re-exports
-pub use evaluator::Instruction;
+pub use analysis::{builtin_names, describe, is_keyword, Analysis, Definition, Reference};
 pub use command::Command;
+pub use debugger::{Breakpoint, Debugger, Resume, StopReason};
+pub use dialect::Dialect;
+pub use error::{Frame, LexError, LogoError, ParseError, RuntimeError};
+pub use evaluator::{Instruction, Pause, Stopped, Trace, TraceEvent};
+pub use lexer::{Span, Token};
+pub use lint::{Lint, Warning};
+pub use locale::Locale;
+pub use value::Value;
 
 /// Exposed type that acts as the interface to the library.
 pub struct Interpreter {
     evaluator: Evaluator,
 }
 
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Interpreter {
     pub fn new() -> Self {
         Interpreter {
@@ -25,6 +55,74 @@ impl Interpreter {
         }
     }
 
+    /// Enables or disables printing a message (as a `show` instruction) whenever a
+    /// program redefines an existing procedure. Disabled by default.
+    pub fn set_redefinition_warnings(&mut self, enabled: bool) {
+        self.evaluator.set_warn_on_redefine(enabled);
+    }
+
+    /// Makes procedure, variable and property list names, and keywords, only match
+    /// when they are spelled with the same case, as in case sensitive dialects of
+    /// Logo. By default names are compared ignoring case, so `FD 10` calls `fd` and
+    /// `:Size` is the variable made by `make "size`, while names are still printed
+    /// the way they were first spelled.
+    pub fn set_case_sensitive(&mut self, enabled: bool) {
+        self.evaluator.set_case_sensitive(enabled);
+    }
+
+    /// Sets how deeply calls of procedures, and of primitives that run code such as
+    /// `run` and `map`, may nest before a stack overflow error is raised. Defaults to
+    /// 1000 (300 in debug builds), which fits the 8 MB stack of the main thread;
+    /// programs run on threads with smaller stacks need a lower limit.
+    pub fn set_max_depth(&mut self, depth: usize) {
+        self.evaluator.set_max_depth(depth);
+    }
+
+    /// Sets the language programs are written in. Primitives and keywords can then
+    /// also be called by their names in that language, such as `avance` and
+    /// `répète` in French, while their English names keep working. Errors are
+    /// reported in that language by `LogoError::message`. English by default.
+    pub fn set_locale(&mut self, locale: Locale) {
+        self.evaluator.set_locale(locale);
+    }
+
+    /// Sets the dialect programs are written in, so that programs written for
+    /// Berkeley Logo can be run with `Dialect::UCBLogo`. The standard dialect by
+    /// default, see `Dialect` for the differences.
+    pub fn set_dialect(&mut self, dialect: Dialect) {
+        self.evaluator.set_dialect(dialect);
+    }
+
+    /// Sets the function called when a program runs `pause`. It is given the paused
+    /// program and should run instructions with `Pause::run` until `continue` has
+    /// been run or the user otherwise wants to continue, then return. Without a
+    /// handler `pause` does nothing.
+    pub fn set_pause_handler<F: FnMut(&mut Pause) + 'static>(&mut self, handler: F) {
+        self.evaluator.set_pause_handler(Box::new(handler));
+    }
+
+    /// Sets the function called for every trace event, when a procedure named by
+    /// `trace` is called or finishes and before each statement of a procedure named
+    /// by `step`. A handler typically prints the event as a trace line, and waits
+    /// for the user before returning from a step. Without a handler trace events
+    /// are shown as trace lines.
+    pub fn set_trace_handler<F: FnMut(&mut Trace) + 'static>(&mut self, handler: F) {
+        self.evaluator.set_trace_handler(Box::new(handler));
+    }
+
+    /// Attaches a debugger to the programs run by the interpreter, which stops them
+    /// at breakpoints and while stepping and hands them to its handler. Passing
+    /// None detaches it.
+    pub fn set_debugger(&mut self, debugger: Option<Debugger>) {
+        self.evaluator.set_debugger(debugger);
+    }
+
+    /// Adds a directory that `import` searches for modules. Directories are
+    /// searched in the order they were added, after the current directory.
+    pub fn add_search_path(&mut self, dir: &str) {
+        self.evaluator.add_search_path(PathBuf::from(dir));
+    }
+
     /// # Args
     /// - self
     /// - source: program source code to be run
@@ -41,7 +139,9 @@ impl Interpreter {
     /// - Misc. turtle commands (penup, hideturtle, setscreencolor ...)
     /// - Exit command
     ///
-    /// Returns a LogoError if an error is encountered during execution.
+    /// Returns a LogoError if an error is encountered during execution. It holds
+    /// the kind of error, with its number as output by `error`, and where it
+    /// happened.
     ///
     /// # Side effects
     /// Not all valid programs will return a set of instructions. Some programs will
@@ -54,24 +154,147 @@ impl Interpreter {
     /// If one of these programs runs successfully, it will return a Vec of length 0
     /// as the instructions set. If it fails, it will return an error as usual.
     pub fn run_program(&mut self, source: &str) -> Result<Vec<Instruction>, LogoError> {
-        // lexing phase
-        let mut lexer = Lexer::new(&source);
-        let tokens = match lexer.collect_tokens() {
-            Ok(t) => Ok(t),
-            Err(e) => Err(LogoError::Lex(e)),
-        }?;
-
-        // parsing phase
-        let mut parser = Parser::new(&tokens);
-        let ast = match parser.build_ast() {
-            Ok(ast) => Ok(ast),
-            Err(e) => Err(LogoError::Parse(e)),
-        }?;
+        // lexing and parsing phases
+        let ast = parse_program(
+            source,
+            self.evaluator.procedure_arities(),
+            self.evaluator.naming(),
+        )?;
 
         // evaluation phase
         match self.evaluator.evaluate_ast(&ast) {
             Ok(instructions) => Ok(instructions),
-            Err(e) => Err(LogoError::Runtime(e)),
+            Err(e) => Err(self.evaluator.locate_error(e)),
+        }
+    }
+
+    /// Runs the program in the file at `path`, the same way as the `load` command.
+    /// Used to load a startup file or a saved workspace.
+    pub fn load_file(&mut self, path: &str) -> Result<Vec<Instruction>, LogoError> {
+        match self.evaluator.load_file(path) {
+            Ok(instructions) => Ok(instructions),
+            Err(e) => Err(self.evaluator.locate_error(e)),
         }
     }
+
+    /// Imports the module `name`, the same way as `import "name`. This is how the
+    /// bundled libraries listed by `libraries` are loaded on demand.
+    pub fn import(&mut self, name: &str) -> Result<Vec<Instruction>, LogoError> {
+        match self.evaluator.import_module(name) {
+            Ok(instructions) => Ok(instructions),
+            Err(e) => Err(self.evaluator.locate_error(e)),
+        }
+    }
+
+    /// Returns the names of the libraries of Logo procedures bundled with the
+    /// interpreter, such as `shapes` and `fractals`.
+    pub fn libraries() -> Vec<&'static str> {
+        stdlib::names()
+    }
+}
+
+/// Lexes and parses `source` into an AST. `arities` holds the arities of user
+/// defined procedures declared outside of `source`.
+fn parse_program(
+    source: &str,
+    arities: HashMap<String, usize>,
+    naming: Naming,
+) -> Result<AST, LogoError> {
+    // lexing phase
+    let mut lexer = Lexer::new(source).naming(naming);
+    let tokens = match lexer.collect_tokens() {
+        Ok(t) => Ok(t),
+        Err(e) => Err(LogoError::Lex(e)),
+    }?;
+
+    // parsing phase
+    let mut parser = Parser::with_arities(&tokens, arities)
+        .with_spans(lexer.spans())
+        .naming(naming);
+    match parser.build_ast() {
+        Ok(ast) => Ok(ast),
+        Err(error) => Err(LogoError::Parse {
+            span: parser.error_span(&error),
+            error,
+        }),
+    }
+}
+
+/// Lexes and parses `source` as an instruction list run by `run`, see
+/// `Parser::build_instruction_list`.
+fn parse_instruction_list(
+    source: &str,
+    arities: HashMap<String, usize>,
+    naming: Naming,
+) -> Result<(AST, Option<parser::Expression>), LogoError> {
+    let mut lexer = Lexer::new(source).naming(naming);
+    let tokens = lexer.collect_tokens().map_err(LogoError::Lex)?;
+    Parser::with_arities(&tokens, arities)
+        .with_spacing(lexer.spans())
+        .naming(naming)
+        .build_instruction_list()
+        .map_err(|error| LogoError::Parse { error, span: None })
+}
+
+/// Finds the procedures declared in a program and the names it uses, and the
+/// errors that keep it from being parsed, for editors. The program is not run.
+pub fn analyze_program(source: &str) -> Analysis {
+    let mut lexer = Lexer::new(source);
+    let tokens = match lexer.collect_tokens() {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            return Analysis {
+                errors: vec![LogoError::Lex(e)],
+                ..Analysis::default()
+            }
+        }
+    };
+
+    let mut analysis = analysis::analyze(source, &tokens, lexer.spans());
+    let (_, errors) = Parser::new(&tokens)
+        .with_spans(lexer.spans())
+        .build_ast_recovering();
+    for (error, span) in errors {
+        analysis.errors.push(LogoError::Parse { error, span });
+    }
+    analysis
+}
+
+/// Checks a program for likely mistakes without running it, such as calls to
+/// undefined procedures or variables used before they are made, see `logo check`.
+///
+/// Returns every LexError or ParseError, wrapped in LogoErrors, if the program
+/// can't be parsed. The parser carries on after each error, so all of them are
+/// reported at once.
+pub fn check_program(source: &str) -> Result<Vec<Warning>, Vec<LogoError>> {
+    let mut lexer = Lexer::new(source);
+    let tokens = lexer
+        .collect_tokens()
+        .map_err(|e| vec![LogoError::Lex(e)])?;
+
+    let (ast, errors) = Parser::new(&tokens)
+        .with_spans(lexer.spans())
+        .build_ast_recovering();
+    if !errors.is_empty() {
+        let errors = errors
+            .into_iter()
+            .map(|(error, span)| LogoError::Parse { error, span });
+        return Err(errors.collect());
+    }
+    Ok(lint::check(&ast))
+}
+
+/// Formats a program as canonical Logo source, see `logo fmt`.
+///
+/// Returns a LexError or ParseError wrapped in a LogoError if the program can't be
+/// parsed. The program is not run.
+pub fn format_program(source: &str) -> Result<String, LogoError> {
+    let mut lexer = Lexer::new(source);
+    let tokens = lexer.collect_tokens().map_err(LogoError::Lex)?;
+    let mut parser = Parser::new(&tokens).with_spans(lexer.spans());
+    let ast = parser.build_ast().map_err(|error| LogoError::Parse {
+        span: parser.error_span(&error),
+        error,
+    })?;
+    Ok(formatter::format(&ast, HashMap::new(), Naming::default()))
 }
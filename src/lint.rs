@@ -0,0 +1,625 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::command::Command;
+use crate::lexer::{Lexer, Span};
+use crate::parser::{Expression, Parser, Statement, AST};
+use crate::primitive::Primitive;
+use crate::stdlib;
+use crate::value::Value;
+
+/// A likely mistake found in a program without running it, see `check_program`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lint {
+    UndefinedProcedure {
+        name: String,
+    },
+    WrongInputCount {
+        name: String,
+        expected: usize,
+        given: usize,
+    },
+    UndefinedVariable {
+        name: String,
+    },
+    UnusedParameter {
+        procedure: String,
+        name: String,
+    },
+    UnreachableCode,
+    EmptyRepeat {
+        count: isize,
+    },
+    ShadowedPrimitive {
+        name: String,
+    },
+}
+
+impl Lint {
+    /// Returns the name of the kind of lint, e.g. `undefined-procedure`, for
+    /// machine readable output.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Lint::UndefinedProcedure { .. } => "undefined-procedure",
+            Lint::WrongInputCount { .. } => "wrong-input-count",
+            Lint::UndefinedVariable { .. } => "undefined-variable",
+            Lint::UnusedParameter { .. } => "unused-parameter",
+            Lint::UnreachableCode => "unreachable-code",
+            Lint::EmptyRepeat { .. } => "empty-repeat",
+            Lint::ShadowedPrimitive { .. } => "shadowed-primitive",
+        }
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Lint::UndefinedProcedure { name } => {
+                write!(formatter, "Procedure '{}' is not defined", name)
+            }
+            Lint::WrongInputCount {
+                name,
+                expected,
+                given,
+            } => write!(
+                formatter,
+                "'{}' takes {} inputs but is given {}",
+                name, expected, given
+            ),
+            Lint::UndefinedVariable { name } => {
+                write!(formatter, "Variable :{} is used before it is made", name)
+            }
+            Lint::UnusedParameter { procedure, name } => {
+                write!(
+                    formatter,
+                    "Input :{} of '{}' is never used",
+                    name, procedure
+                )
+            }
+            Lint::UnreachableCode => write!(formatter, "Unreachable code after stop or output"),
+            Lint::EmptyRepeat { count } => {
+                write!(formatter, "Repeat count {} never runs its body", count)
+            }
+            Lint::ShadowedPrimitive { name } => {
+                write!(
+                    formatter,
+                    "Procedure '{}' has the name of a primitive",
+                    name
+                )
+            }
+        }
+    }
+}
+
+/// A lint and the span of the statement it was found in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub lint: Lint,
+    pub span: Option<Span>,
+}
+
+/// Checks `ast` for likely mistakes, returning the warnings in the order they
+/// were found in the program.
+///
+/// Logo variables are dynamically scoped, so a procedure may use any variable
+/// made anywhere in the program or any input of another procedure. The top level
+/// may use variables made before, or by procedures. Calls to unknown procedures
+/// are only reported if all imported modules are bundled libraries. Names are
+/// compared ignoring case, as the interpreter does by default.
+pub fn check(ast: &AST) -> Vec<Warning> {
+    let mut linter = Linter {
+        procedures: HashMap::new(),
+        unknown_imports: false,
+        assigned: HashSet::new(),
+        known: HashSet::new(),
+        used: HashSet::new(),
+        span: None,
+        warnings: Vec::new(),
+    };
+    linter.collect(ast, false);
+    linter.check_block(ast);
+
+    let mut warnings = linter.warnings;
+    warnings.sort_by_key(|warning| warning.span.map(|span| span.start));
+    warnings.dedup();
+    warnings
+}
+
+struct Linter {
+    // the fewest and most inputs, if there is a limit, taken by every procedure
+    // declared in the program or a bundled library it imports. Names in these
+    // tables are lowercase
+    procedures: HashMap<String, (usize, Option<usize>)>,
+    // the program imports modules whose procedures aren't known
+    unknown_imports: bool,
+    // variables made or declared anywhere in the program, and procedure inputs
+    assigned: HashSet<String>,
+    // variables that have a value where the linter is, and inputs that have been
+    // used in the procedure being checked
+    known: HashSet<String>,
+    used: HashSet<String>,
+    // span of the statement being checked
+    span: Option<Span>,
+    warnings: Vec<Warning>,
+}
+
+impl Linter {
+    fn warn(&mut self, lint: Lint) {
+        self.warnings.push(Warning {
+            lint,
+            span: self.span,
+        });
+    }
+
+    /// Finds the procedures declared and imported by `ast` and the variables it
+    /// makes, before any statement is checked. Variables made by procedures with
+    /// `make` are known at the top level.
+    fn collect(&mut self, ast: &AST, in_procedure: bool) {
+        for stmt in ast.statements.iter() {
+            match stmt {
+                Statement::ProcedureDeclaration {
+                    name,
+                    body,
+                    params,
+                    optional,
+                    rest,
+                    ..
+                } => {
+                    self.procedures
+                        .insert(name.to_lowercase(), input_range(stmt));
+                    let inputs = params.iter().chain(optional.iter().map(|(name, _)| name));
+                    self.assigned
+                        .extend(inputs.chain(rest).map(|name| name.to_lowercase()));
+                    self.collect(body, true);
+                }
+                Statement::VariableDeclaration { name, val: _ } => {
+                    self.assigned.insert(name.to_lowercase());
+                    if in_procedure {
+                        self.known.insert(name.to_lowercase());
+                    }
+                }
+                Statement::ProcedureCall { name, args } => match name.to_lowercase().as_str() {
+                    name @ ("local" | "localmake" | "global") => {
+                        self.assigned.extend(variable_names(name, args));
+                    }
+                    "import" => self.import(args),
+                    _ => (),
+                },
+                Statement::Repeat { count: _, body } => self.collect(body, in_procedure),
+                Statement::If {
+                    condition: _,
+                    body,
+                    else_body,
+                } => {
+                    self.collect(body, in_procedure);
+                    if let Some(else_body) = else_body {
+                        self.collect(else_body, in_procedure);
+                    }
+                }
+                Statement::Output { val: _ } | Statement::Stop => (),
+            }
+        }
+    }
+
+    /// Adds the procedures of a bundled library imported with `args`, under its
+    /// namespace if it is given one.
+    fn import(&mut self, args: &[Expression]) {
+        let words: Vec<_> = args
+            .iter()
+            .filter_map(|arg| match arg {
+                Expression::Word { literal } => Some(literal.as_str()),
+                _ => None,
+            })
+            .collect();
+        let library = words.first().and_then(|name| stdlib::source(name));
+        let (source, namespace) = match (library, words.len() == args.len()) {
+            (Some(source), true) => (source, words.get(1)),
+            _ => {
+                self.unknown_imports = true;
+                return;
+            }
+        };
+
+        let tokens = Lexer::new(source).collect_tokens().unwrap_or_default();
+        let ast = Parser::new(&tokens)
+            .build_ast()
+            .unwrap_or_else(|_| AST::new());
+        for stmt in ast.statements.iter() {
+            if let Statement::ProcedureDeclaration { name, .. } = stmt {
+                let name = match namespace {
+                    Some(namespace) => format!("{}.{}", namespace, name),
+                    None => name.to_string(),
+                };
+                self.procedures
+                    .insert(name.to_lowercase(), input_range(stmt));
+            }
+        }
+    }
+
+    fn check_block(&mut self, ast: &AST) {
+        let mut stopped = false;
+        let mut reported = false;
+        for (i, stmt) in ast.statements.iter().enumerate() {
+            let span = ast.spans.get(i).copied().or(self.span);
+            let outer = std::mem::replace(&mut self.span, span);
+
+            if stopped && !reported {
+                self.warn(Lint::UnreachableCode);
+                reported = true;
+            }
+            self.check_statement(stmt);
+            if let Statement::Stop | Statement::Output { val: _ } = stmt {
+                stopped = true;
+            }
+
+            self.span = outer;
+        }
+    }
+
+    fn check_statement(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Repeat { count, body } => {
+                self.check_expression(count);
+                if let Expression::Number { val } = count {
+                    if *val <= 0 {
+                        self.warn(Lint::EmptyRepeat { count: *val });
+                    }
+                }
+                self.check_block(body);
+            }
+            Statement::VariableDeclaration { name, val } => {
+                self.check_expression(val);
+                self.known.insert(name.to_lowercase());
+            }
+            Statement::ProcedureDeclaration {
+                name,
+                body,
+                params,
+                optional,
+                rest,
+                ..
+            } => {
+                let key = name.to_lowercase();
+                if Command::from_string(&key).is_some() || Primitive::from_string(&key).is_some() {
+                    self.warn(Lint::ShadowedPrimitive {
+                        name: name.to_string(),
+                    });
+                }
+
+                let known = std::mem::replace(&mut self.known, self.assigned.clone());
+                let used = std::mem::take(&mut self.used);
+                // default values are evaluated when the procedure is called
+                for (_, default) in optional.iter() {
+                    self.check_expression(default);
+                }
+                self.check_block(body);
+                let optional = optional.iter().map(|(name, _)| name);
+                for param in params.iter().chain(optional).chain(rest) {
+                    if !self.used.contains(&param.to_lowercase()) {
+                        self.warn(Lint::UnusedParameter {
+                            procedure: name.to_string(),
+                            name: param.to_string(),
+                        });
+                    }
+                }
+                self.known = known;
+                self.used = used;
+            }
+            Statement::ProcedureCall { name, args } => self.check_call(name, args),
+            Statement::If {
+                condition,
+                body,
+                else_body,
+            } => {
+                self.check_expression(condition);
+                self.check_block(body);
+                if let Some(else_body) = else_body {
+                    self.check_block(else_body);
+                }
+            }
+            Statement::Output { val } => self.check_expression(val),
+            Statement::Stop => (),
+        }
+    }
+
+    fn check_call(&mut self, name: &str, args: &[Expression]) {
+        for arg in args.iter() {
+            self.check_expression(arg);
+        }
+
+        let key = name.to_lowercase();
+        let range = if let Some(command) = Command::from_string(&key) {
+            Some((command.arity(), Some(command.arity())))
+        } else if let Some(primitive) = Primitive::from_string(&key) {
+            // variadic primitives take any number of inputs in parentheses
+            match primitive.is_variadic() {
+                true => Some((0, None)),
+                false => Some((primitive.arity(), Some(primitive.arity()))),
+            }
+        } else {
+            self.procedures.get(&key).copied()
+        };
+
+        let given = args.len();
+        match range {
+            Some((min, _)) if given < min => self.warn(Lint::WrongInputCount {
+                name: name.to_string(),
+                expected: min,
+                given,
+            }),
+            Some((_, Some(max))) if given > max => self.warn(Lint::WrongInputCount {
+                name: name.to_string(),
+                expected: max,
+                given,
+            }),
+            None if !self.unknown_imports => self.warn(Lint::UndefinedProcedure {
+                name: name.to_string(),
+            }),
+            _ => (),
+        }
+
+        match key.as_str() {
+            "local" | "localmake" | "global" => self.known.extend(variable_names(&key, args)),
+            "thing" => self.used.extend(variable_names(&key, args)),
+            _ => (),
+        }
+    }
+
+    fn check_expression(&mut self, expr: &Expression) {
+        match expr {
+            Expression::ArithmeticExpression { postfix } => {
+                for expr in postfix.iter() {
+                    self.check_expression(expr);
+                }
+            }
+            Expression::ProcedureCall { name, args } => self.check_call(name, args),
+            Expression::Variable { name } => {
+                self.used.insert(name.to_lowercase());
+                if !self.known.contains(&name.to_lowercase()) {
+                    self.warn(Lint::UndefinedVariable {
+                        name: name.to_string(),
+                    });
+                }
+            }
+            // lists aren't checked as code, but may use inputs when they are run
+            Expression::List { items } => list_variables(items, &mut self.used),
+            Expression::Operator { op: _ }
+            | Expression::Number { val: _ }
+            | Expression::Word { literal: _ } => (),
+        }
+    }
+}
+
+/// Returns the fewest and most inputs the procedure declared by `stmt` can be
+/// called with. There is no most if it has a rest input.
+fn input_range(stmt: &Statement) -> (usize, Option<usize>) {
+    match stmt {
+        Statement::ProcedureDeclaration {
+            params,
+            optional,
+            rest: None,
+            ..
+        } => (params.len(), Some(params.len() + optional.len())),
+        Statement::ProcedureDeclaration { params, .. } => (params.len(), None),
+        _ => (0, None),
+    }
+}
+
+/// Returns the quoted variable names passed to `name`, as in `localmake "x 1` or
+/// `(local "x "y)`. Only the first input of `localmake` and `thing` is a name.
+fn variable_names(name: &str, args: &[Expression]) -> Vec<String> {
+    let count = if name == "localmake" || name == "thing" {
+        1
+    } else {
+        args.len()
+    };
+    args.iter()
+        .take(count)
+        .filter_map(|arg| match arg {
+            Expression::Word { literal } => Some(literal.to_lowercase()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Adds the variables used in a list, such as `size` in `[fd :size * 2]`.
+fn list_variables(items: &[Value], used: &mut HashSet<String>) {
+    for item in items.iter() {
+        match item {
+            Value::Word(word) if word.starts_with(':') => {
+                let name: String = word[1..]
+                    .chars()
+                    .take_while(|c| c.is_alphanumeric() || *c == '_')
+                    .collect();
+                used.insert(name.to_lowercase());
+            }
+            Value::List(items) => list_variables(items, used),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint(source: &str) -> Vec<(usize, Lint)> {
+        let mut lexer = Lexer::new(source);
+        let tokens = lexer.collect_tokens().unwrap();
+        let ast = Parser::new(&tokens)
+            .with_spans(lexer.spans())
+            .build_ast()
+            .unwrap();
+        check(&ast)
+            .into_iter()
+            .map(|warning| (warning.span.unwrap().line, warning.lint))
+            .collect()
+    }
+
+    #[test]
+    fn lint_procedure_test() {
+        let source = "to square :size :unused
+repeat 4 [fd :size rt 90]
+stop
+show 1
+end
+square 10 20
+(fd 10 20)
+triangle 5
+to fd :x
+show :x
+end";
+        assert_eq!(
+            lint(source),
+            vec![
+                (
+                    1,
+                    Lint::UnusedParameter {
+                        procedure: "square".to_string(),
+                        name: "unused".to_string(),
+                    }
+                ),
+                (4, Lint::UnreachableCode),
+                (
+                    7,
+                    Lint::WrongInputCount {
+                        name: "fd".to_string(),
+                        expected: 1,
+                        given: 2,
+                    }
+                ),
+                (
+                    8,
+                    Lint::UndefinedProcedure {
+                        name: "triangle".to_string(),
+                    }
+                ),
+                (
+                    9,
+                    Lint::ShadowedPrimitive {
+                        name: "fd".to_string(),
+                    }
+                ),
+            ]
+        );
+
+        // names are compared ignoring case
+        assert_eq!(lint("to Square :Size\nFD :size\nend\nSQUARE 10"), vec![]);
+    }
+
+    #[test]
+    fn lint_variable_test() {
+        let source = "show :x
+make \"x 1
+show :x
+repeat 0 [show :y]
+to setup
+make \"y 2
+localmake \"z 3
+show map [? * :factor] [1 2]
+end
+show :z
+show :y";
+        assert_eq!(
+            lint(source),
+            vec![
+                (
+                    1,
+                    Lint::UndefinedVariable {
+                        name: "x".to_string(),
+                    }
+                ),
+                (4, Lint::EmptyRepeat { count: 0 }),
+                (
+                    10,
+                    Lint::UndefinedVariable {
+                        name: "z".to_string(),
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn lint_optional_inputs_test() {
+        let source = "to poly :sides [:size :unknown] [:rest] 1
+fd :size
+end
+poly 5
+(poly 5 30 1 2)
+(poly)
+to pair :a [:b :a]
+show :a
+end
+(pair 1 2 3)";
+        assert_eq!(
+            lint(source),
+            vec![
+                (
+                    1,
+                    Lint::UndefinedVariable {
+                        name: "unknown".to_string(),
+                    }
+                ),
+                (
+                    1,
+                    Lint::UnusedParameter {
+                        procedure: "poly".to_string(),
+                        name: "sides".to_string(),
+                    }
+                ),
+                (
+                    1,
+                    Lint::UnusedParameter {
+                        procedure: "poly".to_string(),
+                        name: "rest".to_string(),
+                    }
+                ),
+                (
+                    6,
+                    Lint::WrongInputCount {
+                        name: "poly".to_string(),
+                        expected: 1,
+                        given: 0,
+                    }
+                ),
+                (
+                    7,
+                    Lint::UnusedParameter {
+                        procedure: "pair".to_string(),
+                        name: "b".to_string(),
+                    }
+                ),
+                (
+                    10,
+                    Lint::WrongInputCount {
+                        name: "pair".to_string(),
+                        expected: 2,
+                        given: 3,
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn lint_import_test() {
+        // procedures of bundled libraries are known, other modules' aren't
+        assert_eq!(
+            lint("import \"shapes\n(import \"fractals \"f)\nsquare 10\nf.tree 5 2"),
+            vec![]
+        );
+        assert_eq!(lint("import \"mine\nanything 1"), vec![]);
+        assert_eq!(
+            lint("import \"shapes\n(square 1 2)"),
+            vec![(
+                2,
+                Lint::WrongInputCount {
+                    name: "square".to_string(),
+                    expected: 1,
+                    given: 2,
+                }
+            )]
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use std::fmt;
+
+use crate::dialect::Dialect;
+
+mod de;
+mod es;
+mod fr;
+
+/// The language programs are written in and errors are reported in. Primitives
+/// and keywords can always be called by their English names, in other locales
+/// they can also be called by their translated names, e.g. `avance 100` in French.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    English,
+    French,
+    German,
+    Spanish,
+}
+
+impl Locale {
+    /// Returns the locale with the language code `s`, such as `fr`.
+    pub fn from_string(s: &str) -> Option<Self> {
+        use Locale::*;
+        let locale = match s {
+            "en" => English,
+            "fr" => French,
+            "de" => German,
+            "es" => Spanish,
+            _ => return None,
+        };
+        Some(locale)
+    }
+
+    /// The language codes of every locale.
+    pub fn names() -> &'static [&'static str] {
+        &["en", "fr", "de", "es"]
+    }
+
+    /// Translated names of primitives, turtle commands and keywords, with the
+    /// English name each one stands for.
+    pub fn aliases(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Locale::English => &[],
+            Locale::French => fr::ALIASES,
+            Locale::German => de::ALIASES,
+            Locale::Spanish => es::ALIASES,
+        }
+    }
+
+    /// Returns the English name of the primitive, turtle command or keyword
+    /// called `name` in this locale, if `name` is a translated name.
+    pub fn translate(&self, name: &str) -> Option<&'static str> {
+        let mut aliases = self.aliases().iter();
+        aliases
+            .find(|(alias, _)| *alias == name)
+            .map(|(_, english)| *english)
+    }
+
+    // error messages in English with their translation, see `text`
+    fn messages(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Locale::English => &[],
+            Locale::French => fr::MESSAGES,
+            Locale::German => de::MESSAGES,
+            Locale::Spanish => es::MESSAGES,
+        }
+    }
+
+    /// Translates the English message `template` and replaces every `{}` in it
+    /// with the next of `args`.
+    pub(crate) fn text(&self, template: &'static str, args: &[&dyn fmt::Display]) -> String {
+        let mut messages = self.messages().iter();
+        let translated = messages
+            .find(|(english, _)| *english == template)
+            .map_or(template, |(_, translated)| *translated);
+
+        let mut pieces = translated.split("{}");
+        let mut text = pieces.next().unwrap_or_default().to_string();
+        for (piece, arg) in pieces.zip(args) {
+            text.push_str(&arg.to_string());
+            text.push_str(piece);
+        }
+        text
+    }
+}
+
+/// How names in programs are matched to keywords, primitives and procedures, and
+/// the dialect programs are written in.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Naming {
+    /// Names only match when they are spelled with the same case.
+    pub case_sensitive: bool,
+    /// Primitives and keywords may also be called by their names in the locale.
+    pub locale: Locale,
+    /// Primitives may also be called by their names in the dialect.
+    pub dialect: Dialect,
+}
+
+impl Naming {
+    /// Returns the key the procedure, primitive or keyword `name` is looked up by:
+    /// the English name of a primitive or keyword called by its translated name or
+    /// its name in the dialect, or else the key of any other name.
+    pub fn key(&self, name: &str) -> String {
+        let key = self.variable_key(name);
+        match self
+            .locale
+            .translate(&key)
+            .or_else(|| self.dialect.translate(&key))
+        {
+            Some(english) => english.to_string(),
+            None => key,
+        }
+    }
+
+    /// Returns the key the variable or property list `name` is looked up by: the
+    /// lowercase name unless names are case sensitive. Variables never stand for
+    /// primitives, so their names aren't translated.
+    pub fn variable_key(&self, name: &str) -> String {
+        if self.case_sensitive {
+            name.to_string()
+        } else {
+            name.to_lowercase()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis;
+
+    #[test]
+    fn locale_table_test() {
+        let builtins = analysis::builtin_names();
+        for name in Locale::names() {
+            let locale = Locale::from_string(name).unwrap();
+            for (alias, english) in locale.aliases() {
+                assert!(
+                    builtins.contains(english),
+                    "{}: {} isn't a primitive",
+                    name,
+                    english
+                );
+                assert_eq!(alias.to_lowercase(), *alias, "{}", name);
+            }
+            // translations take the same inputs as the English message
+            for (english, translated) in locale.messages() {
+                assert_eq!(
+                    english.matches("{}").count(),
+                    translated.matches("{}").count(),
+                    "{}: {}",
+                    name,
+                    english
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn locale_text_test() {
+        let template = "Procedure '{}' does not exist";
+        assert_eq!(
+            Locale::English.text(template, &[&"avance"]),
+            "Procedure 'avance' does not exist"
+        );
+        assert_eq!(
+            Locale::French.text(template, &[&"avanse"]),
+            "La procédure 'avanse' n'existe pas"
+        );
+
+        let naming = Naming {
+            locale: Locale::German,
+            ..Naming::default()
+        };
+        assert_eq!(naming.key("Vorwärts"), "forward");
+        assert_eq!(naming.key("Quadrat"), "quadrat");
+        assert_eq!(Naming::default().key("vorwärts"), "vorwärts");
+    }
+}
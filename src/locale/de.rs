@@ -0,0 +1,212 @@
+// German names, after the German versions of MSWLogo and Comenius Logo
+
+pub(super) const ALIASES: &[(&str, &str)] = &[
+    // keywords
+    ("wiederhole", "repeat"),
+    ("setze", "make"),
+    ("lerne", "to"),
+    ("ende", "end"),
+    ("rückgabe", "output"),
+    ("rueckgabe", "output"),
+    ("rg", "output"),
+    ("halt", "stop"),
+    ("wenn", "if"),
+    ("wennsonst", "ifelse"),
+    // turtle commands
+    ("vorwärts", "forward"),
+    ("vorwaerts", "forward"),
+    ("vw", "forward"),
+    ("rückwärts", "backward"),
+    ("rueckwaerts", "backward"),
+    ("rw", "backward"),
+    ("links", "left"),
+    ("li", "left"),
+    ("rechts", "right"),
+    ("re", "right"),
+    ("aufkurs", "setheading"),
+    ("aufxy", "setxy"),
+    ("mitte", "home"),
+    ("stiftauf", "penup"),
+    ("sa", "penup"),
+    ("stiftab", "pendown"),
+    ("sab", "pendown"),
+    ("stiftbreite", "setpensize"),
+    ("stiftfarbe", "setpencolor"),
+    ("versteckeigel", "hideturtle"),
+    ("vi", "hideturtle"),
+    ("zeigeigel", "showturtle"),
+    ("zi", "showturtle"),
+    ("bildlöschen", "clearscreen"),
+    ("bildloeschen", "clearscreen"),
+    ("säubern", "clean"),
+    ("saeubern", "clean"),
+    ("hintergrundfarbe", "setscreencolor"),
+    ("zeige", "show"),
+    ("beenden", "exit"),
+    // primitives
+    ("summe", "sum"),
+    ("differenz", "difference"),
+    ("produkt", "product"),
+    ("rest", "remainder"),
+    ("lokal", "local"),
+    ("lokalsetze", "localmake"),
+    ("ding", "thing"),
+    ("druckezeile", "print"),
+    ("dz", "print"),
+    ("lösche", "erase"),
+    ("loesche", "erase"),
+    ("löschealles", "erall"),
+    ("loeschealles", "erall"),
+    ("prozeduren", "procedures"),
+    ("namen", "names"),
+    ("speichere", "save"),
+    ("lade", "load"),
+    ("importiere", "import"),
+    ("führeaus", "run"),
+    ("fuehreaus", "run"),
+    ("wendean", "apply"),
+    ("erstes", "first"),
+    ("letztes", "last"),
+    ("ohneerstes", "butfirst"),
+    ("oe", "butfirst"),
+    ("ohneletztes", "butlast"),
+    ("ol", "butlast"),
+    ("anzahl", "count"),
+    ("element", "item"),
+    ("vornean", "fput"),
+    ("hintenan", "lput"),
+    ("liste", "list"),
+    ("leerp", "emptyp"),
+    ("fange", "catch"),
+    ("wirf", "throw"),
+    ("fehler", "error"),
+    ("bilde", "map"),
+    ("filtere", "filter"),
+    ("reduziere", "reduce"),
+    ("fürjedes", "foreach"),
+    ("fuerjedes", "foreach"),
+];
+
+pub(super) const MESSAGES: &[(&str, &str)] = &[
+    ("{} at {}", "{} bei {}"),
+    ("line {}, column {}", "Zeile {}, Spalte {}"),
+    ("in {}", "in {}"),
+    ("called from {}", "aufgerufen von {}"),
+    ("... {} more calls", "... {} weitere Aufrufe"),
+    (
+        "called from top level{}",
+        "aufgerufen von der obersten Ebene{}",
+    ),
+    (", line {}", ", Zeile {}"),
+    (
+        "Found unexpected character '{}' at {}",
+        "Unerwartetes Zeichen '{}' bei {}",
+    ),
+    (
+        "Reached EOF (End of file) while parsing",
+        "Dateiende beim Parsen erreicht",
+    ),
+    (
+        "Unexpected token: {}. Expected: {}",
+        "Unerwartetes Symbol: {}. Erwartet: {}",
+    ),
+    (
+        "Found unexpected type while parsing, expected {}",
+        "Unerwarteter Typ beim Parsen, erwartet: {}",
+    ),
+    (
+        "Error while parsing integer: {}",
+        "Fehler beim Lesen der Zahl: {}",
+    ),
+    (
+        "Found unbalanced parentheses while parsing",
+        "Unausgeglichene Klammern beim Parsen",
+    ),
+    ("Not enough inputs to '{}'", "Zu wenige Eingaben für '{}'"),
+    (
+        "Procedure '{}' can't take {} inputs by default",
+        "Prozedur '{}' kann standardmäßig nicht {} Eingaben nehmen",
+    ),
+    (
+        "Procedure '{}' has already been declared",
+        "Prozedur '{}' wurde bereits definiert",
+    ),
+    (
+        "Procedure '{}' does not exist; did you mean '{}'?",
+        "Prozedur '{}' existiert nicht; meinten Sie '{}'?",
+    ),
+    (
+        "Procedure '{}' does not exist",
+        "Prozedur '{}' existiert nicht",
+    ),
+    (
+        "Variable :{} has not been declared; did you mean :{}?",
+        "Variable :{} wurde nicht deklariert; meinten Sie :{}?",
+    ),
+    (
+        "Variable :{} has not been declared",
+        "Variable :{} wurde nicht deklariert",
+    ),
+    ("Variable :{} has no value", "Variable :{} hat keinen Wert"),
+    (
+        "'{}' doesn't like {} as input",
+        "'{}' mag {} nicht als Eingabe",
+    ),
+    (
+        "Wrong number of arguments to '{}', expected {} but got {}",
+        "Falsche Anzahl von Argumenten für '{}', erwartet {}, erhalten {}",
+    ),
+    (
+        "'{}' can't divide {} by zero",
+        "'{}' kann {} nicht durch null teilen",
+    ),
+    (
+        "'{}' overflowed with inputs {}",
+        "Arithmetischer Überlauf in '{}' mit den Eingaben {}",
+    ),
+    (
+        "Malformed arithmetic expression at '{}'",
+        "Fehlerhafter arithmetischer Ausdruck bei '{}'",
+    ),
+    ("'{}' is a primitive", "'{}' ist eine Grundprozedur"),
+    (
+        "Procedure '{}' did not output a value",
+        "Prozedur '{}' hat keinen Wert zurückgegeben",
+    ),
+    (
+        "You don't say what to do with the output of '{}'",
+        "Es fehlt, was mit der Rückgabe von '{}' geschehen soll",
+    ),
+    (
+        "'{}' can only be used inside a procedure",
+        "'{}' kann nur innerhalb einer Prozedur verwendet werden",
+    ),
+    ("Error with file '{}': {}", "Fehler mit Datei '{}': {}"),
+    (
+        "Could not find module '{}' on the search path",
+        "Modul '{}' wurde im Suchpfad nicht gefunden",
+    ),
+    ("Import cycle: {}", "Importzyklus: {}"),
+    (
+        "Invalid instruction list given to '{}': {}",
+        "Ungültige Anweisungsliste für '{}': {}",
+    ),
+    (
+        "'{}' can only be used inside a template",
+        "'{}' kann nur innerhalb einer Vorlage verwendet werden",
+    ),
+    (
+        "Template input {} doesn't exist, the template has {} inputs",
+        "Vorlageneingabe {} existiert nicht, die Vorlage hat {} Eingaben",
+    ),
+    ("{}, in template {}", "{}, in Vorlage {}"),
+    ("Can't find catch tag for {}", "Kein catch für die Marke {}"),
+    (
+        "'continue' can only be used while paused",
+        "'continue' kann nur während einer Pause verwendet werden",
+    ),
+    (
+        "Stack overflow calling '{}', calls are nested {} deep",
+        "Stapelüberlauf beim Aufruf von '{}', die Aufrufe sind {} Ebenen tief verschachtelt",
+    ),
+];
@@ -0,0 +1,214 @@
+// Spanish names, after the Spanish versions of MSWLogo and XLogo
+
+pub(super) const ALIASES: &[(&str, &str)] = &[
+    // keywords
+    ("repite", "repeat"),
+    ("haz", "make"),
+    ("para", "to"),
+    ("fin", "end"),
+    ("devuelve", "output"),
+    ("alto", "stop"),
+    ("si", "if"),
+    ("sisino", "ifelse"),
+    // turtle commands
+    ("avanza", "forward"),
+    ("av", "forward"),
+    ("retrocede", "backward"),
+    ("re", "backward"),
+    ("giraizquierda", "left"),
+    ("gi", "left"),
+    ("giraderecha", "right"),
+    ("gd", "right"),
+    ("fijarumbo", "setheading"),
+    ("fijaxy", "setxy"),
+    ("centro", "home"),
+    ("subelápiz", "penup"),
+    ("subelapiz", "penup"),
+    ("sl", "penup"),
+    ("bajalápiz", "pendown"),
+    ("bajalapiz", "pendown"),
+    ("bl", "pendown"),
+    ("fijagrosor", "setpensize"),
+    ("fijacolorlápiz", "setpencolor"),
+    ("fijacolorlapiz", "setpencolor"),
+    ("ocultatortuga", "hideturtle"),
+    ("ot", "hideturtle"),
+    ("muestratortuga", "showturtle"),
+    ("mt", "showturtle"),
+    ("borrapantalla", "clearscreen"),
+    ("bp", "clearscreen"),
+    ("limpia", "clean"),
+    ("fijacolorfondo", "setscreencolor"),
+    ("muestra", "show"),
+    ("salir", "exit"),
+    // primitives
+    ("suma", "sum"),
+    ("diferencia", "difference"),
+    ("producto", "product"),
+    ("cociente", "quotient"),
+    ("resto", "remainder"),
+    ("menos", "minus"),
+    ("hazlocal", "localmake"),
+    ("valor", "thing"),
+    ("escribe", "print"),
+    ("borra", "erase"),
+    ("borratodo", "erall"),
+    ("procedimientos", "procedures"),
+    ("nombres", "names"),
+    ("guarda", "save"),
+    ("carga", "load"),
+    ("importa", "import"),
+    ("ejecuta", "run"),
+    ("aplica", "apply"),
+    ("primero", "first"),
+    ("último", "last"),
+    ("ultimo", "last"),
+    ("menosprimero", "butfirst"),
+    ("mp", "butfirst"),
+    ("menosúltimo", "butlast"),
+    ("menosultimo", "butlast"),
+    ("mu", "butlast"),
+    ("cuenta", "count"),
+    ("elemento", "item"),
+    ("ponprimero", "fput"),
+    ("ponúltimo", "lput"),
+    ("ponultimo", "lput"),
+    ("lista", "list"),
+    ("vacíop", "emptyp"),
+    ("vaciop", "emptyp"),
+    ("atrapa", "catch"),
+    ("lanza", "throw"),
+    ("transforma", "map"),
+    ("filtra", "filter"),
+    ("paracada", "foreach"),
+];
+
+pub(super) const MESSAGES: &[(&str, &str)] = &[
+    ("{} at {}", "{} en {}"),
+    ("line {}, column {}", "línea {}, columna {}"),
+    ("in {}", "en {}"),
+    ("called from {}", "llamado desde {}"),
+    ("... {} more calls", "... {} llamadas más"),
+    (
+        "called from top level{}",
+        "llamado desde el nivel superior{}",
+    ),
+    (", line {}", ", línea {}"),
+    (
+        "Found unexpected character '{}' at {}",
+        "Carácter inesperado '{}' en {}",
+    ),
+    (
+        "Reached EOF (End of file) while parsing",
+        "Se llegó al final del archivo durante el análisis",
+    ),
+    (
+        "Unexpected token: {}. Expected: {}",
+        "Símbolo inesperado: {}. Se esperaba: {}",
+    ),
+    (
+        "Found unexpected type while parsing, expected {}",
+        "Tipo inesperado durante el análisis, se esperaba {}",
+    ),
+    (
+        "Error while parsing integer: {}",
+        "Error al leer el número entero: {}",
+    ),
+    (
+        "Found unbalanced parentheses while parsing",
+        "Paréntesis desequilibrados durante el análisis",
+    ),
+    ("Not enough inputs to '{}'", "Faltan entradas para '{}'"),
+    (
+        "Procedure '{}' can't take {} inputs by default",
+        "El procedimiento '{}' no puede tomar {} entradas por defecto",
+    ),
+    (
+        "Procedure '{}' has already been declared",
+        "El procedimiento '{}' ya está definido",
+    ),
+    (
+        "Procedure '{}' does not exist; did you mean '{}'?",
+        "El procedimiento '{}' no existe; ¿quiso decir '{}'?",
+    ),
+    (
+        "Procedure '{}' does not exist",
+        "El procedimiento '{}' no existe",
+    ),
+    (
+        "Variable :{} has not been declared; did you mean :{}?",
+        "La variable :{} no está declarada; ¿quiso decir :{}?",
+    ),
+    (
+        "Variable :{} has not been declared",
+        "La variable :{} no está declarada",
+    ),
+    (
+        "Variable :{} has no value",
+        "La variable :{} no tiene valor",
+    ),
+    (
+        "'{}' doesn't like {} as input",
+        "A '{}' no le gusta {} como entrada",
+    ),
+    (
+        "Wrong number of arguments to '{}', expected {} but got {}",
+        "Número incorrecto de argumentos para '{}': se esperaban {} pero se recibieron {}",
+    ),
+    (
+        "'{}' can't divide {} by zero",
+        "'{}' no puede dividir {} por cero",
+    ),
+    (
+        "'{}' overflowed with inputs {}",
+        "Desbordamiento aritmético en '{}' con las entradas {}",
+    ),
+    (
+        "Malformed arithmetic expression at '{}'",
+        "Expresión aritmética mal formada en '{}'",
+    ),
+    ("'{}' is a primitive", "'{}' es una primitiva"),
+    (
+        "Procedure '{}' did not output a value",
+        "El procedimiento '{}' no devolvió ningún valor",
+    ),
+    (
+        "You don't say what to do with the output of '{}'",
+        "No dice qué hacer con el resultado de '{}'",
+    ),
+    (
+        "'{}' can only be used inside a procedure",
+        "'{}' solo se puede usar dentro de un procedimiento",
+    ),
+    ("Error with file '{}': {}", "Error con el archivo '{}': {}"),
+    (
+        "Could not find module '{}' on the search path",
+        "No se encontró el módulo '{}' en la ruta de búsqueda",
+    ),
+    ("Import cycle: {}", "Ciclo de importación: {}"),
+    (
+        "Invalid instruction list given to '{}': {}",
+        "Lista de instrucciones no válida para '{}': {}",
+    ),
+    (
+        "'{}' can only be used inside a template",
+        "'{}' solo se puede usar dentro de una plantilla",
+    ),
+    (
+        "Template input {} doesn't exist, the template has {} inputs",
+        "La entrada de plantilla {} no existe, la plantilla tiene {} entradas",
+    ),
+    ("{}, in template {}", "{}, en la plantilla {}"),
+    (
+        "Can't find catch tag for {}",
+        "No se encuentra un catch para la etiqueta {}",
+    ),
+    (
+        "'continue' can only be used while paused",
+        "'continue' solo se puede usar durante una pausa",
+    ),
+    (
+        "Stack overflow calling '{}', calls are nested {} deep",
+        "Desbordamiento de pila al llamar a '{}', las llamadas están anidadas {} niveles",
+    ),
+];
@@ -0,0 +1,223 @@
+// French names, after the French versions of UCBLogo and MSWLogo
+
+pub(super) const ALIASES: &[(&str, &str)] = &[
+    // keywords
+    ("répète", "repeat"),
+    ("repete", "repeat"),
+    ("donne", "make"),
+    ("pour", "to"),
+    ("fin", "end"),
+    ("retourne", "output"),
+    ("rends", "output"),
+    ("si", "if"),
+    ("sisinon", "ifelse"),
+    // turtle commands
+    ("avance", "forward"),
+    ("av", "forward"),
+    ("recule", "backward"),
+    ("re", "backward"),
+    ("tournegauche", "left"),
+    ("tg", "left"),
+    ("tournedroite", "right"),
+    ("td", "right"),
+    ("fixecap", "setheading"),
+    ("fcap", "setheading"),
+    ("fixexy", "setxy"),
+    ("origine", "home"),
+    ("levecrayon", "penup"),
+    ("lc", "penup"),
+    ("baissecrayon", "pendown"),
+    ("bc", "pendown"),
+    ("fixetaillecrayon", "setpensize"),
+    ("fixecouleurcrayon", "setpencolor"),
+    ("fcc", "setpencolor"),
+    ("cachetortue", "hideturtle"),
+    ("ct", "hideturtle"),
+    ("montretortue", "showturtle"),
+    ("mt", "showturtle"),
+    ("videécran", "clearscreen"),
+    ("videecran", "clearscreen"),
+    ("ve", "clearscreen"),
+    ("nettoie", "clean"),
+    ("fixecouleurfond", "setscreencolor"),
+    ("fcf", "setscreencolor"),
+    ("montre", "show"),
+    ("quitte", "exit"),
+    // primitives
+    ("somme", "sum"),
+    ("différence", "difference"),
+    ("produit", "product"),
+    ("reste", "remainder"),
+    ("moins", "minus"),
+    ("locale", "local"),
+    ("donnelocale", "localmake"),
+    ("globale", "global"),
+    ("chose", "thing"),
+    ("écris", "print"),
+    ("ecris", "print"),
+    ("ec", "print"),
+    ("efface", "erase"),
+    ("effacetout", "erall"),
+    ("procédures", "procedures"),
+    ("noms", "names"),
+    ("sauve", "save"),
+    ("charge", "load"),
+    ("importe", "import"),
+    ("exécute", "run"),
+    ("execute", "run"),
+    ("applique", "apply"),
+    ("premier", "first"),
+    ("dernier", "last"),
+    ("saufpremier", "butfirst"),
+    ("sp", "butfirst"),
+    ("saufdernier", "butlast"),
+    ("sd", "butlast"),
+    ("compte", "count"),
+    ("élément", "item"),
+    ("element", "item"),
+    ("metspremier", "fput"),
+    ("metsdernier", "lput"),
+    ("liste", "list"),
+    ("videp", "emptyp"),
+    ("attrape", "catch"),
+    ("lance", "throw"),
+    ("erreur", "error"),
+    ("transforme", "map"),
+    ("filtre", "filter"),
+    ("réduis", "reduce"),
+    ("reduis", "reduce"),
+    ("pourchaque", "foreach"),
+];
+
+pub(super) const MESSAGES: &[(&str, &str)] = &[
+    ("{} at {}", "{} à {}"),
+    ("line {}, column {}", "ligne {}, colonne {}"),
+    ("in {}", "dans {}"),
+    ("called from {}", "appelé depuis {}"),
+    ("... {} more calls", "... {} appels de plus"),
+    (
+        "called from top level{}",
+        "appelé depuis le niveau principal{}",
+    ),
+    (", line {}", ", ligne {}"),
+    (
+        "Found unexpected character '{}' at {}",
+        "Caractère inattendu '{}' à {}",
+    ),
+    (
+        "Reached EOF (End of file) while parsing",
+        "Fin du fichier atteinte pendant l'analyse",
+    ),
+    (
+        "Unexpected token: {}. Expected: {}",
+        "Symbole inattendu : {}. Attendu : {}",
+    ),
+    (
+        "Found unexpected type while parsing, expected {}",
+        "Type inattendu pendant l'analyse, attendu : {}",
+    ),
+    (
+        "Error while parsing integer: {}",
+        "Erreur de lecture de l'entier : {}",
+    ),
+    (
+        "Found unbalanced parentheses while parsing",
+        "Parenthèses non équilibrées pendant l'analyse",
+    ),
+    ("Not enough inputs to '{}'", "Pas assez d'entrées pour '{}'"),
+    (
+        "Procedure '{}' can't take {} inputs by default",
+        "La procédure '{}' ne peut pas prendre {} entrées par défaut",
+    ),
+    (
+        "Procedure '{}' has already been declared",
+        "La procédure '{}' est déjà définie",
+    ),
+    (
+        "Procedure '{}' does not exist; did you mean '{}'?",
+        "La procédure '{}' n'existe pas ; vouliez-vous dire '{}' ?",
+    ),
+    (
+        "Procedure '{}' does not exist",
+        "La procédure '{}' n'existe pas",
+    ),
+    (
+        "Variable :{} has not been declared; did you mean :{}?",
+        "La variable :{} n'est pas déclarée ; vouliez-vous dire :{} ?",
+    ),
+    (
+        "Variable :{} has not been declared",
+        "La variable :{} n'est pas déclarée",
+    ),
+    (
+        "Variable :{} has no value",
+        "La variable :{} n'a pas de valeur",
+    ),
+    (
+        "'{}' doesn't like {} as input",
+        "'{}' n'aime pas {} comme entrée",
+    ),
+    (
+        "Wrong number of arguments to '{}', expected {} but got {}",
+        "Mauvais nombre d'arguments pour '{}' : {} attendus mais {} reçus",
+    ),
+    (
+        "'{}' can't divide {} by zero",
+        "'{}' ne peut pas diviser {} par zéro",
+    ),
+    (
+        "'{}' overflowed with inputs {}",
+        "Dépassement de capacité de '{}' avec les entrées {}",
+    ),
+    (
+        "Malformed arithmetic expression at '{}'",
+        "Expression arithmétique mal formée à '{}'",
+    ),
+    ("'{}' is a primitive", "'{}' est une primitive"),
+    (
+        "Procedure '{}' did not output a value",
+        "La procédure '{}' n'a pas retourné de valeur",
+    ),
+    (
+        "You don't say what to do with the output of '{}'",
+        "Vous ne dites pas quoi faire du résultat de '{}'",
+    ),
+    (
+        "'{}' can only be used inside a procedure",
+        "'{}' ne peut être utilisé que dans une procédure",
+    ),
+    (
+        "Error with file '{}': {}",
+        "Erreur avec le fichier '{}' : {}",
+    ),
+    (
+        "Could not find module '{}' on the search path",
+        "Module '{}' introuvable dans le chemin de recherche",
+    ),
+    ("Import cycle: {}", "Cycle d'importation : {}"),
+    (
+        "Invalid instruction list given to '{}': {}",
+        "Liste d'instructions invalide pour '{}' : {}",
+    ),
+    (
+        "'{}' can only be used inside a template",
+        "'{}' ne peut être utilisé que dans un modèle",
+    ),
+    (
+        "Template input {} doesn't exist, the template has {} inputs",
+        "L'entrée de modèle {} n'existe pas, le modèle a {} entrées",
+    ),
+    ("{}, in template {}", "{}, dans le modèle {}"),
+    (
+        "Can't find catch tag for {}",
+        "Aucun catch pour l'étiquette {}",
+    ),
+    (
+        "'continue' can only be used while paused",
+        "'continue' ne peut être utilisé que pendant une pause",
+    ),
+    (
+        "Stack overflow calling '{}', calls are nested {} deep",
+        "Débordement de pile en appelant '{}', les appels sont imbriqués sur {} niveaux",
+    ),
+];
@@ -0,0 +1,215 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::error::RuntimeError;
+use crate::locale::Naming;
+use crate::parser::{Expression, Statement, AST};
+use crate::value::Value;
+
+// extensions tried, in order, when an imported module is named without one
+const EXTENSIONS: [&str; 2] = ["lgo", "logo"];
+
+/// Locates the files of imported modules and keeps track of which modules have
+/// been imported, so that every module is only run once and import cycles are
+/// detected.
+pub struct Modules {
+    search_path: Vec<PathBuf>,
+    // modules that have been imported, with the namespace they were imported into
+    imported: HashSet<(PathBuf, Option<String>)>,
+    // stack of the modules that are currently being imported
+    importing: Vec<PathBuf>,
+}
+
+impl Modules {
+    pub fn new() -> Self {
+        Modules {
+            search_path: Vec::new(),
+            imported: HashSet::new(),
+            importing: Vec::new(),
+        }
+    }
+
+    /// Adds a directory that is searched for modules after the current directory.
+    pub fn add_search_path(&mut self, dir: PathBuf) {
+        self.search_path.push(dir);
+    }
+
+    /// Returns the canonical path of the file for `name`. The name is looked up
+    /// relative to the directory of the module currently being imported, then the
+    /// current directory, then every directory of the search path. In each of them
+    /// the name is tried as given and with every extension in EXTENSIONS.
+    pub fn resolve(&self, name: &str) -> Result<PathBuf, RuntimeError> {
+        let mut dirs: Vec<&Path> = Vec::new();
+        if let Some(dir) = self.importing.last().and_then(|module| module.parent()) {
+            dirs.push(dir);
+        }
+        dirs.push(Path::new(""));
+        dirs.extend(self.search_path.iter().map(PathBuf::as_path));
+
+        for dir in dirs {
+            let path = dir.join(name);
+            let candidates = std::iter::once(path.clone())
+                .chain(EXTENSIONS.iter().map(|ext| path.with_extension(ext)));
+            for candidate in candidates {
+                if candidate.is_file() {
+                    if let Ok(canonical) = candidate.canonicalize() {
+                        return Ok(canonical);
+                    }
+                }
+            }
+        }
+
+        Err(RuntimeError::ModuleNotFound {
+            name: name.to_string(),
+        })
+    }
+
+    /// Returns the module currently being imported, if any.
+    pub fn current(&self) -> Option<&PathBuf> {
+        self.importing.last()
+    }
+
+    pub fn is_imported(&self, path: &Path, namespace: &Option<String>) -> bool {
+        self.imported
+            .contains(&(path.to_path_buf(), namespace.clone()))
+    }
+
+    /// Marks `path` as being imported, failing if that would close an import cycle.
+    pub fn begin_import(&mut self, path: &Path) -> Result<(), RuntimeError> {
+        if self.importing.iter().any(|module| module == path) {
+            let chain = self
+                .importing
+                .iter()
+                .skip_while(|module| *module != path)
+                .chain(std::iter::once(&path.to_path_buf()))
+                .map(|module| module.display().to_string())
+                .collect();
+            return Err(RuntimeError::ImportCycle { chain });
+        }
+
+        self.importing.push(path.to_path_buf());
+        Ok(())
+    }
+
+    /// Ends the import of the innermost module, recording it as imported if the
+    /// import succeeded.
+    pub fn end_import(&mut self, namespace: Option<String>, succeeded: bool) {
+        if let Some(path) = self.importing.pop() {
+            if succeeded {
+                self.imported.insert((path, namespace));
+            }
+        }
+    }
+}
+
+/// Prefixes the name of every procedure declared at the top level of `ast` with
+/// `namespace` and a dot, renaming the calls to them throughout `ast` as well.
+/// Importing `shapes` into the namespace `s` turns `square` into `s.square`.
+/// Lists may be run as code and words name procedures for `map` and the like, so
+/// `[square 10]` and `"square` are renamed too.
+/// Calls are matched to declarations by their key in `naming`, so `Square` is
+/// renamed too unless names are case sensitive.
+pub fn namespace_procedures(ast: &mut AST, namespace: &str, naming: Naming) {
+    let names: HashMap<String, String> = ast
+        .statements
+        .iter()
+        .filter_map(|stmt| match stmt {
+            Statement::ProcedureDeclaration { name, .. } => {
+                Some((naming.key(name), format!("{}.{}", namespace, name)))
+            }
+            _ => None,
+        })
+        .collect();
+
+    rename_in_ast(ast, &names, naming);
+}
+
+// `names` maps the keys of the old names to the new names
+fn rename_in_ast(ast: &mut AST, names: &HashMap<String, String>, naming: Naming) {
+    for stmt in ast.statements.iter_mut() {
+        match stmt {
+            Statement::Repeat { count, body } => {
+                rename_in_expression(count, names, naming);
+                rename_in_ast(body, names, naming);
+            }
+            Statement::VariableDeclaration { name: _, val } => {
+                rename_in_expression(val, names, naming)
+            }
+            Statement::ProcedureDeclaration {
+                name,
+                body,
+                optional,
+                ..
+            } => {
+                rename(name, names, naming);
+                rename_in_ast(body, names, naming);
+                // default values of optional inputs may call the module's procedures
+                optional
+                    .iter_mut()
+                    .for_each(|(_, default)| rename_in_expression(default, names, naming));
+            }
+            Statement::ProcedureCall { name, args } => {
+                rename(name, names, naming);
+                args.iter_mut()
+                    .for_each(|arg| rename_in_expression(arg, names, naming));
+            }
+            Statement::If {
+                condition,
+                body,
+                else_body,
+            } => {
+                rename_in_expression(condition, names, naming);
+                rename_in_ast(body, names, naming);
+                if let Some(else_body) = else_body {
+                    rename_in_ast(else_body, names, naming);
+                }
+            }
+            Statement::Output { val } => rename_in_expression(val, names, naming),
+            Statement::Stop => (),
+        }
+    }
+}
+
+fn rename_in_expression(expr: &mut Expression, names: &HashMap<String, String>, naming: Naming) {
+    match expr {
+        Expression::ArithmeticExpression { postfix } => postfix
+            .iter_mut()
+            .for_each(|expr| rename_in_expression(expr, names, naming)),
+        Expression::ProcedureCall { name, args } => {
+            rename(name, names, naming);
+            args.iter_mut()
+                .for_each(|arg| rename_in_expression(arg, names, naming));
+        }
+        Expression::Word { literal } => rename(literal, names, naming),
+        Expression::List { items } => items
+            .iter_mut()
+            .for_each(|item| rename_in_value(item, names, naming)),
+        Expression::Operator { .. } | Expression::Number { .. } | Expression::Variable { .. } => {}
+    }
+}
+
+// words of a list are calls when the list is run, and quoted words in it are
+// names given to the procedures it calls
+fn rename_in_value(value: &mut Value, names: &HashMap<String, String>, naming: Naming) {
+    match value {
+        Value::List(items) => items
+            .iter_mut()
+            .for_each(|item| rename_in_value(item, names, naming)),
+        Value::Word(word) => {
+            let (quote, name) = match word.strip_prefix('"') {
+                Some(name) => ("\"", name),
+                None => ("", word.as_str()),
+            };
+            if let Some(new_name) = names.get(&naming.key(name)) {
+                *word = format!("{}{}", quote, new_name);
+            }
+        }
+        Value::Number(_) => (),
+    }
+}
+
+fn rename(name: &mut String, names: &HashMap<String, String>, naming: Naming) {
+    if let Some(new_name) = names.get(&naming.key(name)) {
+        *name = new_name.to_string();
+    }
+}
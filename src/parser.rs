@@ -1,6 +1,12 @@
+use crate::command::Command;
 use crate::error::ParseError;
-use crate::lexer::{Operator, Token};
+use crate::lexer::{Operator, Span, Token};
+use crate::locale::Naming;
+use crate::primitive::Primitive;
+use crate::value::Value;
+use std::collections::HashMap;
 use std::iter::Peekable;
+use std::ops::Range;
 use std::slice;
 
 /// Statements are any logo 'sentence' that does not evaluate to a value
@@ -18,11 +24,27 @@ pub enum Statement {
         name: String,
         body: AST,
         params: Vec<String>,
+        // inputs that may be left out, with the expression giving their default
+        // value, and the input collecting any extra inputs as a list
+        optional: Vec<(String, Expression)>,
+        rest: Option<String>,
+        // number of inputs taken by unparenthesized calls, if it isn't the number
+        // of required inputs
+        default_arity: Option<usize>,
     },
     ProcedureCall {
         name: String,
         args: Vec<Expression>,
     },
+    If {
+        condition: Expression,
+        body: AST,
+        else_body: Option<AST>,
+    },
+    Output {
+        val: Expression,
+    },
+    Stop,
 }
 
 /// Expressions are any logo 'sentence' that evaluates to a value
@@ -31,40 +53,331 @@ pub enum Expression {
     ArithmeticExpression { postfix: Vec<Expression> },
     Operator { op: Operator },
     Number { val: isize },
+    Word { literal: String },
     Variable { name: String },
+    ProcedureCall { name: String, args: Vec<Expression> },
+    List { items: Vec<Value> },
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, Clone)]
 pub struct AST {
     pub statements: Vec<Statement>,
+    // the span of each statement, empty if the parser wasn't given spans
+    pub spans: Vec<Span>,
+}
+
+// custom implementation to only consider the statements, not where they were read
+impl PartialEq for AST {
+    fn eq(&self, other: &Self) -> bool {
+        self.statements == other.statements
+    }
 }
 
 impl AST {
     pub fn new() -> Self {
-        AST { statements: vec![] }
+        AST::from(vec![])
+    }
+}
+
+impl From<Vec<Statement>> for AST {
+    fn from(statements: Vec<Statement>) -> Self {
+        AST {
+            statements,
+            spans: vec![],
+        }
+    }
+}
+
+/// The inputs declared on the title line of a procedure, found from the tokens
+/// alone so that procedures can be found before a program is parsed, or in
+/// programs that don't parse.
+pub(crate) struct TitleLine {
+    /// Names of the required inputs.
+    pub params: Vec<String>,
+    /// The tokens of each optional input, from its `[` to its `]`.
+    pub optional: Vec<Range<usize>>,
+    pub rest: Option<String>,
+    pub default_arity: Option<usize>,
+}
+
+impl TitleLine {
+    /// Scans the inputs declared by `tokens`, the tokens following the name of a
+    /// procedure, as in `:sides [:size 50] [:rest] 2`.
+    pub fn scan(tokens: &[Token]) -> Self {
+        let params: Vec<String> = tokens
+            .iter()
+            .map_while(|tok| match tok {
+                Token::Variable { name } => Some(name.to_string()),
+                _ => None,
+            })
+            .collect();
+
+        let mut optional = Vec::new();
+        let mut rest = None;
+        let mut i = params.len();
+        while rest.is_none() && tokens.get(i) == Some(&Token::LBracket) {
+            // the default value may be a list, so brackets are matched
+            let mut depth = 0;
+            let close = tokens[i..].iter().position(|tok| {
+                match tok {
+                    Token::LBracket => depth += 1,
+                    Token::RBracket => depth -= 1,
+                    _ => (),
+                }
+                depth == 0
+            });
+            let end = match close {
+                Some(close) => i + close + 1,
+                None => break,
+            };
+            match &tokens[i + 1..end] {
+                [Token::Variable { name }, Token::RBracket] => rest = Some(name.to_string()),
+                _ => optional.push(i..end),
+            }
+            i = end;
+        }
+
+        let default_arity = match tokens.get(i) {
+            Some(Token::Number { literal }) => literal.parse().ok(),
+            _ => None,
+        };
+        TitleLine {
+            params,
+            optional,
+            rest,
+            default_arity,
+        }
+    }
+
+    /// The number of inputs taken by unparenthesized calls of the procedure.
+    pub fn arity(&self) -> usize {
+        self.default_arity.unwrap_or(self.params.len())
     }
 }
 
 pub struct Parser<'a> {
     tokens: Peekable<slice::Iter<'a, Token>>,
+    all_tokens: &'a [Token],
+    // spans of the tokens, if known
+    spans: &'a [Span],
+    // whether each token is written right after the one before it, without
+    // whitespace, if known
+    adjacent: Vec<bool>,
+    // number of inputs taken by each user defined procedure
+    arities: HashMap<String, usize>,
+    // errors skipped over by `build_ast_recovering`, None if the parser stops at
+    // the first error
+    errors: Option<Vec<(ParseError, Option<Span>)>>,
+    // tokens closing the blocks and procedure bodies being parsed, innermost last
+    closers: Vec<Token>,
+    // how names are matched to primitives and procedures
+    naming: Naming,
 }
 
 impl<'a> Parser<'a> {
-    pub fn new(tokens: &'a Vec<Token>) -> Self {
+    pub fn new(tokens: &'a [Token]) -> Self {
+        Parser::with_arities(tokens, HashMap::new())
+    }
+
+    /// Creates a parser that knows the arities of procedures declared outside of
+    /// `tokens`, e.g. by earlier programs run by the same evaluator. Procedures
+    /// declared with `to` inside `tokens` are added to the table as well, so they
+    /// can be called before their declaration.
+    pub fn with_arities(tokens: &'a [Token], mut arities: HashMap<String, usize>) -> Self {
+        for (i, tok) in tokens.iter().enumerate() {
+            if let (Token::To, Some(Token::Identifier { literal })) = (tok, tokens.get(i + 1)) {
+                arities.insert(
+                    literal.to_string(),
+                    TitleLine::scan(&tokens[i + 2..]).arity(),
+                );
+            }
+        }
+
         Parser {
             tokens: tokens.iter().peekable(),
+            all_tokens: tokens,
+            spans: &[],
+            adjacent: Vec::new(),
+            arities,
+            errors: None,
+            closers: Vec::new(),
+            naming: Naming::default(),
         }
     }
 
+    /// Sets how names are matched to primitives and procedures. By default they
+    /// match in any case, so `FD 10` calls `fd`, and only English names of
+    /// primitives are known.
+    pub(crate) fn naming(mut self, naming: Naming) -> Self {
+        self.naming = naming;
+        self
+    }
+
+    /// Gives the parser the span of every token, as returned by `Lexer::spans`, so
+    /// that statements and errors can be located in the source.
+    pub fn with_spans(mut self, spans: &'a [Span]) -> Self {
+        self.spans = spans;
+        self.with_spacing(spans)
+    }
+
+    /// Gives the parser the span of every token only to tell which tokens are
+    /// written without whitespace between them, see `parse_list`. Statements are
+    /// not located, as for lists run as code, which are located where they are run.
+    pub(crate) fn with_spacing(mut self, spans: &[Span]) -> Self {
+        let follows = |(i, span): (usize, &Span)| i > 0 && spans[i - 1].end == span.start;
+        self.adjacent = spans.iter().enumerate().map(follows).collect();
+        self
+    }
+
+    /// Returns the span of the token the parser stopped at with `error`. None if
+    /// the parser wasn't given spans.
+    pub fn error_span(&self, error: &ParseError) -> Option<Span> {
+        let position = self.position();
+        // most errors are found after consuming the offending token, but unexpected
+        // tokens are often found by peeking at the next one
+        if let ParseError::UnexpectedToken(found, _) = error {
+            let last = position.checked_sub(1).map(|i| &self.all_tokens[i]);
+            if last != Some(found) && self.all_tokens.get(position) == Some(found) {
+                return self.spans.get(position).copied();
+            }
+        }
+        self.last_span()
+    }
+
+    // index of the next token to be parsed
+    fn position(&self) -> usize {
+        self.all_tokens.len() - self.tokens.len()
+    }
+
+    // span of the last token parsed
+    fn last_span(&self) -> Option<Span> {
+        self.spans.get(self.position().checked_sub(1)?).copied()
+    }
+
     pub fn build_ast(&mut self) -> Result<AST, ParseError> {
         let mut ast = AST::new();
 
         while let Some(tok) = self.tokens.next() {
-            ast.statements.push(self.parse_statement(tok)?);
+            self.push_statement(&mut ast, tok)?;
         }
         Ok(ast)
     }
 
+    /// Parses a program like `build_ast`, but rather than stopping at the first
+    /// error it records the error and its span and carries on from the next
+    /// statement boundary, see `recover`. Returns the statements that could be
+    /// parsed along with every error, so that all of them can be reported at once.
+    pub fn build_ast_recovering(&mut self) -> (AST, Vec<(ParseError, Option<Span>)>) {
+        self.errors = Some(Vec::new());
+        let ast = match self.build_ast() {
+            Ok(ast) => ast,
+            // every error is recovered from by `push_statement`
+            Err(_) => AST::new(),
+        };
+        (ast, self.errors.take().unwrap_or_default())
+    }
+
+    /// Parses the statement starting with `token`, which has just been consumed,
+    /// and adds it to `ast` along with its span.
+    fn push_statement(&mut self, ast: &mut AST, token: &Token) -> Result<(), ParseError> {
+        let start = self.position() - 1;
+        let statement = match self.parse_statement(token) {
+            Ok(statement) => statement,
+            Err(error) if self.errors.is_some() => {
+                let span = self.error_span(&error);
+                if let Some(errors) = &mut self.errors {
+                    errors.push((error, span));
+                }
+                self.recover(token);
+                return Ok(());
+            }
+            Err(error) => return Err(error),
+        };
+
+        ast.statements.push(statement);
+        if let (Some(first), Some(last)) = (self.spans.get(start), self.last_span()) {
+            ast.spans.push(first.to(&last));
+        }
+        Ok(())
+    }
+
+    /// Skips the rest of a statement starting with `token` that failed to parse, up
+    /// to the next line or the `]` or `end` closing the block it is in. Lists on
+    /// the way are skipped whole. A procedure declaration whose title line failed
+    /// to parse is skipped up to and including its `end`.
+    fn recover(&mut self, token: &Token) {
+        if *token == Token::To {
+            self.tokens.find(|tok| **tok == Token::End);
+            return;
+        }
+
+        let line = self.last_span().map(|span| span.line);
+        let mut depth = 0;
+        while let Some(&tok) = self.tokens.peek() {
+            let next_line = self.spans.get(self.position()).map(|span| span.line);
+            match tok {
+                Token::RBracket | Token::End if depth == 0 && self.closers.contains(tok) => return,
+                _ if depth == 0 && next_line > line => return,
+                Token::LBracket => depth += 1,
+                Token::RBracket if depth > 0 => depth -= 1,
+                _ => (),
+            }
+            self.tokens.next();
+        }
+    }
+
+    /// Parses a list of instructions that is run as code, as by `run`. Unlike a
+    /// program, the list may end with an expression whose value is output by the
+    /// list, such as `[fd 10 sum 1 2]` or `[:x * 2]`. A trailing procedure call is
+    /// returned as that expression, whether or not the procedure outputs a value.
+    pub fn build_instruction_list(&mut self) -> Result<(AST, Option<Expression>), ParseError> {
+        let mut ast = AST::new();
+
+        while let Some(&tok) = self.tokens.peek() {
+            if let Token::Identifier { literal: _ } = tok {
+                // the rest of the list may be an expression starting with a
+                // procedure call, such as `[? * 2]` or `[sum 1 2]`
+                let start = self.tokens.clone();
+                if let Ok(val) = self.parse_expression() {
+                    if self.tokens.peek().is_none() {
+                        return Ok((ast, Some(val)));
+                    }
+                }
+                self.tokens = start;
+            }
+
+            let is_statement = match tok {
+                Token::LParen => {
+                    let mut lookahead = self.tokens.clone();
+                    lookahead.next();
+                    matches!(lookahead.next(), Some(Token::Identifier { literal: _ }))
+                }
+                Token::Number { literal: _ }
+                | Token::Word { literal: _ }
+                | Token::Variable { name: _ }
+                | Token::LBracket
+                | Token::Operator(_) => false,
+                _ => true,
+            };
+
+            if !is_statement {
+                let val = self.parse_expression()?;
+                return match self.tokens.next() {
+                    Some(tok) => Err(ParseError::UnexpectedToken(
+                        tok.clone(),
+                        vec![Token::RBracket],
+                    )),
+                    None => Ok((ast, Some(val))),
+                };
+            }
+
+            self.tokens.next();
+            self.push_statement(&mut ast, tok)?;
+        }
+
+        Ok((ast, None))
+    }
+
     /// expect that the next token is 'Token' and return an Err if this is not the case
     /// it is okay to conusme the next token in the underlying Iterator here because
     /// the parser will be in an error state if the next token does not match `expected`
@@ -89,18 +402,46 @@ impl<'a> Parser<'a> {
         match token {
             Repeat => self.parse_repeat(),
 
+            If => self.parse_if(false),
+
+            IfElse => self.parse_if(true),
+
             Make => self.parse_variable_declaration(),
 
             To => self.parse_procedure_declaration(),
 
             Identifier { literal } => self.parse_procedure_call(literal),
 
+            LParen => match self.tokens.next() {
+                Some(Identifier { literal }) => Ok(Statement::ProcedureCall {
+                    name: literal.to_string(),
+                    args: self.parse_parenthesized_arguments()?,
+                }),
+                Some(tok) => Err(ParseError::UnexpectedToken(
+                    tok.clone(),
+                    vec![Identifier {
+                        literal: "".to_string(),
+                    }],
+                )),
+                None => Err(ParseError::EOF),
+            },
+
+            Output => Ok(Statement::Output {
+                val: self.parse_expression()?,
+            }),
+
+            Stop => Ok(Statement::Stop),
+
             _ => Err(ParseError::UnexpectedToken(
                 token.clone(),
                 vec![
                     Repeat,
+                    If,
+                    IfElse,
                     Make,
                     To,
+                    Output,
+                    Stop,
                     Identifier {
                         literal: "".to_string(),
                     },
@@ -110,48 +451,145 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_procedure_call(&mut self, name: &str) -> Result<Statement, ParseError> {
+        Ok(Statement::ProcedureCall {
+            name: name.to_string(),
+            args: self.parse_arguments(name)?,
+        })
+    }
+
+    /// Returns the number of inputs `name` takes when called without parentheses,
+    /// or None if no primitive or known user procedure has that name.
+    fn arity(&self, name: &str) -> Option<usize> {
+        let key = self.naming.key(name);
+        if let Some(command) = Command::from_string(&key) {
+            Some(command.arity())
+        } else if let Some(primitive) = Primitive::from_string(&key) {
+            Some(primitive.arity())
+        } else if let Some(arity) = self.arities.get(name) {
+            Some(*arity)
+        } else if self.naming.case_sensitive {
+            None
+        } else {
+            let mut arities = self.arities.iter();
+            let found = arities.find(|(other, _)| self.naming.key(other) == key);
+            found.map(|(_, arity)| *arity)
+        }
+    }
+
+    /// Parses the arguments of an unparenthesized call to `name`. Known procedures
+    /// take exactly as many arguments as their arity, so `fd sum 1 2 rt 90` is two
+    /// statements. Arguments of unknown procedures are collected until the next
+    /// token that can't start an expression without being a procedure call.
+    fn parse_arguments(&mut self, name: &str) -> Result<Vec<Expression>, ParseError> {
         let mut args: Vec<Expression> = Vec::new();
 
+        if let Some(arity) = self.arity(name) {
+            for _ in 0..arity {
+                match self.tokens.peek() {
+                    Some(Token::Variable { name: _ })
+                    | Some(Token::Number { literal: _ })
+                    | Some(Token::Word { literal: _ })
+                    | Some(Token::Identifier { literal: _ })
+                    | Some(Token::LParen)
+                    | Some(Token::LBracket)
+                    | Some(Token::Operator(Operator::Subtraction))
+                    | Some(Token::Operator(Operator::Negation)) => {
+                        args.push(self.parse_expression()?)
+                    }
+                    _ => {
+                        return Err(ParseError::NotEnoughInputs {
+                            name: name.to_string(),
+                        })
+                    }
+                }
+            }
+            return Ok(args);
+        }
+
         while let Some(tok) = self.tokens.peek() {
             match tok {
-                Token::Variable { name: _ } | Token::Number { literal: _ } | Token::LParen => {
-                    args.push(self.parse_expression()?)
-                }
+                Token::Variable { name: _ }
+                | Token::Number { literal: _ }
+                | Token::Word { literal: _ }
+                | Token::LParen
+                | Token::LBracket
+                | Token::Operator(Operator::Subtraction)
+                | Token::Operator(Operator::Negation) => args.push(self.parse_expression()?),
                 _ => break,
             }
         }
 
-        Ok(Statement::ProcedureCall {
-            name: name.to_string(),
-            args,
-        })
+        Ok(args)
+    }
+
+    /// Parses the arguments of a parenthesized call such as `(sum 1 2 3)` up to and
+    /// including the closing parenthesis. The opening parenthesis and the procedure
+    /// name must already have been consumed.
+    fn parse_parenthesized_arguments(&mut self) -> Result<Vec<Expression>, ParseError> {
+        let mut args: Vec<Expression> = Vec::new();
+        loop {
+            match self.tokens.peek() {
+                Some(Token::RParen) => {
+                    self.tokens.next();
+                    return Ok(args);
+                }
+                Some(_) => args.push(self.parse_expression()?),
+                None => return Err(ParseError::UnbalancedParens),
+            }
+        }
     }
 
     fn parse_repeat(&mut self) -> Result<Statement, ParseError> {
         let count: Expression = self.parse_expression()?;
+        let body = self.parse_block()?;
 
-        self.expect(Token::LBracket)?;
-
-        let mut body: Vec<Statement> = Vec::new();
-        // parse expressions of repeat body until we find a closing bracket
-        loop {
-            let tok = match self.tokens.next() {
-                Some(tok) => match tok {
-                    Token::RBracket => break,
-                    _ => self.parse_statement(tok),
-                },
-                None => Err(ParseError::EOF),
-            }?;
+        Ok(Statement::Repeat { count, body })
+    }
 
-            body.push(tok);
-        }
+    /// Parses `if condition [ ... ]`, or `ifelse condition [ ... ] [ ... ]` if
+    /// `has_else` is set.
+    fn parse_if(&mut self, has_else: bool) -> Result<Statement, ParseError> {
+        let condition = self.parse_expression()?;
+        let body = self.parse_block()?;
+        let else_body = if has_else {
+            Some(self.parse_block()?)
+        } else {
+            None
+        };
 
-        Ok(Statement::Repeat {
-            count,
-            body: AST { statements: body },
+        Ok(Statement::If {
+            condition,
+            body,
+            else_body,
         })
     }
 
+    /// Parses a bracketed list of statements, such as the body of a repeat.
+    fn parse_block(&mut self) -> Result<AST, ParseError> {
+        self.expect(Token::LBracket)?;
+        self.parse_until(Token::RBracket)
+    }
+
+    /// Parses statements up to and including `closer`, which ends a block or the
+    /// body of a procedure.
+    fn parse_until(&mut self, closer: Token) -> Result<AST, ParseError> {
+        self.closers.push(closer.clone());
+        let mut body = AST::new();
+        let result = loop {
+            match self.tokens.next() {
+                Some(tok) if *tok == closer => break Ok(body),
+                Some(tok) => {
+                    if let Err(error) = self.push_statement(&mut body, tok) {
+                        break Err(error);
+                    }
+                }
+                None => break Err(ParseError::EOF),
+            }
+        };
+        self.closers.pop();
+        result
+    }
+
     fn parse_procedure_declaration(&mut self) -> Result<Statement, ParseError> {
         let name = self
             .expect(Token::Identifier {
@@ -173,22 +611,54 @@ impl<'a> Parser<'a> {
             }
         }
 
-        let mut body = AST::new();
-
-        // parse the body of the procedure until a repeat is found
-        loop {
-            let tok = match self.tokens.next() {
-                Some(tok) => match tok {
-                    Token::End => break,
-                    _ => self.parse_statement(tok),
-                },
-                None => Err(ParseError::EOF),
-            }?;
+        // optional inputs are followed by their default value, as in `[:size 50]`,
+        // and a rest input comes last, as in `[:rest]`
+        let mut optional = Vec::new();
+        let mut rest = None;
+        while rest.is_none() && self.tokens.peek() == Some(&&Token::LBracket) {
+            self.tokens.next();
+            let param = self
+                .expect(Token::Variable {
+                    name: "".to_string(),
+                })?
+                .value()
+                .unwrap()
+                .to_string();
+            if self.tokens.peek() == Some(&&Token::RBracket) {
+                rest = Some(param);
+            } else {
+                optional.push((param, self.parse_expression()?));
+            }
+            self.expect(Token::RBracket)?;
+        }
 
-            body.statements.push(tok);
+        // the title line may end with the number of inputs taken by unparenthesized
+        // calls, which is the number of required inputs by default
+        let mut default_arity = None;
+        if let Some(Token::Number { literal }) = self.tokens.peek() {
+            self.tokens.next();
+            let arity = match literal.parse() {
+                Ok(arity) => arity,
+                Err(_) => return Err(ParseError::ParseInteger(literal.to_string())),
+            };
+            let most = params.len() + optional.len();
+            if arity < params.len() || (rest.is_none() && arity > most) {
+                return Err(ParseError::InvalidDefaultArity { name, arity });
+            }
+            default_arity = Some(arity);
         }
 
-        Ok(Statement::ProcedureDeclaration { name, body, params })
+        // parse the body of the procedure until its end is found
+        let body = self.parse_until(Token::End)?;
+
+        Ok(Statement::ProcedureDeclaration {
+            name,
+            body,
+            params,
+            optional,
+            rest,
+            default_arity,
+        })
     }
 
     fn parse_variable_declaration(&mut self) -> Result<Statement, ParseError> {
@@ -207,109 +677,244 @@ impl<'a> Parser<'a> {
 
     /// Uses the shunting-yard algorithm for parsing arithmetic expressions.
     /// Parses the expression into postfix notation and returns an
-    /// Expression::ArithmeticExpression
-    fn parse_arithmetic_expression<T>(
-        tokens: &mut Peekable<T>,
-        first: Option<Expression>,
-    ) -> Result<Expression, ParseError>
-    where
-        T: Iterator<Item = &'a Token>,
-    {
+    /// Expression::ArithmeticExpression, or the operand itself if the expression
+    /// consists of a single operand.
+    ///
+    /// Words and procedure calls are operands too, so `3 * sum 1 2` multiplies by the
+    /// output of `sum`, whose arguments are in turn parsed as complete expressions.
+    ///
+    /// The expression ends as soon as an operand is found where an operator was
+    /// expected (`fd 10 20`, `fd 10 -5`), or at a closing parenthesis that was not
+    /// opened inside the expression, so consecutive arguments are split predictably.
+    fn parse_arithmetic_expression(&mut self) -> Result<Expression, ParseError> {
         let mut operator_stack: Vec<Token> = Vec::new();
-        let mut output: Vec<Expression> = match first {
-            Some(expr) => vec![expr],
-            None => vec![],
-        };
+        let mut output: Vec<Expression> = Vec::new();
+        // whether the next token has to start an operand (number, variable, procedure
+        // call, opening parenthesis or unary minus) rather than be a binary operator
+        let mut expect_operand = true;
+        let mut depth = 0;
 
-        loop {
-            // check that the next token is either a number or an operator
-            match tokens.peek() {
-                Some(tok) => match tok {
-                    Token::Number { literal: _ } => (),
-                    Token::Variable { name: _ } => (),
-                    Token::Operator(_) => (),
-                    Token::LParen | Token::RParen => (),
-                    _ => break,
-                },
-                None => break,
-            }
-
-            if let Some(tok) = tokens.next() {
-                match tok {
-                    Token::Number { literal } => output.push(Parser::parse_number(literal)?),
-                    Token::Variable { name } => output.push(Expression::Variable {
+        while let Some(&tok) = self.tokens.peek() {
+            match tok {
+                Token::Number { literal: _ }
+                | Token::Word { literal: _ }
+                | Token::Variable { name: _ }
+                | Token::Identifier { literal: _ }
+                | Token::LParen
+                | Token::LBracket
+                    if !expect_operand =>
+                {
+                    break
+                }
+                Token::LBracket => {
+                    self.tokens.next();
+                    output.push(Expression::List {
+                        items: self.parse_list()?,
+                    });
+                    expect_operand = false;
+                    continue;
+                }
+                Token::Identifier { literal } => {
+                    self.tokens.next();
+                    output.push(Expression::ProcedureCall {
+                        name: literal.to_string(),
+                        args: self.parse_arguments(literal)?,
+                    });
+                    expect_operand = false;
+                    continue;
+                }
+                Token::LParen => {
+                    // `(name ...)` is a parenthesized procedure call taking any number
+                    // of arguments rather than a grouping of arithmetic
+                    let mut lookahead = self.tokens.clone();
+                    lookahead.next();
+                    if let Some(Token::Identifier { literal }) = lookahead.next() {
+                        self.tokens.next();
+                        self.tokens.next();
+                        output.push(Expression::ProcedureCall {
+                            name: literal.to_string(),
+                            args: self.parse_parenthesized_arguments()?,
+                        });
+                        expect_operand = false;
+                        continue;
+                    }
+                    operator_stack.push(Token::LParen);
+                    depth += 1;
+                }
+                Token::Number { literal } => {
+                    output.push(Parser::parse_number(literal)?);
+                    expect_operand = false;
+                }
+                Token::Word { literal } => {
+                    output.push(Expression::Word {
+                        literal: literal.to_string(),
+                    });
+                    expect_operand = false;
+                }
+                Token::Variable { name } => {
+                    output.push(Expression::Variable {
                         name: name.to_string(),
-                    }),
-                    Token::Operator(op) => {
-                        while !operator_stack.is_empty()
-                            && op.precedence()
-                                <= match &operator_stack[operator_stack.len() - 1] {
-                                    Token::Operator(op) => op.precedence(),
-                                    _ => 0,
-                                }
-                        {
-                            if let Some(popped) = operator_stack.pop() {
-                                match popped {
-                                    Token::Operator(op) => output.push(Expression::Operator { op }),
-                                    _ => (),
-                                }
-                            }
+                    });
+                    expect_operand = false;
+                }
+                Token::RParen => {
+                    if depth == 0 {
+                        break;
+                    }
+                    if expect_operand {
+                        return Err(ParseError::UnexpectedToken(
+                            Token::RParen,
+                            Parser::expected_operand(),
+                        ));
+                    }
+                    loop {
+                        match operator_stack.pop() {
+                            Some(Token::LParen) => break,
+                            Some(Token::Operator(op)) => output.push(Expression::Operator { op }),
+                            _ => return Err(ParseError::UnbalancedParens),
                         }
-                        operator_stack.push(tok.clone());
                     }
-
-                    Token::LParen => operator_stack.push(Token::LParen),
-
-                    Token::RParen => loop {
-                        if operator_stack.is_empty() {
-                            return Err(ParseError::UnbalancedParens);
+                    depth -= 1;
+                }
+                Token::Operator(op) => {
+                    if expect_operand {
+                        // a minus in operand position always negates, whatever the spacing
+                        match op {
+                            Operator::Subtraction | Operator::Negation => {
+                                operator_stack.push(Token::Operator(Operator::Negation))
+                            }
+                            _ => {
+                                return Err(ParseError::UnexpectedToken(
+                                    tok.clone(),
+                                    Parser::expected_operand(),
+                                ))
+                            }
                         }
-
-                        match operator_stack[operator_stack.len() - 1] {
-                            Token::LParen => {
-                                operator_stack.pop();
+                    } else if *op == Operator::Negation {
+                        // `fd 10 -:size` is two separate expressions
+                        break;
+                    } else {
+                        while let Some(Token::Operator(top)) = operator_stack.last() {
+                            if top.precedence() < op.precedence() {
                                 break;
                             }
-                            _ => match operator_stack.pop() {
-                                // can't use parse_expression to cover all options here because the
-                                // operator is a special case
-                                Some(tok) => output.push(match tok {
-                                    Token::Number { literal } => Parser::parse_number(&literal)?,
-
-                                    Token::Variable { name } => Expression::Variable {
-                                        name: name.to_string(),
-                                    },
-
-                                    Token::Operator(op) => Expression::Operator { op },
-
-                                    _ => {
-                                        return Err(ParseError::TypeMismatch {
-                                            expected: "Number, Variable, Operator".to_string(),
-                                        })
-                                    }
-                                }),
-                                _ => (),
-                            },
+                            output.push(Expression::Operator { op: top.clone() });
+                            operator_stack.pop();
                         }
-                    },
-                    _ => (),
+                        operator_stack.push(Token::Operator(op.clone()));
+                        expect_operand = true;
+                    }
                 }
+                _ => break,
             }
+            self.tokens.next();
         }
 
-        while !operator_stack.is_empty() {
-            if let Some(popped) = operator_stack.pop() {
-                match popped {
-                    Token::Operator(op) => output.push(Expression::Operator { op }),
-                    Token::LParen | Token::RParen => return Err(ParseError::UnbalancedParens),
-                    _ => (),
-                }
+        if expect_operand {
+            return match self.tokens.peek() {
+                Some(tok) => Err(ParseError::UnexpectedToken(
+                    (*tok).clone(),
+                    Parser::expected_operand(),
+                )),
+                None => Err(ParseError::EOF),
+            };
+        }
+
+        while let Some(popped) = operator_stack.pop() {
+            match popped {
+                Token::Operator(op) => output.push(Expression::Operator { op }),
+                _ => return Err(ParseError::UnbalancedParens),
+            }
+        }
+
+        if output.len() == 1 {
+            if let Some(expr @ Expression::Number { val: _ })
+            | Some(expr @ Expression::Word { literal: _ })
+            | Some(expr @ Expression::Variable { name: _ })
+            | Some(expr @ Expression::ProcedureCall { name: _, args: _ })
+            | Some(expr @ Expression::List { items: _ }) = output.pop()
+            {
+                return Ok(expr);
             }
         }
 
         Ok(Expression::ArithmeticExpression { postfix: output })
     }
 
+    /// Parses the items of a list literal such as `[fd 10 [a b]]` up to and
+    /// including the closing bracket, which must already have been opened. The
+    /// items are not evaluated: names, quoted words, variables and operators are
+    /// kept as words written as in the source, so that the list can be run as code
+    /// later on. In UCBLogo, tokens written without whitespace between them are a
+    /// single word, as in `[a+b]`.
+    fn parse_list(&mut self) -> Result<Vec<Value>, ParseError> {
+        let mut items = Vec::new();
+        // the last item as written, while the next token may be part of it
+        let mut written: Option<String> = None;
+        loop {
+            let position = self.position();
+            let tok = match self.tokens.next() {
+                Some(tok) => tok,
+                None => return Err(ParseError::EOF),
+            };
+
+            let brackets = [Token::LBracket, Token::RBracket];
+            let joined = self.naming.dialect.has_list_words()
+                && self.adjacent.get(position) == Some(&true)
+                && !brackets.contains(tok);
+            if let (true, Some(word)) = (joined, written.as_mut()) {
+                word.push_str(&list_word(tok));
+                items.pop();
+                items.push(Value::Word(word.clone()));
+                continue;
+            }
+            let item = match tok {
+                Token::RBracket => return Ok(items),
+                Token::LBracket => Value::List(self.parse_list()?),
+                Token::Number { literal } => match literal.parse() {
+                    Ok(n) => Value::Number(n),
+                    Err(_) => return Err(ParseError::ParseInteger(literal.to_string())),
+                },
+                Token::Word { literal } => Value::Word(format!("\"{}", literal)),
+                Token::Variable { name } => Value::Word(format!(":{}", name)),
+                // a negation is attached to its operand, as `-:size` is not `- :size`
+                Token::Operator(Operator::Negation) => match self.tokens.peek() {
+                    Some(Token::Variable { name }) => {
+                        self.tokens.next();
+                        Value::Word(format!("-:{}", name))
+                    }
+                    Some(Token::Identifier { literal }) => {
+                        self.tokens.next();
+                        Value::Word(format!("-{}", literal))
+                    }
+                    _ => Value::Word("-".to_string()),
+                },
+                tok => Value::Word(tok.value().unwrap_or_else(|| tok.to_string()).to_string()),
+            };
+            written = match &item {
+                Value::List(_) => None,
+                Value::Word(word) => Some(word.clone()),
+                Value::Number(_) => Some(list_word(tok)),
+            };
+            items.push(item);
+        }
+    }
+
+    fn expected_operand() -> Vec<Token> {
+        vec![
+            Token::Number {
+                literal: "".to_string(),
+            },
+            Token::Variable {
+                name: "".to_string(),
+            },
+            Token::Identifier {
+                literal: "".to_string(),
+            },
+            Token::LParen,
+        ]
+    }
+
     fn parse_number(literal: &str) -> Result<Expression, ParseError> {
         match literal.parse() {
             Ok(n) => Ok(Expression::Number { val: n }),
@@ -318,36 +923,29 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_expression(&mut self) -> Result<Expression, ParseError> {
-        let mut expr = match self.tokens.next() {
-            Some(tok) => match tok {
-                Token::LParen => Parser::parse_arithmetic_expression(&mut self.tokens, None),
-                Token::Number { literal } => Parser::parse_number(literal),
-                Token::Variable { name } => Ok(Expression::Variable {
-                    name: name.to_string(),
-                }),
-                _ => Err(ParseError::UnexpectedToken(
-                    tok.clone(),
-                    vec![
-                        Token::Number {
-                            literal: "".to_string(),
-                        },
-                        Token::Variable {
-                            name: "".to_string(),
-                        },
-                    ],
-                )),
-            },
+        match self.tokens.peek() {
+            Some(Token::Number { literal: _ })
+            | Some(Token::Word { literal: _ })
+            | Some(Token::Variable { name: _ })
+            | Some(Token::Identifier { literal: _ })
+            | Some(Token::LParen)
+            | Some(Token::LBracket)
+            | Some(Token::Operator(_)) => self.parse_arithmetic_expression(),
+            Some(tok) => Err(ParseError::UnexpectedToken(
+                (*tok).clone(),
+                Parser::expected_operand(),
+            )),
             None => Err(ParseError::EOF),
-        }?;
-
-        // look ahead one token to check for an operator
-        if let Some(tok) = self.tokens.peek() {
-            if let Token::Operator(_) = tok {
-                expr = Parser::parse_arithmetic_expression(&mut self.tokens, Some(expr))?;
-            }
         }
+    }
+}
 
-        Ok(expr)
+// a token inside a list literal as it is written in the source
+fn list_word(tok: &Token) -> String {
+    match tok {
+        Token::Word { literal } => format!("\"{}", literal),
+        Token::Variable { name } => format!(":{}", name),
+        tok => tok.value().unwrap_or_else(|| tok.to_string()).to_string(),
     }
 }
 
@@ -371,12 +969,10 @@ mod tests {
                     literal: String::from("70"),
                 },
             ],
-            AST {
-                statements: vec![Statement::ProcedureCall {
-                    name: "forward".to_string(),
-                    args: vec![Expression::Number { val: 70 }],
-                }],
-            },
+            AST::from(vec![Statement::ProcedureCall {
+                name: "forward".to_string(),
+                args: vec![Expression::Number { val: 70 }],
+            }]),
         );
     }
 
@@ -394,15 +990,13 @@ mod tests {
                     literal: String::from("60"),
                 },
             ],
-            AST {
-                statements: vec![Statement::ProcedureCall {
-                    name: "setxy".to_string(),
-                    args: vec![
-                        Expression::Number { val: -60 },
-                        Expression::Number { val: 60 },
-                    ],
-                }],
-            },
+            AST::from(vec![Statement::ProcedureCall {
+                name: "setxy".to_string(),
+                args: vec![
+                    Expression::Number { val: -60 },
+                    Expression::Number { val: 60 },
+                ],
+            }]),
         );
     }
 
@@ -420,19 +1014,17 @@ mod tests {
                     name: String::from("Y"),
                 },
             ],
-            AST {
-                statements: vec![Statement::ProcedureCall {
-                    name: "setxy".to_string(),
-                    args: vec![
-                        Expression::Variable {
-                            name: String::from("x"),
-                        },
-                        Expression::Variable {
-                            name: String::from("Y"),
-                        },
-                    ],
-                }],
-            },
+            AST::from(vec![Statement::ProcedureCall {
+                name: "setxy".to_string(),
+                args: vec![
+                    Expression::Variable {
+                        name: String::from("x"),
+                    },
+                    Expression::Variable {
+                        name: String::from("Y"),
+                    },
+                ],
+            }]),
         );
     }
 
@@ -454,17 +1046,13 @@ mod tests {
                 },
                 Token::RBracket,
             ],
-            AST {
-                statements: vec![Statement::Repeat {
-                    count: Expression::Number { val: 10 },
-                    body: AST {
-                        statements: vec![Statement::ProcedureCall {
-                            name: "forward".to_string(),
-                            args: vec![Expression::Number { val: 50 }],
-                        }],
-                    },
-                }],
-            },
+            AST::from(vec![Statement::Repeat {
+                count: Expression::Number { val: 10 },
+                body: AST::from(vec![Statement::ProcedureCall {
+                    name: "forward".to_string(),
+                    args: vec![Expression::Number { val: 50 }],
+                }]),
+            }]),
         );
     }
 
@@ -498,35 +1086,29 @@ mod tests {
                 Token::RBracket,
                 Token::RBracket,
             ],
-            AST {
-                statements: vec![Statement::Repeat {
-                    count: Expression::Number { val: 10 },
-                    body: AST {
-                        statements: vec![
-                            Statement::ProcedureCall {
-                                name: "forward".to_string(),
-                                args: vec![Expression::Number { val: 50 }],
-                            },
-                            Statement::Repeat {
-                                count: Expression::Number { val: 45 },
-                                body: AST {
-                                    statements: vec![Statement::ProcedureCall {
-                                        name: "right".to_string(),
-                                        args: vec![Expression::Number { val: 1 }],
-                                    }],
-                                },
-                            },
-                        ],
+            AST::from(vec![Statement::Repeat {
+                count: Expression::Number { val: 10 },
+                body: AST::from(vec![
+                    Statement::ProcedureCall {
+                        name: "forward".to_string(),
+                        args: vec![Expression::Number { val: 50 }],
                     },
-                }],
-            },
+                    Statement::Repeat {
+                        count: Expression::Number { val: 45 },
+                        body: AST::from(vec![Statement::ProcedureCall {
+                            name: "right".to_string(),
+                            args: vec![Expression::Number { val: 1 }],
+                        }]),
+                    },
+                ]),
+            }]),
         );
     }
 
     #[test]
     fn parse_arithmetic_expression_test() {
         // 10 + 7 * 8 - 2
-        let input = vec![
+        let input = [
             Token::Number {
                 literal: "10".to_string(),
             },
@@ -545,7 +1127,7 @@ mod tests {
         ];
 
         assert_eq!(
-            Parser::parse_arithmetic_expression(&mut input.iter().peekable(), None,).unwrap(),
+            Parser::new(&input).parse_arithmetic_expression().unwrap(),
             Expression::ArithmeticExpression {
                 //postfix: "10 7 8 * + 2 -".to_string()
                 postfix: vec![
@@ -567,7 +1149,7 @@ mod tests {
         );
 
         // :size + :count * :length
-        let input = vec![
+        let input = [
             Token::Variable {
                 name: "size".to_string(),
             },
@@ -582,7 +1164,7 @@ mod tests {
         ];
 
         assert_eq!(
-            Parser::parse_arithmetic_expression(&mut input.iter().peekable(), None,).unwrap(),
+            Parser::new(&input).parse_arithmetic_expression().unwrap(),
             Expression::ArithmeticExpression {
                 // :size :count :length * +
                 postfix: vec![
@@ -641,7 +1223,7 @@ mod tests {
 
         // expect: 2 7 + 5 3 1 / * *
         assert_eq!(
-            Parser::parse_arithmetic_expression(&mut input.iter().peekable(), None).unwrap(),
+            Parser::new(&input).parse_arithmetic_expression().unwrap(),
             Expression::ArithmeticExpression {
                 postfix: vec![
                     Expression::Number { val: 2 },
@@ -702,29 +1284,26 @@ mod tests {
                 Token::RBracket,
                 Token::End,
             ],
-            AST {
-                statements: vec![Statement::ProcedureDeclaration {
-                    name: "my_procedure".to_string(),
-                    body: AST {
-                        statements: vec![
-                            Statement::ProcedureCall {
-                                name: "forward".to_string(),
-                                args: vec![Expression::Number { val: 100 }],
-                            },
-                            Statement::Repeat {
-                                count: Expression::Number { val: 10 },
-                                body: AST {
-                                    statements: vec![Statement::ProcedureCall {
-                                        name: "right".to_string(),
-                                        args: vec![Expression::Number { val: 45 }],
-                                    }],
-                                },
-                            },
-                        ],
+            AST::from(vec![Statement::ProcedureDeclaration {
+                name: "my_procedure".to_string(),
+                body: AST::from(vec![
+                    Statement::ProcedureCall {
+                        name: "forward".to_string(),
+                        args: vec![Expression::Number { val: 100 }],
                     },
-                    params: Vec::new(),
-                }],
-            },
+                    Statement::Repeat {
+                        count: Expression::Number { val: 10 },
+                        body: AST::from(vec![Statement::ProcedureCall {
+                            name: "right".to_string(),
+                            args: vec![Expression::Number { val: 45 }],
+                        }]),
+                    },
+                ]),
+                params: Vec::new(),
+                optional: vec![],
+                rest: None,
+                default_arity: None,
+            }]),
         );
     }
 
@@ -751,20 +1330,593 @@ mod tests {
                 },
                 Token::End,
             ],
-            AST {
-                statements: vec![Statement::ProcedureDeclaration {
-                    name: "show_me".to_string(),
-                    body: AST {
-                        statements: vec![Statement::ProcedureCall {
-                            name: "show".to_string(),
-                            args: vec![Expression::Variable {
-                                name: "x".to_string(),
-                            }],
-                        }],
+            AST::from(vec![Statement::ProcedureDeclaration {
+                name: "show_me".to_string(),
+                body: AST::from(vec![Statement::ProcedureCall {
+                    name: "show".to_string(),
+                    args: vec![Expression::Variable {
+                        name: "x".to_string(),
+                    }],
+                }]),
+                params: vec!["x".to_string()],
+                optional: vec![],
+                rest: None,
+                default_arity: None,
+            }]),
+        );
+    }
+
+    #[test]
+    fn parse_optional_inputs_test() {
+        let source = "to poly :sides [:size :sides * 10] [:rest] fd :size end poly 5 (poly 5 6 7)";
+        let tokens = crate::lexer::Lexer::new(source).collect_tokens().unwrap();
+        let ast = Parser::new(&tokens).build_ast().unwrap();
+        match &ast.statements[0] {
+            Statement::ProcedureDeclaration {
+                params,
+                optional,
+                rest,
+                default_arity,
+                ..
+            } => {
+                assert_eq!(params, &vec!["sides".to_string()]);
+                assert_eq!(optional.len(), 1);
+                assert_eq!(optional[0].0, "size");
+                assert!(matches!(
+                    optional[0].1,
+                    Expression::ArithmeticExpression { .. }
+                ));
+                assert_eq!((rest.as_deref(), *default_arity), (Some("rest"), None));
+            }
+            other => panic!("expected ProcedureDeclaration, got {:?}", other),
+        }
+        // unparenthesized calls take the required inputs, parenthesized calls any
+        // number of inputs
+        let args: Vec<_> = ast.statements[1..]
+            .iter()
+            .map(|stmt| match stmt {
+                Statement::ProcedureCall { args, .. } => args.len(),
+                other => panic!("expected ProcedureCall, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(args, vec![1, 3]);
+
+        // a number ending the title line is the number of inputs of unparenthesized
+        // calls, which is known before the declaration
+        let source = "between 1 5 fd 10 to between :low [:high 10] [:step 1] 2 end";
+        let tokens = crate::lexer::Lexer::new(source).collect_tokens().unwrap();
+        let ast = Parser::new(&tokens).build_ast().unwrap();
+        assert_eq!(ast.statements.len(), 3);
+        assert!(matches!(
+            &ast.statements[2],
+            Statement::ProcedureDeclaration {
+                default_arity: Some(2),
+                ..
+            }
+        ));
+
+        for source in ["to f :a [:b 1] 0 end", "to f :a [:b 1] 3 end"].iter() {
+            let tokens = crate::lexer::Lexer::new(source).collect_tokens().unwrap();
+            match Parser::new(&tokens).build_ast() {
+                Err(ParseError::InvalidDefaultArity { name, .. }) => assert_eq!(name, "f"),
+                other => panic!("expected InvalidDefaultArity, got {:?}", other),
+            }
+        }
+    }
+
+    // lexes and parses `source`, expecting a single call to `name` with `args`
+    fn parse_call_source_test(source: &str, name: &str, args: Vec<Expression>) {
+        let tokens = crate::lexer::Lexer::new(source).collect_tokens().unwrap();
+        parse_test(
+            tokens,
+            AST::from(vec![Statement::ProcedureCall {
+                name: name.to_string(),
+                args,
+            }]),
+        );
+    }
+
+    #[test]
+    fn parse_unary_minus_test() {
+        use Expression::{ArithmeticExpression, Number, Variable};
+        let op = |op| Expression::Operator { op };
+        let size = || Variable {
+            name: "size".to_string(),
+        };
+
+        parse_call_source_test("fd -5", "fd", vec![Number { val: -5 }]);
+        parse_call_source_test(
+            "fd -:size",
+            "fd",
+            vec![ArithmeticExpression {
+                postfix: vec![size(), op(Operator::Negation)],
+            }],
+        );
+        parse_call_source_test(
+            "fd -(3 + 4)",
+            "fd",
+            vec![ArithmeticExpression {
+                postfix: vec![
+                    Number { val: 3 },
+                    Number { val: 4 },
+                    op(Operator::Addition),
+                    op(Operator::Negation),
+                ],
+            }],
+        );
+        parse_call_source_test(
+            "fd - 5",
+            "fd",
+            vec![ArithmeticExpression {
+                postfix: vec![Number { val: 5 }, op(Operator::Negation)],
+            }],
+        );
+        parse_call_source_test(
+            "fd 3 * -:size",
+            "fd",
+            vec![ArithmeticExpression {
+                postfix: vec![
+                    Number { val: 3 },
+                    size(),
+                    op(Operator::Negation),
+                    op(Operator::Multiplication),
+                ],
+            }],
+        );
+        parse_call_source_test(
+            "fd --5",
+            "fd",
+            vec![ArithmeticExpression {
+                postfix: vec![Number { val: -5 }, op(Operator::Negation)],
+            }],
+        );
+    }
+
+    #[test]
+    fn parse_minus_argument_splitting_test() {
+        use Expression::{ArithmeticExpression, Number, Variable};
+        let op = |op| Expression::Operator { op };
+        let difference = || ArithmeticExpression {
+            postfix: vec![
+                Number { val: 10 },
+                Number { val: 5 },
+                op(Operator::Subtraction),
+            ],
+        };
+
+        // whitespace on both sides or neither side of a minus means subtraction
+        parse_call_source_test("fd 10 - 5", "fd", vec![difference()]);
+        parse_call_source_test("fd 10-5", "fd", vec![difference()]);
+        parse_call_source_test("fd 10- 5", "fd", vec![difference()]);
+
+        // a minus attached only to the following operand starts a new argument
+        parse_call_source_test(
+            "setxy 10 -5",
+            "setxy",
+            vec![Number { val: 10 }, Number { val: -5 }],
+        );
+        parse_call_source_test(
+            "setxy -10 -:size",
+            "setxy",
+            vec![
+                Number { val: -10 },
+                ArithmeticExpression {
+                    postfix: vec![
+                        Variable {
+                            name: "size".to_string(),
+                        },
+                        op(Operator::Negation),
+                    ],
+                },
+            ],
+        );
+        parse_call_source_test(
+            "setxy (1 + 2) -(3)",
+            "setxy",
+            vec![
+                ArithmeticExpression {
+                    postfix: vec![Number { val: 1 }, Number { val: 2 }, op(Operator::Addition)],
+                },
+                ArithmeticExpression {
+                    postfix: vec![Number { val: 3 }, op(Operator::Negation)],
+                },
+            ],
+        );
+
+        // a parenthesized expression may be followed by more arithmetic
+        parse_call_source_test(
+            "fd (2 + 3) * 4",
+            "fd",
+            vec![ArithmeticExpression {
+                postfix: vec![
+                    Number { val: 2 },
+                    Number { val: 3 },
+                    op(Operator::Addition),
+                    Number { val: 4 },
+                    op(Operator::Multiplication),
+                ],
+            }],
+        );
+    }
+
+    #[test]
+    fn parse_incomplete_expression_test() {
+        for source in ["fd 10 +", "fd (3 + 4", "fd * 3", "fd (3 +) 4"].iter() {
+            let tokens = crate::lexer::Lexer::new(source).collect_tokens().unwrap();
+            assert!(Parser::new(&tokens).build_ast().is_err(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn parse_nested_call_test() {
+        use Expression::{Number, ProcedureCall};
+        let tokens = crate::lexer::Lexer::new("fd sum 1 product 2 3 rt 90")
+            .collect_tokens()
+            .unwrap();
+        parse_test(
+            tokens,
+            AST::from(vec![
+                Statement::ProcedureCall {
+                    name: "fd".to_string(),
+                    args: vec![ProcedureCall {
+                        name: "sum".to_string(),
+                        args: vec![
+                            Number { val: 1 },
+                            ProcedureCall {
+                                name: "product".to_string(),
+                                args: vec![Number { val: 2 }, Number { val: 3 }],
+                            },
+                        ],
+                    }],
+                },
+                Statement::ProcedureCall {
+                    name: "rt".to_string(),
+                    args: vec![Number { val: 90 }],
+                },
+            ]),
+        );
+    }
+
+    #[test]
+    fn parse_reporter_in_arithmetic_test() {
+        use Expression::{ArithmeticExpression, Number, ProcedureCall};
+        // the last argument of sum extends to the end of the arithmetic expression
+        parse_call_source_test(
+            "fd 2 * sum 1 2 + 3",
+            "fd",
+            vec![ArithmeticExpression {
+                postfix: vec![
+                    Number { val: 2 },
+                    ProcedureCall {
+                        name: "sum".to_string(),
+                        args: vec![
+                            Number { val: 1 },
+                            ArithmeticExpression {
+                                postfix: vec![
+                                    Number { val: 2 },
+                                    Number { val: 3 },
+                                    Expression::Operator {
+                                        op: Operator::Addition,
+                                    },
+                                ],
+                            },
+                        ],
                     },
-                    params: vec!["x".to_string()],
+                    Expression::Operator {
+                        op: Operator::Multiplication,
+                    },
+                ],
+            }],
+        );
+    }
+
+    #[test]
+    fn parse_parenthesized_call_test() {
+        use Expression::{Number, ProcedureCall};
+        let tokens = crate::lexer::Lexer::new("fd (sum 1 2 3) (setxy 4 5)")
+            .collect_tokens()
+            .unwrap();
+        parse_test(
+            tokens,
+            AST::from(vec![
+                Statement::ProcedureCall {
+                    name: "fd".to_string(),
+                    args: vec![ProcedureCall {
+                        name: "sum".to_string(),
+                        args: vec![Number { val: 1 }, Number { val: 2 }, Number { val: 3 }],
+                    }],
+                },
+                Statement::ProcedureCall {
+                    name: "setxy".to_string(),
+                    args: vec![Number { val: 4 }, Number { val: 5 }],
+                },
+            ]),
+        );
+    }
+
+    #[test]
+    fn parse_user_procedure_arity_test() {
+        use Expression::{Number, ProcedureCall};
+        // `double` is called before its declaration, and `triangle` is known from an
+        // earlier program
+        let tokens =
+            crate::lexer::Lexer::new("triangle double 5 fd 10 to double :n output :n * 2 end")
+                .collect_tokens()
+                .unwrap();
+        let mut arities = HashMap::new();
+        arities.insert("triangle".to_string(), 1);
+        let ast = Parser::with_arities(&tokens, arities).build_ast().unwrap();
+
+        assert_eq!(
+            ast.statements[0],
+            Statement::ProcedureCall {
+                name: "triangle".to_string(),
+                args: vec![ProcedureCall {
+                    name: "double".to_string(),
+                    args: vec![Number { val: 5 }],
                 }],
-            },
+            }
+        );
+        assert_eq!(ast.statements.len(), 3);
+    }
+
+    #[test]
+    fn parse_not_enough_inputs_test() {
+        for source in ["fd", "setxy 10", "repeat 2 [ fd ]", "fd sum 1"].iter() {
+            let tokens = crate::lexer::Lexer::new(source).collect_tokens().unwrap();
+            match Parser::new(&tokens).build_ast() {
+                Err(ParseError::NotEnoughInputs { name: _ }) => (),
+                other => panic!("{}: expected NotEnoughInputs, got {:?}", source, other),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_if_test() {
+        use Expression::{ArithmeticExpression, Number, Operator as Op, Variable};
+
+        let tokens = crate::lexer::Lexer::new("if :n < 1 + 1 [stop] ifelse :n = 0 [fd 1] []")
+            .collect_tokens()
+            .unwrap();
+        let ast = Parser::new(&tokens).build_ast().unwrap();
+        let n = || Variable {
+            name: "n".to_string(),
+        };
+
+        assert_eq!(
+            ast.statements,
+            vec![
+                Statement::If {
+                    condition: ArithmeticExpression {
+                        postfix: vec![
+                            n(),
+                            Number { val: 1 },
+                            Number { val: 1 },
+                            Op {
+                                op: Operator::Addition
+                            },
+                            Op {
+                                op: Operator::LessThan
+                            },
+                        ],
+                    },
+                    body: AST::from(vec![Statement::Stop]),
+                    else_body: None,
+                },
+                Statement::If {
+                    condition: ArithmeticExpression {
+                        postfix: vec![
+                            n(),
+                            Number { val: 0 },
+                            Op {
+                                op: Operator::Equal
+                            },
+                        ],
+                    },
+                    body: AST::from(vec![Statement::ProcedureCall {
+                        name: "fd".to_string(),
+                        args: vec![Number { val: 1 }],
+                    }]),
+                    else_body: Some(AST::new()),
+                },
+            ]
+        );
+
+        let tokens = crate::lexer::Lexer::new("ifelse 1 [fd 1]")
+            .collect_tokens()
+            .unwrap();
+        match Parser::new(&tokens).build_ast() {
+            Err(ParseError::EOF) => (),
+            other => panic!("expected EOF, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_list_test() {
+        let word = |w: &str| Value::Word(w.to_string());
+        parse_call_source_test(
+            "show [fd -:size * 2 [rt \"a] -5] + 1",
+            "show",
+            vec![Expression::ArithmeticExpression {
+                postfix: vec![
+                    Expression::List {
+                        items: vec![
+                            word("fd"),
+                            word("-:size"),
+                            word("*"),
+                            Value::Number(2),
+                            Value::List(vec![word("rt"), word("\"a")]),
+                            Value::Number(-5),
+                        ],
+                    },
+                    Expression::Number { val: 1 },
+                    Expression::Operator {
+                        op: Operator::Addition,
+                    },
+                ],
+            }],
+        );
+
+        let tokens = crate::lexer::Lexer::new("show [a [b]")
+            .collect_tokens()
+            .unwrap();
+        match Parser::new(&tokens).build_ast() {
+            Err(ParseError::EOF) => (),
+            other => panic!("expected EOF, got {:?}", other),
+        }
+
+        // in UCBLogo only spaces and brackets separate the words of a list
+        let naming = Naming {
+            dialect: crate::dialect::Dialect::UCBLogo,
+            ..Naming::default()
+        };
+        let mut lexer = crate::lexer::Lexer::new("show [a+b, -:x*2 [c] \"d]").naming(naming);
+        let tokens = lexer.collect_tokens().unwrap();
+        let ast = Parser::new(&tokens)
+            .with_spacing(lexer.spans())
+            .naming(naming)
+            .build_ast()
+            .unwrap();
+        let items = vec![
+            word("a+b,"),
+            word("-:x*2"),
+            Value::List(vec![word("c")]),
+            word("\"d"),
+        ];
+        assert_eq!(
+            ast,
+            AST::from(vec![Statement::ProcedureCall {
+                name: "show".to_string(),
+                args: vec![Expression::List { items }],
+            }])
+        );
+    }
+
+    #[test]
+    fn parse_instruction_list_test() {
+        let parse = |source: &str| {
+            let tokens = crate::lexer::Lexer::new(source).collect_tokens().unwrap();
+            Parser::new(&tokens).build_instruction_list()
+        };
+
+        let (ast, result) = parse("fd 10 sum 1 2").unwrap();
+        assert_eq!(ast.statements.len(), 1);
+        assert_eq!(
+            result,
+            Some(Expression::ProcedureCall {
+                name: "sum".to_string(),
+                args: vec![Expression::Number { val: 1 }, Expression::Number { val: 2 }],
+            })
+        );
+
+        let (ast, result) = parse("rt 90 :x").unwrap();
+        assert_eq!(ast.statements.len(), 1);
+        assert_eq!(
+            result,
+            Some(Expression::Variable {
+                name: "x".to_string()
+            })
+        );
+
+        let (ast, result) = parse("repeat 2 [fd 1]").unwrap();
+        assert_eq!((ast.statements.len(), result), (1, None));
+
+        match parse("1 fd 2") {
+            Err(ParseError::UnexpectedToken(_, _)) => (),
+            other => panic!("expected UnexpectedToken, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_span_test() {
+        let source = "fd 10\nrepeat 2 [\n  rt 90 fd sum 1 2\n]\nmake \"x ]";
+        let mut lexer = crate::lexer::Lexer::new(source);
+        let tokens = lexer.collect_tokens().unwrap();
+
+        // statements are located, including the statements of a block
+        let mut parser = Parser::new(&tokens[..12]).with_spans(lexer.spans());
+        let ast = parser.build_ast().unwrap();
+        let text: Vec<_> = ast.spans.iter().map(|s| &source[s.start..s.end]).collect();
+        assert_eq!(text, vec!["fd 10", "repeat 2 [\n  rt 90 fd sum 1 2\n]"]);
+        match &ast.statements[1] {
+            Statement::Repeat { count: _, body } => {
+                let lines: Vec<_> = body.spans.iter().map(|s| (s.line, s.column)).collect();
+                assert_eq!(lines, vec![(3, 3), (3, 9)]);
+            }
+            other => panic!("expected Repeat, got {:?}", other),
+        }
+
+        // errors are located at the token the parser stopped at
+        let mut parser = Parser::new(&tokens).with_spans(lexer.spans());
+        match parser.build_ast() {
+            Err(error) => {
+                let span = parser.error_span(&error).unwrap();
+                assert_eq!((span.line, span.column), (5, 9));
+            }
+            other => panic!("expected an error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_recovery_test() {
+        let source = "fd 10 )
+repeat 2 [rt sum 1 ] fd 5
+to 5 :x
+fd :x
+end
+to ok
+make \"y ]
+bk 1
+end
+home";
+        let mut lexer = crate::lexer::Lexer::new(source);
+        let tokens = lexer.collect_tokens().unwrap();
+
+        // every error is reported, and parsing carries on after each of them
+        let mut parser = Parser::new(&tokens).with_spans(lexer.spans());
+        let (ast, errors) = parser.build_ast_recovering();
+        let lines: Vec<_> = errors
+            .iter()
+            .map(|(error, span)| (error.code(), span.unwrap().line))
+            .collect();
+        assert_eq!(lines, vec![(12, 1), (6, 2), (102, 3), (26, 7)]);
+        assert!(matches!(
+            errors[2].0,
+            ParseError::UnexpectedToken(Token::Number { .. }, _)
+        ));
+
+        // the statements around the errors are kept
+        assert_eq!(
+            ast,
+            AST::from(vec![
+                Statement::ProcedureCall {
+                    name: "fd".to_string(),
+                    args: vec![Expression::Number { val: 10 }],
+                },
+                Statement::Repeat {
+                    count: Expression::Number { val: 2 },
+                    body: AST::new(),
+                },
+                Statement::ProcedureCall {
+                    name: "fd".to_string(),
+                    args: vec![Expression::Number { val: 5 }],
+                },
+                Statement::ProcedureDeclaration {
+                    name: "ok".to_string(),
+                    body: AST::from(vec![Statement::ProcedureCall {
+                        name: "bk".to_string(),
+                        args: vec![Expression::Number { val: 1 }],
+                    }]),
+                    params: vec![],
+                    optional: vec![],
+                    rest: None,
+                    default_arity: None,
+                },
+                Statement::ProcedureCall {
+                    name: "home".to_string(),
+                    args: vec![],
+                },
+            ])
         );
     }
 }
@@ -0,0 +1,344 @@
+/// Primitive procedures that are handled by the evaluator itself rather than being
+/// sent to the frontend as an Instruction. Some of them output a value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Primitive {
+    // arithmetic
+    Sum,
+    Difference,
+    Product,
+    Quotient,
+    Remainder,
+    Minus,
+
+    // variables
+    Local,
+    LocalMake,
+    Global,
+    Thing,
+    NameP,
+
+    // printing
+    Print,
+
+    // workspace
+    Erase,
+    ErAll,
+    Po,
+    Pots,
+    Pons,
+    Procedures,
+    Names,
+    Save,
+    Load,
+
+    // modules
+    Import,
+
+    // running lists as code
+    Run,
+    RunResult,
+    Apply,
+    Invoke,
+
+    // lists
+    First,
+    Last,
+    ButFirst,
+    ButLast,
+    Count,
+    Item,
+    FPut,
+    LPut,
+    List,
+    EmptyP,
+
+    // property lists
+    PProp,
+    GProp,
+    RemProp,
+    PList,
+    PLists,
+    Pops,
+    ErPls,
+
+    // errors and debugging
+    Catch,
+    Throw,
+    Error,
+    Pause,
+    Continue,
+    Trace,
+    Untrace,
+    Step,
+    Unstep,
+
+    // templates
+    Slot,
+    Map,
+    Filter,
+    Reduce,
+    ForEach,
+    CrossMap,
+}
+
+// every name a primitive can be called by, including abbreviations. Template
+// slots are matched separately. Names in other languages are translated to
+// these, see `Locale`
+const NAMES: &[(&str, Primitive)] = &[
+    ("sum", Primitive::Sum),
+    ("difference", Primitive::Difference),
+    ("product", Primitive::Product),
+    ("quotient", Primitive::Quotient),
+    ("remainder", Primitive::Remainder),
+    ("minus", Primitive::Minus),
+    ("local", Primitive::Local),
+    ("localmake", Primitive::LocalMake),
+    ("global", Primitive::Global),
+    ("thing", Primitive::Thing),
+    ("namep", Primitive::NameP),
+    ("print", Primitive::Print),
+    ("pr", Primitive::Print),
+    ("erase", Primitive::Erase),
+    ("er", Primitive::Erase),
+    ("erall", Primitive::ErAll),
+    ("po", Primitive::Po),
+    ("pots", Primitive::Pots),
+    ("pons", Primitive::Pons),
+    ("procedures", Primitive::Procedures),
+    ("names", Primitive::Names),
+    ("save", Primitive::Save),
+    ("load", Primitive::Load),
+    ("import", Primitive::Import),
+    ("run", Primitive::Run),
+    ("runresult", Primitive::RunResult),
+    ("apply", Primitive::Apply),
+    ("invoke", Primitive::Invoke),
+    ("first", Primitive::First),
+    ("last", Primitive::Last),
+    ("butfirst", Primitive::ButFirst),
+    ("bf", Primitive::ButFirst),
+    ("butlast", Primitive::ButLast),
+    ("bl", Primitive::ButLast),
+    ("count", Primitive::Count),
+    ("item", Primitive::Item),
+    ("fput", Primitive::FPut),
+    ("lput", Primitive::LPut),
+    ("list", Primitive::List),
+    ("emptyp", Primitive::EmptyP),
+    ("pprop", Primitive::PProp),
+    ("gprop", Primitive::GProp),
+    ("remprop", Primitive::RemProp),
+    ("plist", Primitive::PList),
+    ("plists", Primitive::PLists),
+    ("pops", Primitive::Pops),
+    ("erpls", Primitive::ErPls),
+    ("catch", Primitive::Catch),
+    ("throw", Primitive::Throw),
+    ("error", Primitive::Error),
+    ("pause", Primitive::Pause),
+    ("continue", Primitive::Continue),
+    ("co", Primitive::Continue),
+    ("trace", Primitive::Trace),
+    ("untrace", Primitive::Untrace),
+    ("step", Primitive::Step),
+    ("unstep", Primitive::Unstep),
+    ("map", Primitive::Map),
+    ("filter", Primitive::Filter),
+    ("reduce", Primitive::Reduce),
+    ("foreach", Primitive::ForEach),
+    ("crossmap", Primitive::CrossMap),
+];
+
+impl Primitive {
+    pub fn from_string(s: &str) -> Option<Self> {
+        match NAMES.iter().find(|(name, _)| *name == s) {
+            Some((_, primitive)) => Some(*primitive),
+            // `?` is the first input of a template, `?2` the second and so on
+            None if s.starts_with('?') && s[1..].chars().all(|c| c.is_ascii_digit()) => {
+                Some(Primitive::Slot)
+            }
+            None => None,
+        }
+    }
+
+    /// Number of arguments taken when the primitive is called without parentheses.
+    pub fn arity(&self) -> usize {
+        use Primitive::*;
+        match self {
+            Sum | Difference | Product | Quotient | Remainder => 2,
+            Minus => 1,
+            Local | Global | Thing | NameP => 1,
+            LocalMake => 2,
+            Print => 1,
+            Erase | Po | Save | Load => 1,
+            Import => 1,
+            Run | RunResult => 1,
+            Apply | Invoke => 2,
+            First | Last | ButFirst | ButLast | Count | EmptyP => 1,
+            Item | FPut | LPut | List => 2,
+            PProp => 3,
+            GProp | RemProp => 2,
+            PList => 1,
+            PLists | Pops | ErPls => 0,
+            Catch => 2,
+            Throw => 1,
+            Error | Pause | Continue => 0,
+            Trace | Untrace | Step | Unstep => 1,
+            Slot => 0,
+            Map | Filter | Reduce | ForEach | CrossMap => 2,
+            ErAll | Pots | Pons | Procedures | Names => 0,
+        }
+    }
+
+    /// Whether a parenthesized call may pass any number of arguments, as in
+    /// `(sum 1 2 3)` or `(local "x "y)`. `import` takes an optional namespace,
+    /// `invoke` passes its inputs after the first to the procedure it invokes,
+    /// `map` and `crossmap` take several lists, `reduce` an initial value and
+    /// `throw` a value for its catch to output. Procedures that take names, such as
+    /// `erase` and `trace`, take several names, and `print` prints several things.
+    pub fn is_variadic(&self) -> bool {
+        use Primitive::*;
+        match self {
+            Sum | Product | Local | Global => true,
+            Print => true,
+            Erase | Po | Import => true,
+            Difference | Quotient | Remainder | Minus => false,
+            LocalMake | Thing | NameP => false,
+            ErAll | Pots | Pons | Procedures | Names => false,
+            Save | Load => false,
+            Invoke => true,
+            Run | RunResult | Apply => false,
+            List | Map | Reduce | CrossMap => true,
+            First | Last | ButFirst | ButLast | Count | Item | FPut | LPut | EmptyP => false,
+            PProp | GProp | RemProp | PList | PLists | Pops | ErPls => false,
+            Throw => true,
+            Catch | Error | Pause | Continue => false,
+            Trace | Untrace | Step | Unstep => true,
+            Slot | Filter | ForEach => false,
+        }
+    }
+
+    /// Whether the primitive runs instructions or templates it is given, or the
+    /// procedure it is given, rather than working on values alone.
+    pub fn runs_code(&self) -> bool {
+        use Primitive::*;
+        match self {
+            Run | RunResult | Catch | Apply | Invoke => true,
+            Map | Filter | Reduce | ForEach | CrossMap => true,
+            Sum | Difference | Product | Quotient | Remainder | Minus => false,
+            Local | LocalMake | Global | Thing | NameP | Print => false,
+            Erase | ErAll | Po | Pots | Pons | Procedures | Names => false,
+            Save | Load | Import => false,
+            List | First | Last | ButFirst | ButLast | Count | Item | FPut | LPut | EmptyP => false,
+            PProp | GProp | RemProp | PList | PLists | Pops | ErPls => false,
+            Throw | Error | Pause | Continue | Slot => false,
+            Trace | Untrace | Step | Unstep => false,
+        }
+    }
+
+    /// Every name a primitive can be called by, including abbreviations. Template
+    /// slots are listed as `?`.
+    pub fn names() -> Vec<&'static str> {
+        let mut names: Vec<_> = NAMES.iter().map(|(name, _)| *name).collect();
+        names.push("?");
+        names
+    }
+
+    /// Names of the inputs taken by the primitive when called without parentheses,
+    /// as shown in its usage.
+    pub fn inputs(&self) -> &'static [&'static str] {
+        use Primitive::*;
+        match self {
+            Sum | Difference | Product | Quotient | Remainder => &["number1", "number2"],
+            Minus => &["number"],
+            Local | Global | Thing | NameP => &["name"],
+            LocalMake => &["name", "value"],
+            Print => &["thing"],
+            Erase | Po => &["name"],
+            Save | Load => &["file"],
+            Import => &["module"],
+            Run | RunResult => &["list"],
+            Apply => &["procedure", "inputs"],
+            Invoke => &["procedure", "input"],
+            First | Last | ButFirst | ButLast | Count | EmptyP => &["thing"],
+            Item => &["index", "thing"],
+            FPut | LPut => &["thing", "list"],
+            List => &["thing1", "thing2"],
+            PProp => &["plist", "property", "value"],
+            GProp | RemProp => &["plist", "property"],
+            PList => &["plist"],
+            Catch => &["tag", "list"],
+            Throw => &["tag"],
+            Trace | Untrace | Step | Unstep => &["procedure"],
+            Map | Filter | ForEach | CrossMap => &["template", "list"],
+            Reduce => &["template", "list"],
+            PLists | Pops | ErPls | Error | Pause | Continue | Slot => &[],
+            ErAll | Pots | Pons | Procedures | Names => &[],
+        }
+    }
+
+    /// A short description of what the primitive does.
+    pub fn doc(&self) -> &'static str {
+        use Primitive::*;
+        match self {
+            Sum => "Outputs the sum of its inputs.",
+            Difference => "Outputs the first input minus the second.",
+            Product => "Outputs the product of its inputs.",
+            Quotient => "Outputs the first input divided by the second, rounded toward zero.",
+            Remainder => "Outputs the remainder of dividing the first input by the second.",
+            Minus => "Outputs the negative of its input.",
+            Local => "Declares a variable local to the procedure being run.",
+            LocalMake => "Declares a local variable and gives it a value.",
+            Global => "Declares a global variable without a value.",
+            Thing => "Outputs the value of the variable with that name, like `:name`.",
+            NameP => "Outputs true if the variable with that name has a value.",
+            Print => "Prints its inputs, lists without their outer brackets.",
+            Erase => "Removes a procedure.",
+            ErAll => "Removes all procedures, global variables and property lists.",
+            Po => "Prints the definition of a procedure.",
+            Pots => "Prints the title line of every procedure.",
+            Pons => "Prints every global variable.",
+            Procedures => "Outputs a list of the defined procedures.",
+            Names => "Outputs a list of the global variables.",
+            Save => "Writes the workspace to a file as Logo source.",
+            Load => "Runs the Logo program in a file.",
+            Import => "Imports a module from the search path or the bundled libraries.",
+            Run => "Runs a list of instructions, outputting the value of its last expression.",
+            RunResult => "Runs a list, outputting [value] or [] if it doesn't output one.",
+            Apply => "Calls a procedure with the members of a list as inputs.",
+            Invoke => "Calls a procedure with the rest of its own inputs.",
+            First => "Outputs the first member of a list or character of a word.",
+            Last => "Outputs the last member of a list or character of a word.",
+            ButFirst => "Outputs a list or word without its first member.",
+            ButLast => "Outputs a list or word without its last member.",
+            Count => "Outputs the number of members of a list or characters of a word.",
+            Item => "Outputs the member of a list at an index, starting from 1.",
+            FPut => "Outputs the list with the thing added at the front.",
+            LPut => "Outputs the list with the thing added at the end.",
+            List => "Outputs a list of its inputs.",
+            EmptyP => "Outputs true if its input is an empty list or word.",
+            PProp => "Sets a property of a property list.",
+            GProp => "Outputs a property of a property list, or [] if it isn't set.",
+            RemProp => "Removes a property of a property list.",
+            PList => "Outputs a property list as a list of properties and values.",
+            PLists => "Outputs the names of all property lists.",
+            Pops => "Prints every property list.",
+            ErPls => "Removes all property lists.",
+            Catch => "Runs a list, stopping it when the tag is thrown.",
+            Throw => "Stops running the list of the catch with this tag.",
+            Error => "Outputs the last error caught by `catch \"error`, or [].",
+            Pause => "Stops the procedure being run and starts a shell.",
+            Continue => "Carries on running a paused procedure.",
+            Trace => "Prints a line whenever the procedure is called or finishes.",
+            Untrace => "Stops tracing the procedure.",
+            Step => "Prints each statement of the procedure and waits before running it.",
+            Unstep => "Stops stepping through the procedure.",
+            Slot => "Outputs an input of the template being run, `?2` being the second.",
+            Map => "Outputs a list of the results of a template for every member.",
+            Filter => "Outputs the members of a list for which a template outputs true.",
+            Reduce => "Combines the members of a list with a template.",
+            ForEach => "Runs a template for every member of a list.",
+            CrossMap => "Outputs the results of a template for every combination of members.",
+        }
+    }
+}
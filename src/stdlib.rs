@@ -0,0 +1,115 @@
+/// Libraries of procedures written in Logo that are bundled with the interpreter,
+/// as (name, source) pairs. They are imported like any other module, e.g.
+/// `import "shapes`, when no file of the same name is found on the search path.
+const LIBRARIES: [(&str, &str); 2] = [
+    ("shapes", include_str!("stdlib/shapes.lgo")),
+    ("fractals", include_str!("stdlib/fractals.lgo")),
+];
+
+/// Returns the source of the bundled library `name`.
+pub fn source(name: &str) -> Option<&'static str> {
+    LIBRARIES
+        .iter()
+        .find(|(library, _)| *library == name)
+        .map(|(_, source)| *source)
+}
+
+/// Returns the names of all bundled libraries.
+pub fn names() -> Vec<&'static str> {
+    LIBRARIES.iter().map(|(name, _)| *name).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Command, Instruction, Interpreter};
+
+    // imports every bundled library and runs `source`
+    fn run(source: &str) -> Vec<Instruction> {
+        let mut interpreter = Interpreter::new();
+        for name in names() {
+            interpreter.import(name).unwrap();
+        }
+        interpreter.run_program(source).unwrap()
+    }
+
+    // follows the turtle through the movement instructions, returning its final
+    // position and heading and the number of forward moves
+    fn trace(instructions: &[Instruction]) -> ((f64, f64), isize, usize) {
+        let (mut x, mut y, mut heading, mut moves) = (0.0, 0.0, 0isize, 0);
+        for instruction in instructions.iter() {
+            let arg = instruction.args[0].number().unwrap();
+            let mut step = |distance: f64| {
+                let radians = (heading as f64).to_radians();
+                x += distance * radians.sin();
+                y += distance * radians.cos();
+            };
+            match instruction.command {
+                Command::Forward => {
+                    step(arg as f64);
+                    moves += 1;
+                }
+                Command::Backward => step(-arg as f64),
+                Command::Right => heading += arg,
+                Command::Left => heading -= arg,
+                ref command => panic!("unexpected instruction {:?}", command),
+            }
+        }
+        ((x, y), heading.rem_euclid(360), moves)
+    }
+
+    fn assert_closed(source: &str, moves: usize) {
+        let ((x, y), heading, actual_moves) = trace(&run(source));
+        assert!(
+            x.abs() < 1e-6 && y.abs() < 1e-6,
+            "{} ends at ({}, {})",
+            source,
+            x,
+            y
+        );
+        assert_eq!(heading, 0, "{}", source);
+        assert_eq!(actual_moves, moves, "{}", source);
+    }
+
+    #[test]
+    fn stdlib_source_test() {
+        for name in names() {
+            let source = source(name).unwrap();
+            assert_eq!(crate::format_program(source).unwrap(), source, "{}", name);
+        }
+        assert_eq!(source("missing"), None);
+    }
+
+    #[test]
+    fn stdlib_closed_shapes_test() {
+        assert_closed("square 50", 4);
+        assert_closed("triangle 50", 3);
+        assert_closed("ngon 6 30", 6);
+        assert_closed("star 40", 5);
+        assert_closed("rosette 4 20 6", 24);
+        assert_closed("tree 30 3", 7);
+        assert_closed("snowflake 81 2", 48);
+    }
+
+    #[test]
+    fn stdlib_open_shapes_test() {
+        let ((_, _), heading, moves) = trace(&run("circle 50"));
+        assert_eq!((heading, moves), (0, 36));
+        let ((_, _), heading, moves) = trace(&run("arcl 20 90"));
+        assert_eq!((heading, moves), (270, 9));
+
+        let instructions = run("spiral 10 90 5 4");
+        let distances: Vec<_> = instructions
+            .iter()
+            .filter(|instruction| instruction.command == Command::Forward)
+            .map(|instruction| instruction.args[0].number().unwrap())
+            .collect();
+        assert_eq!(distances, vec![10, 15, 20, 25]);
+
+        let ((x, y), _, moves) = trace(&run("koch 27 3"));
+        assert!(x.abs() < 1e-6 && (y - 27.0).abs() < 1e-6);
+        assert_eq!(moves, 64);
+        assert_eq!(trace(&run("dragon 5 4")).2, 16);
+        assert_eq!(trace(&run("hilbert 5 3 1")).2, 63);
+    }
+}
@@ -0,0 +1,58 @@
+use std::fmt;
+
+/// A Logo value, as stored in variables and passed to procedures.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(isize),
+    Word(String),
+    List(Vec<Value>),
+}
+
+impl Value {
+    /// Returns the Logo boolean word for `b`, either "true or "false.
+    pub fn boolean(b: bool) -> Self {
+        Value::Word(if b { "true" } else { "false" }.to_string())
+    }
+
+    /// Returns the value as it would be written in Logo source, e.g. `"word` for a
+    /// word, as opposed to the Display output used by `show`.
+    pub fn source(&self) -> String {
+        match self {
+            Value::Word(w) => format!("\"{}", w),
+            _ => self.to_string(),
+        }
+    }
+
+    pub fn number(&self) -> Option<isize> {
+        match self {
+            Value::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn word(&self) -> Option<&str> {
+        match self {
+            Value::Word(w) => Some(w),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Value::Number(n) => write!(formatter, "{}", n),
+            Value::Word(w) => write!(formatter, "{}", w),
+            Value::List(items) => {
+                formatter.write_str("[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        formatter.write_str(" ")?;
+                    }
+                    write!(formatter, "{}", item)?;
+                }
+                formatter.write_str("]")
+            }
+        }
+    }
+}
@@ -0,0 +1,218 @@
+//! Debugs a program with the `logo-dap` server the way an editor does, speaking
+//! the Debug Adapter Protocol over the server's stdin and stdout.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use serde_json::{json, Value as Json};
+
+// `inner` is called from a template that names its input, which has a local scope
+// between those of `outer` and `inner`
+const PROGRAM: &str = "to outer :a\nforeach [1] [[x] inner :x]\nend\n\
+                       to inner :b\nshow :b\nend\nmake \"n 5\nouter :n\n";
+
+/// Acts as the editor, sending requests to a running server and reading the
+/// responses and events it writes.
+struct Client {
+    server: Child,
+    input: ChildStdin,
+    output: BufReader<ChildStdout>,
+    seq: i64,
+    // events read while waiting for a response
+    events: VecDeque<Json>,
+}
+
+impl Client {
+    fn start() -> Self {
+        let mut server = Command::new(env!("CARGO_BIN_EXE_logo-dap"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let input = server.stdin.take().unwrap();
+        let output = BufReader::new(server.stdout.take().unwrap());
+        Client {
+            server,
+            input,
+            output,
+            seq: 0,
+            events: VecDeque::new(),
+        }
+    }
+
+    fn read(&mut self) -> Json {
+        let mut length = None;
+        loop {
+            let mut line = String::new();
+            assert!(
+                self.output.read_line(&mut line).unwrap() > 0,
+                "the server exited"
+            );
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(value) = line.strip_prefix("Content-Length:") {
+                length = value.trim().parse().ok();
+            }
+        }
+        let mut body = vec![0; length.unwrap()];
+        self.output.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    /// Sends a request and returns the body of its response, which must succeed.
+    fn request(&mut self, command: &str, arguments: Json) -> Json {
+        self.seq += 1;
+        let message = json!({
+            "seq": self.seq,
+            "type": "request",
+            "command": command,
+            "arguments": arguments,
+        });
+        let body = message.to_string();
+        write!(self.input, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        self.input.flush().unwrap();
+
+        loop {
+            let mut message = self.read();
+            if message["type"] == "event" {
+                self.events.push_back(message);
+                continue;
+            }
+            assert_eq!(message["request_seq"], self.seq);
+            assert_eq!(message["success"], true, "{} failed: {}", command, message);
+            return message["body"].take();
+        }
+    }
+
+    /// Returns the body of the next event, which must be `event`.
+    fn event(&mut self, event: &str) -> Json {
+        let mut message = match self.events.pop_front() {
+            Some(message) => message,
+            None => self.read(),
+        };
+        assert_eq!(
+            message["event"], event,
+            "expected {}, got {}",
+            event, message
+        );
+        message["body"].take()
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        let _ = self.server.kill();
+        let _ = self.server.wait();
+    }
+}
+
+#[test]
+fn dap_session_test() {
+    let dir = std::env::temp_dir().join(format!("logo_dap_test_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let program = dir.join("program.lgo");
+    fs::write(&program, PROGRAM).unwrap();
+
+    let mut client = Client::start();
+    let capabilities = client.request("initialize", json!({ "adapterID": "logo" }));
+    assert_eq!(capabilities["supportsConfigurationDoneRequest"], true);
+    client.event("initialized");
+    client.request(
+        "launch",
+        json!({ "program": program, "stopOnEntry": false }),
+    );
+
+    // the editor may spell the path of the program differently
+    let path = dir.join(".").join("program.lgo");
+    let source = json!({ "path": path });
+    let breakpoints = client.request(
+        "setBreakpoints",
+        json!({ "source": source, "breakpoints": [{ "line": 5 }] }),
+    );
+    assert_eq!(
+        breakpoints["breakpoints"],
+        json!([{ "verified": true, "line": 5 }])
+    );
+    let breakpoints = client.request(
+        "setBreakpoints",
+        json!({ "source": { "path": dir.join("other.lgo") }, "breakpoints": [{ "line": 1 }] }),
+    );
+    assert_eq!(breakpoints["breakpoints"][0]["verified"], false);
+    client.request(
+        "setBreakpoints",
+        json!({ "source": source, "breakpoints": [{ "line": 5 }] }),
+    );
+    client.request("configurationDone", json!({}));
+
+    // stopped in inner, called by outer, called from the top level
+    let stopped = client.event("stopped");
+    assert_eq!(stopped["reason"], "breakpoint");
+    let trace = client.request("stackTrace", json!({ "threadId": 1 }));
+    let frames: Vec<_> = trace["stackFrames"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|frame| {
+            (
+                frame["id"].clone(),
+                frame["name"].clone(),
+                frame["line"].clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        frames,
+        vec![
+            (json!(2), json!("inner 1"), json!(5)),
+            (json!(1), json!("outer 5"), json!(2)),
+            (json!(0), json!("top level"), json!(8)),
+        ]
+    );
+
+    // every frame shows the local variables of its own procedure
+    let variables = |client: &mut Client, frame: i64| {
+        let scopes = client.request("scopes", json!({ "frameId": frame }));
+        let scopes = scopes["scopes"].as_array().unwrap().clone();
+        let names: Vec<_> = scopes.iter().map(|scope| scope["name"].clone()).collect();
+        let reference = scopes[0]["variablesReference"].clone();
+        let variables = client.request("variables", json!({ "variablesReference": reference }));
+        let variables: Vec<_> = variables["variables"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|variable| format!("{} = {}", variable["name"], variable["value"]))
+            .collect();
+        (names, variables)
+    };
+    let (locals, globals) = (json!("Locals"), json!("Globals"));
+    assert_eq!(
+        variables(&mut client, 2),
+        (
+            vec![locals.clone(), globals.clone()],
+            vec![r#""b" = "1""#.to_string()]
+        )
+    );
+    assert_eq!(
+        variables(&mut client, 1),
+        (
+            vec![locals, globals.clone()],
+            vec![r#""a" = "5""#.to_string()]
+        )
+    );
+    assert_eq!(
+        variables(&mut client, 0),
+        (vec![globals], vec![r#""n" = "5""#.to_string()])
+    );
+
+    // the program carries on to its end
+    client.request("continue", json!({ "threadId": 1 }));
+    assert_eq!(client.event("output")["output"], "1\n");
+    assert_eq!(client.event("exited")["exitCode"], 0);
+    client.event("terminated");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,82 @@
+//! Runs the programs in `tests/ucblogo`, which are written for Berkeley Logo, with
+//! the UCBLogo dialect. Every `name.lgo` has the output it should print in
+//! `name.out`, and if it moves the turtle the turtle commands it should run, one
+//! per line, in `name.trace`. The expectations were written by hand from the
+//! UCBLogo manual and have not been checked against a UCBLogo run, so this tests
+//! that the dialect keeps reading and running these programs the same way rather
+//! than that it conforms to UCBLogo.
+
+use std::fs;
+use std::path::Path;
+
+use logo::{Command, Dialect, Instruction, Interpreter};
+
+// splits the instructions into the printed output and the turtle commands
+fn output_and_trace(instructions: &[Instruction]) -> (String, String) {
+    let mut output = String::new();
+    let mut trace = String::new();
+    for instruction in instructions.iter() {
+        if instruction.command == Command::Show {
+            output.push_str(&format!("{}\n", instruction.args[0]));
+            continue;
+        }
+
+        // commands are written by their full names, e.g. `setxy 10 -20`
+        trace.push_str(&format!("{:?}", instruction.command).to_lowercase());
+        for arg in instruction.args.iter() {
+            trace.push_str(&format!(" {}", arg));
+        }
+        trace.push('\n');
+    }
+    (output, trace)
+}
+
+#[test]
+fn ucblogo_programs_test() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("ucblogo");
+    let mut programs: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "lgo"))
+        .collect();
+    programs.sort();
+    assert!(!programs.is_empty());
+
+    let mut failures = Vec::new();
+    for program in programs.iter() {
+        let source = fs::read_to_string(program).unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_dialect(Dialect::UCBLogo);
+        let instructions = match interpreter.run_program(&source) {
+            Ok(instructions) => instructions,
+            Err(e) => {
+                failures.push(format!("{}: {}", program.display(), e));
+                continue;
+            }
+        };
+
+        let (output, trace) = output_and_trace(&instructions);
+        let read_expected =
+            |extension| fs::read_to_string(program.with_extension(extension)).unwrap_or_default();
+        let (expected_output, expected_trace) = (read_expected("out"), read_expected("trace"));
+        if output != expected_output {
+            failures.push(format!(
+                "{}: printed\n{}expected\n{}",
+                program.display(),
+                output,
+                expected_output
+            ));
+        }
+        if trace != expected_trace {
+            failures.push(format!(
+                "{}: ran\n{}expected\n{}",
+                program.display(),
+                trace,
+                expected_trace
+            ));
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}